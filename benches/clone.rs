@@ -0,0 +1,80 @@
+//! Cloning snapshots, as watchers and diffs do on every update.
+//!
+//! Also prints the size of a post, which decides the memory of board-wide caches.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use dot4ch::{persist::Persist, post::Post, thread::Thread, Client};
+use serde_json::{json, Value};
+use std::{env, fs, mem};
+
+/// The number of posts in the benchmarked thread, about as many as a thread at bump limit.
+const POSTS: u32 = 310;
+
+/// Returns a post as the API sends it, with a file on every third post.
+fn post(no: u32) -> Value {
+    let mut post = json!({
+        "no": no,
+        "resto": if no == 1 { 0 } else { 1 },
+        "now": "01/01/21(Fri)00:00:00",
+        "time": 1_609_459_200 + i64::from(no),
+        "name": "Anonymous",
+        "com": "<a href=\"#p1\" class=\"quotelink\">&gt;&gt;1</a><br>the quick brown fox jumps over the lazy dog",
+    });
+    if no == 1 {
+        post["sub"] = json!("Benchmark thread");
+        post["replies"] = json!(POSTS - 1);
+        post["semantic_url"] = json!("benchmark-thread");
+    }
+    if no % 3 == 1 {
+        post["tim"] = json!(1_609_459_200_000_u64 + u64::from(no));
+        post["filename"] = json!("image");
+        post["ext"] = json!(".png");
+        post["fsize"] = json!(123_456);
+        post["md5"] = json!("uU3Ggj8DOwE5vt2ZnCjmLw==");
+        post["w"] = json!(1920);
+        post["h"] = json!(1080);
+        post["tn_w"] = json!(250);
+        post["tn_h"] = json!(140);
+    }
+    post
+}
+
+/// Loads a thread from a snapshot written to the temporary directory.
+fn thread() -> Thread {
+    let snapshot = json!({
+        "metadata": {
+            "url": "https://a.4cdn.org/g/thread/1.json",
+            "board": "g",
+            "last_modified": null,
+            "saved_at": "2021-01-01T00:00:00Z",
+        },
+        "data": (1..=POSTS).map(post).collect::<Vec<_>>(),
+    });
+    let path = env::temp_dir().join("dot4ch-bench-thread.json");
+    fs::write(&path, snapshot.to_string()).expect("writing the snapshot");
+    let thread = Thread::load_json(&Client::new(), &path).expect("loading the snapshot");
+    let _ = fs::remove_file(path);
+    thread
+}
+
+fn clone(c: &mut Criterion) {
+    let thread = thread();
+    let reply: Post = serde_json::from_value(post(2)).expect("parsing a reply");
+    let op = thread.op().clone();
+
+    println!("size of Post: {} bytes", mem::size_of::<Post>());
+    println!("size of Thread: {} bytes", mem::size_of::<Thread>());
+
+    c.bench_function("clone a text reply", |b| {
+        b.iter(|| black_box(&reply).clone())
+    });
+    c.bench_function("clone an OP with a file", |b| {
+        b.iter(|| black_box(&op).clone())
+    });
+    c.bench_function("clone a thread of 310 posts", |b| {
+        b.iter(|| black_box(&thread).clone())
+    });
+}
+
+criterion_group!(benches, clone);
+criterion_main!(benches);
@@ -0,0 +1,575 @@
+//! Text and activity analysis over threads and boards.
+//!
+//! # Example
+//!
+//! ```
+//! use dot4ch::analysis::WordCounter;
+//!
+//! let counter = WordCounter::new();
+//! let words = counter.count(vec![
+//!     "Rust is <b>fast</b>",
+//!     "is Rust &quot;fast&quot; enough?<br>yes",
+//! ]);
+//!
+//! assert_eq!(words.get("rust"), 2);
+//! assert_eq!(words.get("is"), 0); // a stopword
+//! assert_eq!(words.top(1), vec![("fast", 2)]);
+//! ```
+
+use crate::{board::Board, post::Post, text::Pipeline, thread::Thread};
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::TryFrom,
+    iter,
+    time::Duration,
+};
+
+/// Common English words which are not counted by default.
+pub const STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "am", "an", "and", "any", "are", "as", "at", "be",
+    "because", "been", "but", "by", "can", "could", "did", "do", "does", "don't", "for", "from",
+    "get", "had", "has", "have", "he", "her", "him", "his", "how", "i", "i'm", "if", "in", "into",
+    "is", "it", "it's", "its", "just", "like", "me", "more", "my", "no", "not", "now", "of", "on",
+    "one", "only", "or", "other", "our", "out", "so", "some", "than", "that", "the", "their",
+    "them", "then", "there", "they", "this", "to", "up", "us", "was", "we", "were", "what", "when",
+    "which", "who", "why", "will", "with", "would", "you", "your",
+];
+
+/// Counts words or phrases in post comments.
+///
+/// Comments are run through a [`Pipeline`] first and split into lowercase words.
+/// Numbers, such as the post numbers of quotelinks, are not counted.
+#[derive(Debug, Clone)]
+pub struct WordCounter {
+    /// Applied to every comment before splitting it
+    pipeline: Pipeline,
+    /// Words which are skipped
+    stopwords: HashSet<String>,
+    /// The number of words in a phrase
+    phrase: usize,
+}
+
+impl Default for WordCounter {
+    fn default() -> Self {
+        Self {
+            pipeline: Pipeline::default(),
+            stopwords: STOPWORDS.iter().map(|word| (*word).to_string()).collect(),
+            phrase: 1,
+        }
+    }
+}
+
+impl WordCounter {
+    /// Returns a counter of single words using the default [`Pipeline`] and [`STOPWORDS`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the pipeline applied to every comment.
+    #[must_use]
+    pub fn pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Replaces the stopwords. Pass an empty list to count every word.
+    #[must_use]
+    pub fn stopwords<I, S>(mut self, stopwords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.stopwords = stopwords
+            .into_iter()
+            .map(|word| word.as_ref().to_lowercase())
+            .collect();
+        self
+    }
+
+    /// Counts phrases of `words` consecutive words instead of single words.
+    ///
+    /// Stopwords are removed before phrases are formed.
+    #[must_use]
+    pub fn phrase(mut self, words: usize) -> Self {
+        self.phrase = words.max(1);
+        self
+    }
+
+    /// Counts the words in HTML comments.
+    pub fn count<'a, I: IntoIterator<Item = &'a str>>(&self, comments: I) -> Frequencies {
+        let mut counts = HashMap::new();
+        for comment in comments {
+            let text = self.pipeline.apply(comment).to_lowercase();
+            let words: Vec<_> = text
+                .split(|c: char| !c.is_alphanumeric() && c != '\'')
+                .map(|word| word.trim_matches('\''))
+                .filter(|word| !word.is_empty() && !word.chars().all(|c| c.is_ascii_digit()))
+                .filter(|word| !self.stopwords.contains(*word))
+                .collect();
+            for phrase in words.windows(self.phrase) {
+                *counts.entry(phrase.join(" ")).or_insert(0) += 1;
+            }
+        }
+        Frequencies { counts }
+    }
+
+    /// Counts the words in every post of a thread.
+    pub fn count_thread(&self, thread: &Thread) -> Frequencies {
+        self.count(
+            iter::once(thread.op())
+                .chain(&thread[..])
+                .map(Post::content),
+        )
+    }
+
+    /// Counts the words in every post of every cached thread of a board.
+    pub fn count_board(&self, board: &Board) -> Frequencies {
+        self.count(board.iter().flat_map(|(_, thread)| {
+            iter::once(thread.op())
+                .chain(&thread[..])
+                .map(Post::content)
+        }))
+    }
+}
+
+/// How often every word or phrase occurred.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frequencies {
+    /// The count of every word or phrase
+    counts: HashMap<String, usize>,
+}
+
+impl Frequencies {
+    /// Returns how often a word or phrase occurred.
+    pub fn get(&self, word: &str) -> usize {
+        self.counts.get(word).copied().unwrap_or(0)
+    }
+
+    /// Returns the `n` most frequent words or phrases, most frequent first.
+    ///
+    /// Ties are ordered alphabetically.
+    pub fn top(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut top: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(word, &count)| (word.as_str(), count))
+            .collect();
+        top.sort_unstable_by_key(|&(word, count)| (Reverse(count), word));
+        top.truncate(n);
+        top
+    }
+
+    /// Returns the number of counted words or phrases, repeats included.
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Returns the number of distinct words or phrases.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns true if nothing was counted.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Returns an iterator over every word or phrase and its count, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.counts
+            .iter()
+            .map(|(word, &count)| (word.as_str(), count))
+    }
+
+    /// Adds the counts of `other` to these.
+    pub fn merge(&mut self, other: &Self) {
+        for (word, count) in &other.counts {
+            *self.counts.entry(word.clone()).or_insert(0) += count;
+        }
+    }
+}
+
+/// Post counts over time, see [`time_histogram`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeHistogram {
+    /// The number of posts in every bucket, keyed by the UNIX timestamp the bucket starts at
+    ///
+    /// Buckets without posts are left out.
+    pub buckets: BTreeMap<i64, usize>,
+    /// The number of posts on every day of the week in UTC, Monday first
+    pub weekdays: [usize; 7],
+    /// The number of posts in every hour of the day in UTC
+    pub hours: [usize; 24],
+}
+
+/// Counts posts per time bucket of length `bucket` and per weekday and hour of the day.
+///
+/// Buckets are aligned to the UNIX epoch, so a bucket of an hour starts on the full hour.
+///
+/// # Example
+///
+/// ```
+/// use dot4ch::{analysis, post::Post};
+/// use std::time::Duration;
+///
+/// // posted at the UNIX epoch, a Thursday
+/// let posts = vec![Post::default(), Post::default()];
+/// let histogram = analysis::time_histogram(&posts, Duration::from_secs(3600));
+///
+/// assert_eq!(histogram.buckets.get(&0), Some(&2));
+/// assert_eq!(histogram.weekdays[3], 2);
+/// assert_eq!(histogram.hours[0], 2);
+/// ```
+pub fn time_histogram<'a, I>(posts: I, bucket: Duration) -> TimeHistogram
+where
+    I: IntoIterator<Item = &'a Post>,
+{
+    let width = i64::try_from(bucket.as_secs()).unwrap_or(i64::MAX).max(1);
+    let mut histogram = TimeHistogram::default();
+    for post in posts {
+        let time = post.post_time();
+        *histogram
+            .buckets
+            .entry(time - time.rem_euclid(width))
+            .or_insert(0) += 1;
+        let date = NaiveDateTime::from_timestamp(time, 0);
+        histogram.weekdays[date.weekday().num_days_from_monday() as usize] += 1;
+        histogram.hours[date.hour() as usize] += 1;
+    }
+    histogram
+}
+
+/// Posts per hour of every day of the week on a board, gathered over many snapshots.
+///
+/// Every post is counted once, however many of the added snapshots it is in,
+/// so a board can be added after every update. Post numbers are only unique on one board,
+/// so a heatmap should only be given posts of a single board.
+///
+/// # Example
+///
+/// ```
+/// use dot4ch::{analysis::Heatmap, post::Post};
+///
+/// // posted at the UNIX epoch, a Thursday
+/// let posts = vec![Post::builder(1).build(), Post::builder(2).post_time(3600).build()];
+/// let mut heatmap = Heatmap::new();
+/// heatmap.add(&posts);
+/// heatmap.add(&posts);
+///
+/// assert_eq!(heatmap.cells[3][0], 1);
+/// assert_eq!(heatmap.cells[3][1], 1);
+/// assert_eq!(heatmap.weekdays()[3], 2);
+/// assert_eq!(heatmap.total(), 2);
+/// println!("{}", serde_json::to_string(&heatmap).unwrap());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Heatmap {
+    /// The number of posts in every hour of every day of the week in UTC, Monday first
+    pub cells: [[usize; 24]; 7],
+    /// The numbers of the posts counted so far
+    #[serde(skip)]
+    seen: HashSet<u32>,
+}
+
+impl Heatmap {
+    /// Creates an empty heatmap.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the posts which were not counted yet.
+    pub fn add<'a, I>(&mut self, posts: I)
+    where
+        I: IntoIterator<Item = &'a Post>,
+    {
+        for post in posts {
+            if !self.seen.insert(post.id()) {
+                continue;
+            }
+            let date = NaiveDateTime::from_timestamp(post.post_time(), 0);
+            self.cells[date.weekday().num_days_from_monday() as usize][date.hour() as usize] += 1;
+        }
+    }
+
+    /// Counts the posts of a thread which were not counted yet.
+    pub fn add_thread(&mut self, thread: &Thread) {
+        self.add(iter::once(thread.op()).chain(&thread[..]));
+    }
+
+    /// Counts the posts of every cached thread of a board which were not counted yet.
+    pub fn add_board(&mut self, board: &Board) {
+        for (_, thread) in board {
+            self.add_thread(thread);
+        }
+    }
+
+    /// Returns the number of posts in every hour of the day in UTC.
+    pub fn hours(&self) -> [usize; 24] {
+        let mut hours = [0; 24];
+        for day in &self.cells {
+            for (hour, posts) in hours.iter_mut().zip(day) {
+                *hour += posts;
+            }
+        }
+        hours
+    }
+
+    /// Returns the number of posts on every day of the week in UTC, Monday first.
+    pub fn weekdays(&self) -> [usize; 7] {
+        let mut weekdays = [0; 7];
+        for (weekday, day) in weekdays.iter_mut().zip(&self.cells) {
+            *weekday = day.iter().sum();
+        }
+        weekdays
+    }
+
+    /// Returns the number of posts counted.
+    pub fn total(&self) -> usize {
+        self.cells.iter().flatten().sum()
+    }
+}
+
+/// The number of posts with one flag.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FlagCount {
+    /// The country code or board flag code
+    pub code: String,
+    /// The name of the country or board flag
+    pub name: String,
+    /// The number of posts with the flag
+    pub posts: usize,
+}
+
+/// The flags of posts, see [`flags`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlagDistribution {
+    /// Posts per country flag, most posts first
+    pub countries: Vec<FlagCount>,
+    /// Posts per board flag, such as the meme flags on /pol/, most posts first
+    pub board_flags: Vec<FlagCount>,
+    /// The number of posts without any flag
+    pub unflagged: usize,
+}
+
+/// Tallies posts per country flag and per board flag.
+///
+/// Country flags and board flags are kept apart, so boards which let posters
+/// replace their country with a meme flag do not skew the countries.
+///
+/// # Example
+///
+/// ```
+/// use dot4ch::{analysis, post::Post};
+///
+/// let posts = vec![Post::default()];
+/// let flags = analysis::flags(&posts);
+///
+/// assert!(flags.countries.is_empty());
+/// assert_eq!(flags.unflagged, 1);
+/// ```
+pub fn flags<'a, I>(posts: I) -> FlagDistribution
+where
+    I: IntoIterator<Item = &'a Post>,
+{
+    let mut countries = HashMap::new();
+    let mut board_flags = HashMap::new();
+    let mut unflagged = 0;
+    for post in posts {
+        if let Some(code) = post.country_code() {
+            let name = post.country().unwrap_or(code);
+            *countries.entry((code, name)).or_insert(0) += 1;
+        } else if let Some(flag) = post.board_flag() {
+            *board_flags.entry(flag).or_insert(0) += 1;
+        } else {
+            unflagged += 1;
+        }
+    }
+    FlagDistribution {
+        countries: sorted(countries),
+        board_flags: sorted(board_flags),
+        unflagged,
+    }
+}
+
+/// Returns the flag counts with the most posts first, then by code.
+fn sorted(counts: HashMap<(&str, &str), usize>) -> Vec<FlagCount> {
+    let mut counts: Vec<_> = counts
+        .into_iter()
+        .map(|((code, name), posts)| FlagCount {
+            code: code.to_string(),
+            name: name.to_string(),
+            posts,
+        })
+        .collect();
+    counts.sort_unstable_by(|a, b| b.posts.cmp(&a.posts).then_with(|| a.code.cmp(&b.code)));
+    counts
+}
+
+/// The language of a post, see [`Post::detect_language`].
+#[cfg(feature = "whatlang")]
+pub use whatlang::Lang;
+
+#[cfg(feature = "whatlang")]
+impl Post {
+    /// Detects the language of the comment.
+    ///
+    /// The comment is turned into plain text and its quotelinks are left out first.
+    /// Returns `None` if the comment is too short or too mixed to tell reliably.
+    ///
+    /// Requires the `whatlang` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dot4ch::{analysis::Lang, post::Post};
+    ///
+    /// let post = Post::builder(2)
+    ///     .content("&gt;&gt;1<br>Das ist doch völlig egal, ich habe es schon gestern gesagt.")
+    ///     .build();
+    /// assert_eq!(post.detect_language(), Some(Lang::Deu));
+    /// assert_eq!(Post::builder(3).content("&gt;&gt;2").build().detect_language(), None);
+    /// ```
+    pub fn detect_language(&self) -> Option<Lang> {
+        let text = self.plain_text();
+        let words: Vec<_> = text
+            .split_whitespace()
+            .filter(|word| !word.starts_with(">>"))
+            .collect();
+        whatlang::detect(&words.join(" "))
+            .filter(whatlang::Info::is_reliable)
+            .map(|info| info.lang())
+    }
+}
+
+/// The number of posts in one language.
+#[cfg(feature = "whatlang")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LanguageCount {
+    /// The language
+    pub lang: Lang,
+    /// The number of posts in the language
+    pub posts: usize,
+}
+
+/// The languages of posts, see [`languages`].
+#[cfg(feature = "whatlang")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LanguageDistribution {
+    /// Posts per language, most posts first
+    pub languages: Vec<LanguageCount>,
+    /// The number of posts whose language could not be told
+    pub undetected: usize,
+}
+
+#[cfg(feature = "whatlang")]
+impl LanguageDistribution {
+    /// Returns the language of the most posts, if any could be told.
+    pub fn dominant(&self) -> Option<Lang> {
+        self.languages.first().map(|count| count.lang)
+    }
+}
+
+/// Tallies posts per language of their comment, see [`Post::detect_language`].
+///
+/// Requires the `whatlang` feature.
+///
+/// # Example
+///
+/// ```
+/// use dot4ch::{analysis::{self, Lang}, post::Post};
+///
+/// let posts = vec![
+///     Post::builder(1).content("This thread is about the best programming language.").build(),
+///     Post::builder(2).content("I would rather write everything in assembly by hand.").build(),
+///     Post::builder(3).content("based").build(),
+/// ];
+/// let languages = analysis::languages(&posts);
+///
+/// assert_eq!(languages.dominant(), Some(Lang::Eng));
+/// assert_eq!(languages.undetected, 1);
+/// ```
+#[cfg(feature = "whatlang")]
+pub fn languages<'a, I>(posts: I) -> LanguageDistribution
+where
+    I: IntoIterator<Item = &'a Post>,
+{
+    let mut counts = HashMap::new();
+    let mut undetected = 0;
+    for post in posts {
+        match post.detect_language() {
+            Some(lang) => *counts.entry(lang).or_insert(0) += 1,
+            None => undetected += 1,
+        }
+    }
+    let mut languages: Vec<_> = counts
+        .into_iter()
+        .map(|(lang, posts)| LanguageCount { lang, posts })
+        .collect();
+    languages.sort_unstable_by(|a, b| {
+        b.posts
+            .cmp(&a.posts)
+            .then_with(|| a.lang.code().cmp(b.lang.code()))
+    });
+    LanguageDistribution {
+        languages,
+        undetected,
+    }
+}
+
+/// The posts of one poster ID in a thread.
+#[derive(Debug, Clone)]
+pub struct Poster<'a> {
+    /// The poster ID
+    pub id: &'a str,
+    /// The posts made under the ID, in the order they were posted
+    pub posts: Vec<&'a Post>,
+}
+
+impl Poster<'_> {
+    /// Returns the number of posts made under the ID.
+    pub fn count(&self) -> usize {
+        self.posts.len()
+    }
+}
+
+/// Groups posts by their poster ID, ordered by the first post of every ID.
+///
+/// Posts without an ID are left out, so this is empty on boards without IDs.
+///
+/// # Example
+///
+/// ```
+/// # use dot4ch::{analysis, Client, thread::Thread};
+/// # async fn posters() -> anyhow::Result<()> {
+/// # let client = Client::new();
+/// let thread = Thread::new(&client, "pol", 124205675).await?;
+/// let mut posters = analysis::posters(thread.posts());
+///
+/// posters.sort_by_key(|poster| std::cmp::Reverse(poster.count()));
+/// for poster in posters.iter().take(5) {
+///     println!("ID:{} made {} posts", poster.id, poster.count());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn posters<'a, I>(posts: I) -> Vec<Poster<'a>>
+where
+    I: IntoIterator<Item = &'a Post>,
+{
+    let mut order = HashMap::new();
+    let mut posters: Vec<Poster<'a>> = vec![];
+    for post in posts {
+        let Some(id) = post.poster_id() else {
+            continue;
+        };
+        let idx = *order.entry(id).or_insert_with(|| {
+            posters.push(Poster { id, posts: vec![] });
+            posters.len() - 1
+        });
+        posters[idx].posts.push(post);
+    }
+    posters
+}
@@ -0,0 +1,327 @@
+//! A list of the archived threads of a board.
+//!
+//! This is documented as `archive.json` in the
+//! [4chan API Repository](<https://github.com/4chan/4chan-API/blob/master/pages/Archive.md>)
+//!
+//! Archived threads are closed to replies but can still be fetched as a [`Thread`]
+//! until they are deleted for good.
+//!
+//! Not every board has an archive, the request fails with a 404 on those boards.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, archive::Archive};
+//! # async fn archive() -> anyhow::Result<()> {
+//! # let client = Client::new();
+//! let archive = Archive::new(&client, "g").await?;
+//!
+//! // the oldest archived thread, which will be deleted first
+//! if let Some(id) = archive.threads().first() {
+//!     let thread = archive.thread(*id).await?;
+//!     println!("{}", thread);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    diff::ArchiveDiff,
+    logging::debug,
+    persist::{read, write, Format, Metadata, Persist, Snapshot},
+    thread::Thread,
+    Dot4chClient, Error, IfModifiedSince, Procedures, Updatable, Update, UpdateStatus,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use futures::Stream;
+use reqwest::{Response, StatusCode};
+use std::{
+    fmt::{self, Display, Formatter},
+    mem,
+    path::Path,
+};
+
+/// The thread numbers of all archived threads on a board, oldest first.
+#[derive(Debug, Clone)]
+pub struct Archive {
+    /// The board of the archive
+    board: String,
+    /// The archived thread numbers
+    threads: Vec<u32>,
+    /// The time when the archive was accessed
+    last_accessed: DateTime<Utc>,
+    /// The `Last-Modified` header of the last response
+    last_modified: Option<String>,
+    /// The threads added and removed by the last update
+    changes: ArchiveDiff,
+    /// client
+    client: Dot4chClient,
+}
+
+impl Display for Archive {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Board: /{}/\nLast accessed: {}\nArchived threads: {}",
+            self.board,
+            self.last_accessed.format("%a, %d %b %Y %T"),
+            self.threads.len()
+        )
+    }
+}
+
+impl Archive {
+    /// Returns the archive of a board.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails
+    /// or if the board does not have an archive.
+    pub async fn new(client: &Dot4chClient, board: &str) -> crate::Result<Self> {
+        crate::boards::check(client, board).await?;
+        let url = format!("https://a.4cdn.org/{board}/archive.json");
+        let operation = format!("fetching the archive of /{board}/");
+        let (threads, last_modified) =
+            crate::fetch_json::<Vec<u32>>(client, &url, board, None, &operation).await?;
+
+        Ok(Self {
+            board: board.to_string(),
+            threads: oldest_first(threads),
+            last_accessed: Utc::now(),
+            last_modified,
+            changes: ArchiveDiff::default(),
+            client: client.clone(),
+        })
+    }
+
+    /// Returns the archived thread numbers, oldest first.
+    pub fn threads(&self) -> &[u32] {
+        &self.threads
+    }
+
+    /// Returns true if the thread is in the archive.
+    pub fn contains(&self, id: u32) -> bool {
+        self.threads.binary_search(&id).is_ok()
+    }
+
+    /// Returns the threads which were archived or deleted between the last two fetches.
+    ///
+    /// Empty until the archive was updated, and after an update which found no change.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, Update, archive::Archive};
+    /// # async fn changes() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let mut archive = Archive::new(&client, "g").await?;
+    /// loop {
+    ///     archive = archive.update().await?;
+    ///     // fetch the newly archived threads before they are deleted
+    ///     for &id in &archive.changes().added {
+    ///         let thread = archive.thread(id).await?;
+    ///         println!("{} got archived", thread.op().id());
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn changes(&self) -> &ArchiveDiff {
+        &self.changes
+    }
+
+    /// Returns the time until the archive may be updated again, `None` if it may be updated now.
+    pub fn cooldown(&self) -> Option<std::time::Duration> {
+        crate::cooldown(self.last_accessed)
+    }
+
+    /// Updates the archive in place without waiting out the cooldown.
+    ///
+    /// Returns whether the archive changed since the last update.
+    /// Schedulers can sleep for the `remaining` time of the error and try again.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an [`Error::Cooldown`] if the archive was updated
+    /// less than 10 seconds ago, or any error of [`Update::update`].
+    pub async fn try_update(&mut self) -> crate::Result<UpdateStatus> {
+        if let Some(remaining) = self.cooldown() {
+            return Err(Error::Cooldown { remaining }.into());
+        }
+        self.refresh().await
+    }
+
+    /// Fetches the archive if it changed since the last update.
+    async fn refresh(&mut self) -> crate::Result<UpdateStatus> {
+        let url = self.url();
+        let context = format!("updating the archive of /{}/ ({url})", self.board);
+        let response = crate::refresh(
+            &self.client,
+            &url,
+            &self.board,
+            None,
+            self.last_modified.as_deref(),
+        )
+        .await
+        .with_context(|| context.clone())?;
+        self.apply(response).await.context(context)
+    }
+
+    /// Replaces the thread numbers with those of a response, `None` if they did not change.
+    async fn apply(&mut self, response: Option<Response>) -> crate::Result<UpdateStatus> {
+        let status = if response.is_some() {
+            UpdateStatus::Modified
+        } else {
+            UpdateStatus::NotModified
+        };
+        if let Some(response) = response {
+            let last_modified = crate::last_modified(&response);
+            let threads = oldest_first(crate::json(response).await?);
+            let older = mem::replace(&mut self.threads, threads);
+            self.changes = ArchiveDiff::between(&older, &self.threads);
+            self.last_modified = last_modified;
+        } else {
+            self.changes = ArchiveDiff::default();
+        }
+        self.last_accessed = Utc::now();
+        Ok(status)
+    }
+
+    /// Returns the board of the archive.
+    pub fn board(&self) -> &str {
+        &self.board
+    }
+
+    /// Returns the API URL of the archive.
+    pub fn url(&self) -> String {
+        format!("https://a.4cdn.org/{}/archive.json", self.board)
+    }
+
+    /// Fetches an archived [`Thread`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to fetch the [`Thread`] fails.
+    pub async fn thread(&self, id: u32) -> crate::Result<Thread> {
+        Thread::new(&self.client, &self.board, id).await
+    }
+
+    /// Fetches every archived [`Thread`] with [`Thread::fetch_many`], starting with the oldest,
+    /// which are the next to be deleted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, archive::Archive};
+    /// # use futures::{pin_mut, StreamExt};
+    /// # async fn backfill() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let archive = Archive::new(&client, "g").await?;
+    /// let threads = archive.iter_threads_oldest_first();
+    /// pin_mut!(threads);
+    /// while let Some(thread) = threads.next().await {
+    ///     println!("{}", thread?.title(80));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_threads_oldest_first(&self) -> impl Stream<Item = crate::Result<Thread>> {
+        Thread::fetch_many(&self.client, &self.board, self.threads.clone())
+    }
+}
+
+/// Sorts archived thread numbers oldest first, thread numbers growing over time.
+fn oldest_first(mut threads: Vec<u32>) -> Vec<u32> {
+    threads.sort_unstable();
+    threads
+}
+
+#[async_trait(?Send)]
+impl Updatable for Archive {
+    async fn update(&mut self, client: &Dot4chClient) -> crate::Result<UpdateStatus> {
+        self.client = client.clone();
+        self.try_update().await
+    }
+}
+
+#[async_trait(?Send)]
+impl Update for Archive {
+    type Output = Self;
+    /// Returns an updated archive.
+    async fn update(mut self) -> crate::Result<Self> {
+        self.refresh_time().await?;
+        self.refresh().await?;
+        Ok(self)
+    }
+}
+
+#[async_trait(?Send)]
+impl Procedures for Archive {
+    type Output = Self;
+
+    async fn refresh_time(&mut self) -> crate::Result<()> {
+        let curr = Utc::now().signed_duration_since(self.last_accessed);
+        if curr < Duration::seconds(10) {
+            debug!(
+                "Updating Archive too quickly! Waiting for {} seconds",
+                (10000_f32 - curr.num_milliseconds() as f32) / 1000_f32
+            );
+            match Duration::seconds(10).checked_sub(&curr) {
+                Some(time) => crate::sleep(&self.client, time.to_std()?).await,
+                None => return Err(anyhow::anyhow!("Overflow in subtraction of `Duration`")),
+            }
+        }
+        Ok(())
+    }
+
+    async fn fetch_status(mut self, response: Response) -> crate::Result<Self::Output> {
+        let response = crate::check_in(response, &self.board, None).await?;
+        match response.status() {
+            StatusCode::OK => self.into_upper(response).await,
+            StatusCode::NOT_MODIFIED => {
+                self.apply(None).await?;
+                Ok(self)
+            }
+            _ => Err(Error::unexpected(&response).into()),
+        }
+    }
+
+    async fn into_upper(mut self, response: Response) -> crate::Result<Self::Output> {
+        self.apply(Some(response)).await?;
+        Ok(self)
+    }
+}
+
+#[async_trait(?Send)]
+impl IfModifiedSince for Archive {
+    async fn fetch(client: &Dot4chClient, url: &str, header: &str) -> Result<Response, Error> {
+        crate::get_since(client, url, header).await
+    }
+}
+
+impl Persist for Archive {
+    fn save<P: AsRef<Path>>(&self, path: P, format: Format) -> crate::Result<()> {
+        let metadata = Metadata::new(self.url(), &self.board, self.last_modified.as_deref());
+        write(
+            &Snapshot {
+                metadata,
+                data: &self.threads,
+            },
+            path,
+            format,
+        )
+    }
+
+    fn load<P: AsRef<Path>>(client: &Dot4chClient, path: P, format: Format) -> crate::Result<Self> {
+        let snapshot: Snapshot<Vec<u32>> = read(path, format)?;
+        Ok(Self {
+            board: snapshot.metadata.board,
+            threads: oldest_first(snapshot.data),
+            last_accessed: snapshot.metadata.saved_at,
+            last_modified: snapshot.metadata.last_modified,
+            changes: ArchiveDiff::default(),
+            client: client.clone(),
+        })
+    }
+}
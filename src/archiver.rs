@@ -0,0 +1,321 @@
+//! Continuous capture of a whole board to disk.
+//!
+//! An [`Archiver`] repeatedly:
+//! - updates the [`Catalog`] of the board,
+//! - saves every live [`Thread`] which changed since the last pass,
+//! - downloads the media of those threads,
+//! - saves the final state of threads that got archived since the last pass.
+//!
+//! All requests go through the shared client, so the request cooldowns are respected.
+//!
+//! Everything is written below the given directory:
+//!
+//! ```text
+//! <dir>/catalog.json
+//! <dir>/archive.json
+//! <dir>/threads/<thread no>.json
+//! <dir>/media/<file>
+//! ```
+//!
+//! Snapshots are written with [`Persist`], so they can be loaded back and updated.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, archiver::Archiver};
+//! # use std::time::Duration;
+//! # async fn archiver() -> anyhow::Result<()> {
+//! let client = Client::new();
+//! let mut archiver = Archiver::new(&client, "g", "archive/g")
+//!     .media(true)
+//!     .interval(Duration::from_mins(1));
+//!
+//! // runs until an error occurs
+//! archiver.run().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    archive::Archive,
+    catalog::Catalog,
+    logging::{info, warn},
+    persist::Persist,
+    post::FileKind,
+    scheduler::Shutdown,
+    thread::Thread,
+    Dot4chClient, Error, Update,
+};
+use anyhow::Context;
+use std::{
+    collections::{HashMap, HashSet},
+    fs, iter,
+    path::PathBuf,
+    time::Duration,
+};
+
+/// Captures a board to disk.
+#[derive(Debug)]
+pub struct Archiver {
+    /// the client
+    client: Dot4chClient,
+    /// The board to capture
+    board: String,
+    /// The directory everything is written to
+    dir: PathBuf,
+    /// Whether media is downloaded
+    media: bool,
+    /// The kinds of media downloaded, every kind if empty
+    media_kinds: HashSet<FileKind>,
+    /// The wait between two passes
+    interval: Duration,
+    /// The catalog of the last pass
+    catalog: Option<Catalog>,
+    /// The archive of the last pass
+    archive: Option<Archive>,
+    /// The `last_modified` of every live thread when it was captured
+    captured: HashMap<u32, i64>,
+}
+
+/// What happened during a single pass of an [`Archiver`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pass {
+    /// The number of live threads saved
+    pub threads: usize,
+    /// The number of newly archived threads saved
+    pub archived: usize,
+    /// The number of media files downloaded
+    pub media: usize,
+    /// The number of threads that disappeared without being archived
+    pub pruned: usize,
+    /// The number of threads that could not be captured
+    pub failed: usize,
+}
+
+impl Archiver {
+    /// Creates an archiver for a board writing to `dir`.
+    ///
+    /// Media is not downloaded by default and passes are one minute apart.
+    pub fn new<P: Into<PathBuf>>(client: &Dot4chClient, board: &str, dir: P) -> Self {
+        Self {
+            client: client.clone(),
+            board: board.to_string(),
+            dir: dir.into(),
+            media: false,
+            media_kinds: HashSet::new(),
+            interval: Duration::from_mins(1),
+            catalog: None,
+            archive: None,
+            captured: HashMap::new(),
+        }
+    }
+
+    /// Sets whether media files are downloaded.
+    #[must_use]
+    pub fn media(mut self, media: bool) -> Self {
+        self.media = media;
+        self
+    }
+
+    /// Only downloads media of the given kinds, such as [`FileKind::Webm`].
+    ///
+    /// Every kind is downloaded by default.
+    #[must_use]
+    pub fn media_kinds<I: IntoIterator<Item = FileKind>>(mut self, kinds: I) -> Self {
+        self.media_kinds = kinds.into_iter().collect();
+        self
+    }
+
+    /// Sets the wait between two passes.
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Runs passes forever, waiting for the interval in between.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a pass fails.
+    /// See [`Archiver::capture`].
+    pub async fn run(&mut self) -> crate::Result<()> {
+        loop {
+            let pass = self.capture().await?;
+            info!("Captured /{}/: {:?}", self.board, pass);
+            crate::sleep(&self.client, self.interval).await;
+        }
+    }
+
+    /// Runs passes until `shutdown` is triggered, waiting for the interval in between.
+    ///
+    /// A pass in progress when the signal comes is finished, so everything it fetched
+    /// is written to disk before returning. The wait between passes is cut short.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a pass fails.
+    /// See [`Archiver::capture`].
+    pub async fn run_until(&mut self, shutdown: &Shutdown) -> crate::Result<()> {
+        while !shutdown.is_triggered() {
+            let pass = self.capture().await?;
+            info!("Captured /{}/: {:?}", self.board, pass);
+            tokio::select! {
+                () = shutdown.triggered() => {}
+                () = crate::sleep(&self.client, self.interval) => {}
+            }
+        }
+        info!("Stopped capturing /{}/", self.board);
+        Ok(())
+    }
+
+    /// Runs a single pass.
+    ///
+    /// A thread which cannot be fetched does not fail the pass,
+    /// it is counted in [`Pass::failed`] and retried on the next pass.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the catalog cannot be fetched
+    /// or if writing to disk fails.
+    pub async fn capture(&mut self) -> crate::Result<Pass> {
+        fs::create_dir_all(self.dir.join("threads"))?;
+        if self.media {
+            fs::create_dir_all(self.dir.join("media"))?;
+        }
+        let mut pass = Pass::default();
+
+        let catalog = match self.catalog.take() {
+            Some(catalog) => catalog.update().await?,
+            None => Catalog::new(&self.client, &self.board).await?,
+        };
+        catalog.save_json(self.dir.join("catalog.json"))?;
+        let live: HashMap<_, _> = catalog
+            .threads()
+            .map(|thread| (thread.id(), thread.last_modified()))
+            .collect();
+        self.catalog = Some(catalog);
+
+        for (&id, &modified) in &live {
+            if self.captured.get(&id) == Some(&modified) {
+                continue;
+            }
+            if self.capture_thread(id, &mut pass).await? {
+                pass.threads += 1;
+                self.captured.insert(id, modified);
+            }
+        }
+
+        let previous: Option<HashSet<u32>> = self
+            .archive
+            .as_ref()
+            .map(|archive| archive.threads().iter().copied().collect());
+        let archive = match self.archive.take() {
+            Some(archive) => archive.update().await,
+            None => Archive::new(&self.client, &self.board).await,
+        };
+        let archived = match archive {
+            Ok(archive) => {
+                archive.save_json(self.dir.join("archive.json"))?;
+                let archived: Vec<_> = archive
+                    .threads()
+                    .iter()
+                    .copied()
+                    .filter(|id| match &previous {
+                        Some(previous) => !previous.contains(id),
+                        // on the first pass only threads we have seen alive are new
+                        None => self.captured.contains_key(id),
+                    })
+                    .collect();
+                self.archive = Some(archive);
+                archived
+            }
+            Err(err) => {
+                warn!("Could not fetch the archive of /{}/: {}", self.board, err);
+                vec![]
+            }
+        };
+
+        for id in archived {
+            if self.capture_thread(id, &mut pass).await? {
+                pass.archived += 1;
+                self.captured.remove(&id);
+            }
+        }
+
+        let gone: Vec<_> = self
+            .captured
+            .keys()
+            .copied()
+            .filter(|id| !live.contains_key(id))
+            .collect();
+        for id in gone {
+            if !self.archive.as_ref().is_some_and(|a| a.contains(id)) {
+                pass.pruned += 1;
+            }
+            self.captured.remove(&id);
+        }
+
+        Ok(pass)
+    }
+
+    /// Fetches and saves a thread along with its media.
+    ///
+    /// Returns false if the thread could not be fetched.
+    async fn capture_thread(&self, id: u32, pass: &mut Pass) -> crate::Result<bool> {
+        let thread = match Thread::new(&self.client, &self.board, id).await {
+            Ok(thread) => thread,
+            Err(err) => {
+                warn!("Could not capture /{}/{}: {}", self.board, id, err);
+                pass.failed += 1;
+                return Ok(false);
+            }
+        };
+        thread.save_json(self.dir.join("threads").join(format!("{id}.json")))?;
+        if self.media {
+            pass.media += self.capture_media(&thread).await?;
+        }
+        Ok(true)
+    }
+
+    /// Downloads the media of a thread which is not on disk yet.
+    ///
+    /// Returns the number of files downloaded.
+    async fn capture_media(&self, thread: &Thread) -> crate::Result<usize> {
+        let mut downloaded = 0;
+        let mut buf = Vec::new();
+        for post in iter::once(thread.op()).chain(&thread[..]) {
+            if post.file_deleted() {
+                continue;
+            }
+            let wanted = post.file_kind().is_some_and(|kind| {
+                self.media_kinds.is_empty() || self.media_kinds.contains(&kind)
+            });
+            if !wanted {
+                continue;
+            }
+            let Some(url) = post.image_url(&self.board) else {
+                continue;
+            };
+            let Some(name) = url.rsplit('/').next() else {
+                continue;
+            };
+            let path = self.dir.join("media").join(name);
+            if path.exists() {
+                continue;
+            }
+            buf.clear();
+            let downloaded_file = self.client.lock().await.get_into(&url, &mut buf).await;
+            if let Err(err) = downloaded_file {
+                if Error::find(&err).is_some_and(|err| err.status().is_some()) {
+                    warn!("Could not download {url}: {err}");
+                    continue;
+                }
+                return Err(err.context(format!("downloading {url}")));
+            }
+            fs::write(&path, &buf).with_context(|| format!("writing {}", path.display()))?;
+            downloaded += 1;
+        }
+        Ok(downloaded)
+    }
+}
@@ -0,0 +1,146 @@
+//! A tiny blocking client for quick scripts.
+//!
+//! Requires the `mini-blocking` feature.
+//!
+//! Fetching a single thread does not need an async runtime:
+//! [`MiniClient`] sends its requests with `ureq` and waits out the
+//! 1 second-per-request cooldown with [`std::thread::sleep`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dot4ch::blocking::MiniClient;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let mut client = MiniClient::new();
+//! let posts = client.get_thread("g", 76759434)?;
+//! println!("{} replies", posts.len() - 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    board::OpPage,
+    boards::Boards,
+    error::{preview, BODY_PREVIEW},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    logging::trace,
+    post::Post,
+    Error, Result,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{
+    io::Read,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The least time between two requests.
+const COOLDOWN: Duration = Duration::from_secs(1);
+
+/// A blocking client which only fetches threads, catalogs and the list of boards.
+#[derive(Debug)]
+pub struct MiniClient {
+    /// The ureq agent
+    agent: ureq::Agent,
+    /// When the last request was sent
+    last_request: Option<Instant>,
+}
+
+impl Default for MiniClient {
+    fn default() -> Self {
+        Self {
+            agent: ureq::Agent::new(),
+            last_request: None,
+        }
+    }
+}
+
+/// The JSON of a thread.
+#[derive(Deserialize)]
+struct ThreadJson {
+    /// Every post of the thread, OP first
+    posts: Vec<Post>,
+}
+
+impl MiniClient {
+    /// Creates a new client.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetches every post of a thread, OP first.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub fn get_thread(&mut self, board: &str, id: u32) -> Result<Vec<Post>> {
+        let url = format!("https://a.4cdn.org/{board}/thread/{id}.json");
+        Ok(self.get::<ThreadJson>(&url)?.posts)
+    }
+
+    /// Fetches the OPs of every thread on a board, in catalog order.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub fn get_catalog(&mut self, board: &str) -> Result<Vec<Post>> {
+        let url = format!("https://a.4cdn.org/{board}/catalog.json");
+        let pages = self.get::<Vec<OpPage>>(&url)?;
+        Ok(pages
+            .into_iter()
+            .flat_map(|page| page.threads)
+            .map(|entry| entry.op)
+            .collect())
+    }
+
+    /// Fetches the list of boards.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub fn get_boards(&mut self) -> Result<Boards> {
+        self.get("https://a.4cdn.org/boards.json")
+    }
+
+    /// Sends a GET request after the cooldown and parses the JSON response.
+    fn get<T: DeserializeOwned>(&mut self, url: &str) -> Result<T> {
+        let wait = self
+            .last_request
+            .and_then(|last| COOLDOWN.checked_sub(last.elapsed()));
+        if let Some(wait) = wait {
+            trace!("Requesting responses too fast! Waiting for {wait:?}");
+            thread::sleep(wait);
+        }
+        let response = self.agent.get(url).call();
+        self.last_request = Some(Instant::now());
+        match response {
+            Ok(response) => Ok(response.into_json()?),
+            Err(ureq::Error::Status(code, response)) => Err(unexpected(code, response)?.into()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Builds an [`Error`] from an error response of ureq.
+fn unexpected(code: u16, response: ureq::Response) -> Result<Error> {
+    let mut headers = HeaderMap::new();
+    for name in response.headers_names() {
+        if let Some(value) = response.header(&name) {
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+    }
+    let mut body = vec![];
+    response
+        .into_reader()
+        .take(BODY_PREVIEW as u64)
+        .read_to_end(&mut body)?;
+    Ok(Error::from_status(
+        StatusCode::from_u16(code)?,
+        headers,
+        preview(&body),
+    ))
+}
@@ -29,13 +29,32 @@
 //! # }
 //! ```
 
-use crate::{thread::Thread, threadlist::Catalog, Dot4chClient, Update};
+use crate::{
+    archive::Archive,
+    cache::{Budget, Lru},
+    hash,
+    logging::{debug, info, warn},
+    persist::{read, write, Format, Metadata, Persist, Snapshot},
+    post::{Capcode, Post},
+    thread::{Source, Thread},
+    threadlist::{Catalog, CatalogThread, Page},
+    Dot4chClient, Updatable, Update, UpdateStatus,
+};
+use anyhow::Context;
 use async_trait::async_trait;
-use log::info;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::Deserialize;
 
 use std::{
-    collections::HashMap,
-    io::{self, Write},
+    cmp::Reverse,
+    collections::{hash_map, HashMap},
+    convert::TryFrom,
+    fmt::{self, Debug, Display, Formatter},
+    iter, mem,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 #[derive(Debug)]
@@ -43,6 +62,20 @@ use std::{
 pub struct Board {
     /// A HashMap of Thread and their ID's
     pub threads: HashMap<u32, Thread>,
+    /// The `last_modified` timestamp from `threads.json` of every cached thread
+    pub(crate) modified: hash::HashMap<u32, i64>,
+    /// Threads which got archived, in their last known state
+    pub(crate) archived: HashMap<u32, Thread>,
+    /// Threads which disappeared without being archived, in their last known state
+    pub(crate) pruned: HashMap<u32, Thread>,
+    /// What happens to threads which leave the board
+    pub(crate) policy: PrunePolicy,
+    /// Which threads of the board are cached
+    selection: Selection,
+    /// The thread of every cached post
+    posts: hash::HashMap<u32, u32>,
+    /// Evicts the least recently used threads over the memory budget
+    lru: Lru<u32, Thread>,
     /// The board on this instance of board is based.
     pub(crate) board: String,
     /// the client
@@ -54,7 +87,7 @@ impl Board {
     ///
     /// # Time
     ///
-    /// A typical board of ~150 threads takes 5+ minutes to cache.
+    /// A typical board of ~150 threads takes around 3 minutes to cache.
     ///
     /// It is advised to build this only once due to its long wait times caused by API cooldowns.
     ///
@@ -62,37 +95,183 @@ impl Board {
     ///
     /// This function will return an error if the request to get a new [`Catalog`] fails.
     pub async fn build(client: &Dot4chClient, board: &str) -> crate::Result<Self> {
-        writeln!(io::stdout(), "Building Board! Please wait.")?;
-        let catalog = Catalog::new(client, board).await?;
-        let ids: Vec<_> = catalog
-            .all_pages()
-            .into_iter()
-            .flat_map(crate::threadlist::Page::threads)
-            .map(|thread| thread.id())
+        Self::build_with(client, board, BuildOptions::default()).await
+    }
+
+    /// Returns an entire board built with the given [`BuildOptions`].
+    ///
+    /// Requests still go out one at a time through the client,
+    /// but up to [`BuildOptions::concurrency`] threads are in flight so that
+    /// downloading and parsing a thread overlaps with requesting the next one.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to get a new [`Catalog`] fails
+    /// or if any of the threads cannot be fetched. Threads which are gone by the time
+    /// they are fetched are skipped.
+    pub async fn build_with(
+        client: &Dot4chClient,
+        board: &str,
+        options: BuildOptions,
+    ) -> crate::Result<Self> {
+        info!("Building /{board}/");
+        let mut board = Self::empty(client, board);
+        board.selection = options.selection.clone();
+        board.fill(&options).await
+    }
+
+    /// Returns a board without any threads.
+    pub(crate) fn empty(client: &Dot4chClient, board: &str) -> Self {
+        Self {
+            threads: HashMap::default(),
+            modified: HashMap::default(),
+            archived: HashMap::default(),
+            pruned: HashMap::default(),
+            policy: PrunePolicy::default(),
+            selection: Selection::default(),
+            posts: HashMap::default(),
+            lru: Lru::default(),
+            board: board.to_string(),
+            client: client.clone(),
+        }
+    }
+
+    /// Resumes an interrupted build from a checkpoint written with [`BuildOptions::checkpoint`].
+    ///
+    /// Threads already in the checkpoint are kept as they are and are only
+    /// checked for changes on the next [`Update::update`].
+    /// Threads which fell off the board in the meantime are dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, board::{Board, BuildOptions}};
+    /// # async fn resume() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let options = BuildOptions::new().checkpoint("g.json", 25);
+    /// let board = match Board::resume(&client, "g.json", options.clone()).await {
+    ///     Ok(board) => board,
+    ///     Err(_) => Board::build_with(&client, "g", options).await?,
+    /// };
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the checkpoint cannot be read,
+    /// if the request to get a new [`Catalog`] fails
+    /// or if any of the missing threads cannot be fetched. Threads which are gone by the time
+    /// they are fetched are skipped.
+    pub async fn resume<P: AsRef<Path>>(
+        client: &Dot4chClient,
+        path: P,
+        options: BuildOptions,
+    ) -> crate::Result<Self> {
+        let mut board = Self::load_json(client, path)?;
+        info!(
+            "Resuming /{}/ with {} threads",
+            board.board,
+            board.threads.len()
+        );
+        board.selection = options.selection.clone();
+        board.fill(&options).await
+    }
+
+    /// Fetches every thread of the board which is not cached yet.
+    async fn fill(self, options: &BuildOptions) -> crate::Result<Self> {
+        let live = self.select().await?;
+        self.fill_from(live, options).await
+    }
+
+    /// Fetches every thread of `live` which is not cached yet and drops the others.
+    ///
+    /// Threads which are gone by the time they are fetched are skipped.
+    /// `live` maps the threads to their `last_modified` timestamp.
+    async fn fill_from(
+        mut self,
+        live: hash::HashMap<u32, i64>,
+        options: &BuildOptions,
+    ) -> crate::Result<Self> {
+        self.threads.retain(|id, _| live.contains_key(id));
+
+        let ids: Vec<_> = live
+            .keys()
+            .copied()
+            .filter(|id| !self.threads.contains_key(id))
             .collect();
+        let total = ids.len();
+        info!("Number of threads: {total}");
 
-        info!("Number of threads: {}", ids.len());
-        let mut threads = vec![];
-        for (idx, id) in ids.iter().enumerate() {
-            threads.push(Thread::new(client, board, *id).await?);
-            info!("Pushed Thread: {}/{}", idx + 1, ids.len())
+        let start = Instant::now();
+        let (client, board) = (self.client.clone(), self.board.clone());
+        let (client, board) = (&client, board.as_str());
+        let pending = stream::iter(ids)
+            .map(|id| async move { (id, Thread::new(client, board, id).await) })
+            .buffer_unordered(options.concurrency.max(1));
+        futures::pin_mut!(pending);
+
+        let mut done = 0;
+        let mut fetched = vec![];
+        while let Some((id, thread)) = pending.next().await {
+            match thread {
+                Ok(thread) => fetched.push((id, thread)),
+                Err(err) if crate::is_not_found(&err) => {
+                    warn!("Thread /{}/{} is gone: {}", self.board, id, err);
+                }
+                Err(err) => return Err(err),
+            }
+            done += 1;
+            let progress = Progress::new(done, total, start.elapsed(), id);
+            debug!(
+                "Pushed Thread: {} ({}/{}), ETA: {:?}",
+                id, progress.done, progress.total, progress.eta
+            );
+            if let Some(callback) = &options.progress {
+                callback(&progress);
+            }
+            if let Some((path, every)) = &options.checkpoint {
+                if done % every == 0 || done == total {
+                    self.checkpoint(&mut fetched, &live, path)?;
+                }
+            }
         }
+        for (id, thread) in fetched {
+            self.modified.insert(id, live[&id]);
+            self.lru.touch(&id);
+            self.threads.insert(id, thread);
+        }
+        info!("Built /{}/ in {:?}", self.board, start.elapsed());
+
+        self.shrink();
+        self.reindex();
+        Ok(self)
+    }
 
-        let threads: Vec<_> = threads.into_iter().zip(ids).collect();
-        let mut id_thread_zip = HashMap::new();
-        for (thread, num) in threads {
-            id_thread_zip.insert(num, thread);
+    /// Moves the fetched threads into the cache and saves it.
+    fn checkpoint(
+        &mut self,
+        fetched: &mut Vec<(u32, Thread)>,
+        live: &hash::HashMap<u32, i64>,
+        path: &Path,
+    ) -> crate::Result<()> {
+        for (id, thread) in fetched.drain(..) {
+            self.modified.insert(id, live[&id]);
+            self.threads.insert(id, thread);
         }
-        Ok(Self {
-            threads: id_thread_zip,
-            board: board.to_string(),
-            client: client.clone(),
-        })
+        debug!(
+            "Saving checkpoint of /{}/ to {}",
+            self.board,
+            path.display()
+        );
+        self.save_json(path)
     }
 
     /// Returns a specific Thread from the Board cache.
     pub fn get(&self, k: u32) -> Option<&'_ Thread> {
-        self.threads.get(&k)
+        let thread = self.threads.get(&k)?;
+        self.lru.touch(&k);
+        Some(thread)
     }
 
     /// Inserts a new thread into a cache.
@@ -100,13 +279,838 @@ impl Board {
     /// If a thread already exists, it updates the thread
     /// while retaining the post number and returns the old thread.
     pub fn insert(&mut self, id: u32, thread: Thread) -> Option<Thread> {
-        self.threads.insert(id, thread)
+        // the thread is checked with `If-Modified-Since` on the next update
+        self.modified.remove(&id);
+        self.index(&thread);
+        self.lru.touch(&id);
+        let old = self.threads.insert(id, thread);
+        self.shrink();
+        old
+    }
+
+    /// Finds a post in any of the cached threads.
+    ///
+    /// Returns the post along with the thread it is in.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, board::Board};
+    /// # async fn find() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let board = Board::build(&client, "g").await?;
+    /// if let Some((thread, post)) = board.find_post(76759434) {
+    ///     println!("{} is in thread {}", post.id(), thread.op().id());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_post(&self, no: u32) -> Option<(&Thread, &Post)> {
+        let thread = self.get(*self.posts.get(&no)?)?;
+        if thread.op().id() == no {
+            return Some((thread, thread.op()));
+        }
+        thread.find(no).map(|post| (thread, post))
+    }
+
+    /// Returns the `n` cached threads ranking highest by `key`, highest first.
+    ///
+    /// Ties are broken by thread number, oldest first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, board::{Board, SortKey}};
+    /// # async fn top() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let board = Board::build(&client, "g").await?;
+    /// for (rank, thread) in board.top(10, SortKey::UniquePosters).iter().enumerate() {
+    ///     println!("#{}: {}", rank + 1, thread.op().subject());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn top(&self, n: usize, key: SortKey) -> Vec<&Thread> {
+        let mut threads: Vec<_> = self.threads.values().collect();
+        threads.sort_unstable_by_key(|thread| (Reverse(key.of(thread)), thread.op().id()));
+        threads.truncate(n);
+        threads
+    }
+
+    /// Returns the cached threads which are pinned, the way the site lists them apart.
+    pub fn stickies(&self) -> impl Iterator<Item = &Thread> {
+        self.threads.values().filter(|thread| thread.op().sticky())
+    }
+
+    /// Returns the cached threads which are closed to replies.
+    pub fn closed(&self) -> impl Iterator<Item = &Thread> {
+        self.threads.values().filter(|thread| thread.op().closed())
+    }
+
+    /// Returns every cached post made with a tripcode, along with its thread.
+    pub fn posts_by_tripcode<'a>(
+        &'a self,
+        tripcode: &'a str,
+    ) -> impl Iterator<Item = (&'a Thread, &'a Post)> {
+        self.threads.values().flat_map(move |thread| {
+            thread
+                .posts_by_tripcode(tripcode)
+                .map(move |post| (thread, post))
+        })
+    }
+
+    /// Returns every cached post made with a [`Capcode`], along with its thread.
+    pub fn posts_by_capcode(&self, capcode: Capcode) -> impl Iterator<Item = (&Thread, &Post)> {
+        self.threads.values().flat_map(move |thread| {
+            thread
+                .posts_by_capcode(capcode.clone())
+                .map(move |post| (thread, post))
+        })
+    }
+
+    /// Adds the posts of a thread to the post index.
+    fn index(&mut self, thread: &Thread) {
+        let id = thread.op().id();
+        for post in iter::once(thread.op()).chain(&thread[..]) {
+            self.posts.insert(post.id(), id);
+        }
+    }
+
+    /// Rebuilds the post index from the cached threads.
+    fn reindex(&mut self) {
+        let mut posts = hash::HashMap::default();
+        for (&id, thread) in &self.threads {
+            for post in iter::once(thread.op()).chain(&thread[..]) {
+                posts.insert(post.id(), id);
+            }
+        }
+        self.posts = posts;
     }
 
     /// Returns the board of the cache
     pub fn board(&self) -> &str {
         &self.board
     }
+
+    /// Returns the link to the board on the site, such as `https://boards.4chan.org/g/`.
+    pub fn web_url(&self) -> String {
+        format!("https://boards.4chan.org/{}/", self.board)
+    }
+
+    /// Returns an iterator over the cached threads and their ID's, in arbitrary order.
+    pub fn iter(&self) -> hash_map::Iter<'_, u32, Thread> {
+        self.threads.iter()
+    }
+
+    /// Returns the number of cached threads.
+    pub fn len(&self) -> usize {
+        self.threads.len()
+    }
+
+    /// Returns true if no threads are cached.
+    pub fn is_empty(&self) -> bool {
+        self.threads.is_empty()
+    }
+
+    /// Sets what happens to threads which leave the board on an update.
+    pub fn set_prune_policy(&mut self, policy: PrunePolicy) {
+        self.policy = policy;
+    }
+
+    /// Bounds the cached threads by a [`Budget`], evicting the least recently used ones.
+    ///
+    /// A thread counts as used when it is fetched, changes on an update or is looked up
+    /// with [`Board::get`] or [`Board::find_post`].
+    /// An evicted thread is only fetched again once it changes on the board.
+    ///
+    /// The threads in [`Board::archived`] and [`Board::pruned`] are not bounded,
+    /// take them out of the board to free them.
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.lru.set_budget(Some(budget));
+        self.shrink();
+    }
+
+    /// Removes the budget of the cached threads.
+    pub fn clear_budget(&mut self) {
+        self.lru.set_budget(None);
+    }
+
+    /// Sets a callback which receives every thread evicted for the [`Budget`].
+    pub fn on_evict<F: Fn(u32, Thread) + Send + Sync + 'static>(&mut self, callback: F) {
+        self.lru.on_evict(callback);
+    }
+
+    /// Evicts threads until the cache fits its budget.
+    fn shrink(&mut self) {
+        let evicted = self.lru.evict(&mut self.threads, Thread::approx_size);
+        if !evicted.is_empty() {
+            debug!("Evicted {} threads of /{}/", evicted.len(), self.board);
+            self.reindex();
+        }
+    }
+
+    /// Returns the threads which got archived since they were cached.
+    ///
+    /// Only filled with [`PrunePolicy::Keep`].
+    pub fn archived(&self) -> &HashMap<u32, Thread> {
+        &self.archived
+    }
+
+    /// Returns the threads which disappeared without being archived,
+    /// either by falling off the last page or by being deleted.
+    ///
+    /// Only filled with [`PrunePolicy::Keep`].
+    pub fn pruned(&self) -> &HashMap<u32, Thread> {
+        &self.pruned
+    }
+
+    /// Takes the archived threads out of the cache.
+    pub fn take_archived(&mut self) -> HashMap<u32, Thread> {
+        mem::take(&mut self.archived)
+    }
+
+    /// Takes the pruned threads out of the cache.
+    pub fn take_pruned(&mut self) -> HashMap<u32, Thread> {
+        mem::take(&mut self.pruned)
+    }
+
+    /// Returns the `last_modified` timestamp of every live thread in the selection.
+    ///
+    /// `catalog.json` is only fetched when the OPs need to be filtered,
+    /// `threads.json` is enough otherwise.
+    async fn select(&self) -> crate::Result<hash::HashMap<u32, i64>> {
+        let selection = &self.selection;
+        let pages = selection.pages.unwrap_or(usize::MAX);
+        if !selection.needs_ops() {
+            let catalog = Catalog::new(&self.client, &self.board).await?;
+            return Ok(catalog
+                .all_pages()
+                .into_iter()
+                .take(pages)
+                .flat_map(Page::threads)
+                .filter(|thread| thread.replies() >= selection.min_replies)
+                .map(|thread| (thread.id(), thread.last_modified()))
+                .collect());
+        }
+
+        let catalog = ops(&self.client, &self.board).await?;
+        Ok(catalog
+            .into_iter()
+            .take(pages)
+            .flat_map(|page| page.threads)
+            .filter(|thread| {
+                thread.op.replies() >= selection.min_replies && selection.admits(&thread.op)
+            })
+            .map(|thread| (thread.op.id(), thread.last_modified))
+            .collect())
+    }
+
+    /// Moves threads which left the board into the archived or pruned bucket.
+    async fn prune(&mut self, gone: HashMap<u32, Thread>) {
+        if gone.is_empty() {
+            return;
+        }
+        info!("Threads gone from /{}/: {}", self.board, gone.len());
+        if self.policy == PrunePolicy::Drop {
+            return;
+        }
+        let archive = match Archive::new(&self.client, &self.board).await {
+            Ok(archive) => Some(archive),
+            Err(err) => {
+                warn!("Could not fetch the archive of /{}/: {}", self.board, err);
+                None
+            }
+        };
+        for (id, thread) in gone {
+            if archive.as_ref().is_some_and(|archive| archive.contains(id)) {
+                self.archived.insert(id, thread);
+            } else {
+                self.pruned.insert(id, thread);
+            }
+        }
+    }
+}
+
+/// What [`Board::top`] ranks threads by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SortKey {
+    /// The number of replies
+    Replies,
+    /// The number of image replies
+    Images,
+    /// The number of unique posters, which archived threads do not report
+    UniquePosters,
+    /// The time of the latest post
+    RecentActivity,
+}
+
+impl SortKey {
+    /// Returns the value of a thread to rank by, higher first.
+    fn of(self, thread: &Thread) -> i64 {
+        let op = thread.op();
+        match self {
+            Self::Replies => i64::from(op.replies()),
+            Self::Images => i64::from(op.images()),
+            Self::UniquePosters => i64::from(op.unique_ips().unwrap_or(0)),
+            Self::RecentActivity => thread.last_post().unwrap_or(op).post_time(),
+        }
+    }
+}
+
+/// What happens to cached threads which leave the board on an update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrunePolicy {
+    /// Move them into [`Board::archived`] or [`Board::pruned`]
+    #[default]
+    Keep,
+    /// Drop them from the cache
+    Drop,
+}
+
+impl Display for Board {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Board: /{}/\nCached threads: {}\nCached posts: {}\nArchived threads: {}\nPruned threads: {}",
+            self.board,
+            self.threads.len(),
+            self.posts.len(),
+            self.archived.len(),
+            self.pruned.len()
+        )
+    }
+}
+
+impl<'a> IntoIterator for &'a Board {
+    type Item = (&'a u32, &'a Thread);
+    type IntoIter = hash_map::Iter<'a, u32, Thread>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.threads.iter()
+    }
+}
+
+impl IntoIterator for Board {
+    type Item = (u32, Thread);
+    type IntoIter = hash_map::IntoIter<u32, Thread>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.threads.into_iter()
+    }
+}
+
+/// The catalog, OPs and archive of a board, fetched one right after another.
+///
+/// The requests go through the client one at a time like any other,
+/// so the three endpoints are a few seconds apart at most.
+///
+/// # Example
+///
+/// ```
+/// # use dot4ch::{Client, board::BoardSnapshot, thread::Source};
+/// # async fn snapshot() -> anyhow::Result<()> {
+/// # let client = Client::new();
+/// let snapshot = BoardSnapshot::fetch(&client, "g").await?;
+/// for thread in snapshot.catalog().threads() {
+///     if let Some(op) = snapshot.op(thread.id()) {
+///         println!("{}: {} replies", op.subject(), thread.replies());
+///     }
+/// }
+/// if let Some(&oldest) = snapshot.archive().and_then(|archive| archive.threads().first()) {
+///     assert_eq!(snapshot.source(oldest), Some(Source::Archive));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BoardSnapshot {
+    /// When the first request was sent
+    taken: DateTime<Utc>,
+    /// `threads.json`
+    catalog: Catalog,
+    /// The OPs of `catalog.json`, in catalog order
+    ops: Vec<Post>,
+    /// The position of every OP in `ops` by thread number
+    index: hash::HashMap<u32, usize>,
+    /// `archive.json`, `None` if the board has no archive
+    archive: Option<Archive>,
+}
+
+impl BoardSnapshot {
+    /// Fetches `catalog.json`, `threads.json` and `archive.json` of a board.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any request fails,
+    /// other than the archive of a board without one.
+    pub async fn fetch(client: &Dot4chClient, board: &str) -> crate::Result<Self> {
+        let taken = Utc::now();
+        let ops: Vec<_> = ops(client, board)
+            .await?
+            .into_iter()
+            .flat_map(|page| page.threads)
+            .map(|thread| thread.op)
+            .collect();
+        let catalog = Catalog::new(client, board).await?;
+        let archive = match Archive::new(client, board).await {
+            Ok(archive) => Some(archive),
+            Err(err) if crate::is_not_found(&err) => None,
+            Err(err) => return Err(err),
+        };
+        let index = ops
+            .iter()
+            .enumerate()
+            .map(|(idx, op)| (op.id(), idx))
+            .collect();
+        Ok(Self {
+            taken,
+            catalog,
+            ops,
+            index,
+            archive,
+        })
+    }
+
+    /// Returns when the snapshot was taken.
+    pub fn taken(&self) -> DateTime<Utc> {
+        self.taken
+    }
+
+    /// Returns the name of the board.
+    pub fn board(&self) -> &str {
+        self.catalog.board()
+    }
+
+    /// Returns the catalog from `threads.json`.
+    pub fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    /// Returns the OPs from `catalog.json`, in catalog order.
+    pub fn ops(&self) -> &[Post] {
+        &self.ops
+    }
+
+    /// Returns the archive, `None` if the board has no archive.
+    pub fn archive(&self) -> Option<&Archive> {
+        self.archive.as_ref()
+    }
+
+    /// Returns the OP of a live thread.
+    pub fn op(&self, id: u32) -> Option<&Post> {
+        self.index.get(&id).map(|&idx| &self.ops[idx])
+    }
+
+    /// Returns where a thread was at the time of the snapshot:
+    /// [`Source::Live`] if it is in the catalog, [`Source::Archive`] if it is in the archive.
+    ///
+    /// Returns `None` if the thread is in neither.
+    pub fn source(&self, id: u32) -> Option<Source> {
+        if self.index.contains_key(&id) || self.catalog.threads().any(|thread| thread.id() == id) {
+            Some(Source::Live)
+        } else if self
+            .archive
+            .as_ref()
+            .is_some_and(|archive| archive.contains(id))
+        {
+            Some(Source::Archive)
+        } else {
+            None
+        }
+    }
+}
+
+/// A page of `catalog.json`.
+///
+/// Unlike `threads.json` it carries the OPs themselves.
+#[derive(Debug, Deserialize)]
+pub(crate) struct OpPage {
+    /// The OPs on the page
+    pub(crate) threads: Vec<CatalogOp>,
+}
+
+/// An OP in `catalog.json`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct CatalogOp {
+    /// The OP
+    #[serde(flatten)]
+    pub(crate) op: Post,
+    /// The UNIX timestamp marking the last time the thread was modified
+    #[serde(default)]
+    pub(crate) last_modified: i64,
+    /// The most recent replies to the thread
+    #[serde(default)]
+    pub(crate) last_replies: Vec<Post>,
+}
+
+/// Fetches the `catalog.json` of a board.
+///
+/// # Errors
+///
+/// This function will return an error if the request fails.
+pub(crate) async fn ops(client: &Dot4chClient, board: &str) -> crate::Result<Vec<OpPage>> {
+    let url = format!("https://a.4cdn.org/{board}/catalog.json");
+    let fetch = async {
+        let response = client.lock().await.get(&url).await?;
+        let response = crate::check_in(response, board, None).await?;
+        crate::stream_array(response).try_collect().await
+    };
+    fetch
+        .await
+        .with_context(|| format!("fetching the OPs of /{board}/ ({url})"))
+}
+
+/// A callback receiving the [`Progress`] of a build.
+type ProgressCallback = Arc<dyn Fn(&Progress)>;
+
+/// A filter on the OPs of threads.
+type OpFilter = Arc<dyn Fn(&Post) -> bool + Send + Sync>;
+
+/// The threads of a board which are cached.
+#[derive(Clone, Default)]
+struct Selection {
+    /// Only threads on the first pages
+    pages: Option<usize>,
+    /// Only threads with at least this many replies
+    min_replies: u32,
+    /// Only threads whose OP passes the filter
+    filter: Option<OpFilter>,
+    /// Leave out pinned threads
+    exclude_stickies: bool,
+    /// Leave out threads closed to replies
+    exclude_closed: bool,
+    /// Leave out archived threads
+    exclude_archived: bool,
+}
+
+impl Selection {
+    /// Returns true if the OPs from `catalog.json` are needed to select threads.
+    fn needs_ops(&self) -> bool {
+        self.filter.is_some()
+            || self.exclude_stickies
+            || self.exclude_closed
+            || self.exclude_archived
+    }
+
+    /// Returns true if the thread of an OP is selected, its replies aside.
+    fn admits(&self, op: &Post) -> bool {
+        (!self.exclude_stickies || !op.sticky())
+            && (!self.exclude_closed || !op.closed())
+            && (!self.exclude_archived || !op.archived())
+            && self.filter.as_ref().is_none_or(|filter| filter(op))
+    }
+
+    /// Returns true if a thread of the catalog is not excluded by its state.
+    fn admits_state(&self, thread: &CatalogThread) -> bool {
+        (!self.exclude_stickies || !thread.sticky())
+            && (!self.exclude_closed || !thread.closed())
+            && (!self.exclude_archived || !thread.archived())
+    }
+}
+
+impl Debug for Selection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Selection")
+            .field("pages", &self.pages)
+            .field("min_replies", &self.min_replies)
+            .field("filter", &self.filter.is_some())
+            .field("exclude_stickies", &self.exclude_stickies)
+            .field("exclude_closed", &self.exclude_closed)
+            .field("exclude_archived", &self.exclude_archived)
+            .finish()
+    }
+}
+
+impl Catalog {
+    /// Builds a [`Board`] with every thread in this catalog.
+    ///
+    /// See [`Catalog::hydrate_with`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the threads cannot be fetched.
+    /// Threads which are gone by the time they are fetched are skipped.
+    pub async fn hydrate(
+        &self,
+        client: &Dot4chClient,
+        previous: Option<Board>,
+    ) -> crate::Result<Board> {
+        self.hydrate_with(client, previous, BuildOptions::default())
+            .await
+    }
+
+    /// Builds a [`Board`] with the threads in this catalog, without fetching the catalog again.
+    ///
+    /// Threads of `previous` which have not been modified since they were cached,
+    /// going by the `last_modified` of this catalog, are kept instead of being fetched again.
+    /// Threads of `previous` which are not in this catalog are dropped, and so are threads
+    /// which were deleted or archived since this catalog was fetched.
+    ///
+    /// The threads are fetched as by [`Board::build_with`]. [`BuildOptions::pages`] and
+    /// [`BuildOptions::min_replies`] apply, but [`BuildOptions::filter`] only applies to
+    /// later updates, as the catalog has no OPs to filter.
+    /// [`BuildOptions::exclude_stickies`], [`BuildOptions::exclude_closed`] and
+    /// [`BuildOptions::exclude_archived`] apply if the catalog was fetched with
+    /// [`Catalog::full`], and only to later updates otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, Update, board::BuildOptions, catalog::Catalog};
+    /// # async fn hydrate() -> anyhow::Result<()> {
+    /// let client = Client::new();
+    /// let catalog = Catalog::new(&client, "g").await?;
+    /// let board = catalog.hydrate(&client, None).await?;
+    ///
+    /// // later, only the threads which changed are fetched
+    /// let catalog = catalog.update().await?;
+    /// let options = BuildOptions::new().on_progress(|progress| println!("{:?}", progress));
+    /// let board = catalog.hydrate_with(&client, Some(board), options).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the threads cannot be fetched.
+    /// Threads which are gone by the time they are fetched are skipped.
+    pub async fn hydrate_with(
+        &self,
+        client: &Dot4chClient,
+        previous: Option<Board>,
+        options: BuildOptions,
+    ) -> crate::Result<Board> {
+        let selection = &options.selection;
+        let live: hash::HashMap<_, _> = (0..)
+            .map_while(|idx| self.page(idx))
+            .take(selection.pages.unwrap_or(usize::MAX))
+            .flat_map(|page| page[..].iter())
+            .filter(|thread| thread.replies() >= selection.min_replies)
+            .filter(|thread| selection.admits_state(thread))
+            .map(|thread| (thread.id(), thread.last_modified()))
+            .collect();
+
+        let mut board = previous
+            .filter(|board| board.board == self.board())
+            .unwrap_or_else(|| Board::empty(client, self.board()));
+        let stale: Vec<_> = board
+            .threads
+            .keys()
+            .copied()
+            .filter(|id| board.modified.get(id) != live.get(id))
+            .collect();
+        debug!(
+            "Hydrating /{}/: {} cached threads changed",
+            board.board,
+            stale.len()
+        );
+        for id in stale {
+            board.threads.remove(&id);
+            board.modified.remove(&id);
+        }
+        board.selection = options.selection.clone();
+        board.fill_from(live, &options).await
+    }
+}
+
+/// Options for building a [`Board`].
+///
+/// # Example
+///
+/// ```
+/// # use dot4ch::{Client, board::{Board, BuildOptions}};
+/// # async fn build() -> anyhow::Result<()> {
+/// # let client = Client::new();
+/// let options = BuildOptions::new()
+///     .concurrency(4)
+///     .on_progress(|progress| {
+///         println!("{}/{} threads, ETA: {:?}", progress.done, progress.total, progress.eta)
+///     });
+/// let board = Board::build_with(&client, "g", options).await?;
+///
+/// // only the generals on the first three pages
+/// let options = BuildOptions::new()
+///     .pages(3)
+///     .min_replies(50)
+///     .filter(|op| op.subject().ends_with("General"));
+/// let generals = Board::build_with(&client, "vg", options).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct BuildOptions {
+    /// The number of threads in flight at once
+    concurrency: usize,
+    /// Called after every fetched thread
+    progress: Option<ProgressCallback>,
+    /// Where to save the partial board and after how many threads
+    checkpoint: Option<(PathBuf, usize)>,
+    /// Which threads are fetched
+    selection: Selection,
+}
+
+impl Debug for BuildOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BuildOptions")
+            .field("concurrency", &self.concurrency)
+            .field("progress", &self.progress.is_some())
+            .field("checkpoint", &self.checkpoint)
+            .field("selection", &self.selection)
+            .finish()
+    }
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 2,
+            progress: None,
+            checkpoint: None,
+            selection: Selection::default(),
+        }
+    }
+}
+
+impl BuildOptions {
+    /// Returns the default options.
+    ///
+    /// Two threads are in flight at once, which is enough to
+    /// keep the client busy under the request cooldown.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of threads in flight at once.
+    ///
+    /// A value of `1` fetches the threads strictly one after another.
+    #[must_use]
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Sets a callback which is called with the [`Progress`] after every fetched thread.
+    #[must_use]
+    pub fn on_progress<F: Fn(&Progress) + 'static>(mut self, callback: F) -> Self {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Saves the partial board as JSON to `path` after every `every` fetched threads,
+    /// so that an interrupted build can be continued with [`Board::resume`].
+    ///
+    /// The finished board is saved as well.
+    #[must_use]
+    pub fn checkpoint<P: Into<PathBuf>>(mut self, path: P, every: usize) -> Self {
+        self.checkpoint = Some((path.into(), every.max(1)));
+        self
+    }
+
+    /// Only fetches threads on the first `pages` pages of the board.
+    #[must_use]
+    pub fn pages(mut self, pages: usize) -> Self {
+        self.selection.pages = Some(pages);
+        self
+    }
+
+    /// Only fetches threads with at least `replies` replies.
+    #[must_use]
+    pub fn min_replies(mut self, replies: u32) -> Self {
+        self.selection.min_replies = replies;
+        self
+    }
+
+    /// Only fetches threads whose OP passes the filter.
+    ///
+    /// The OPs come from `catalog.json`, so the subject and comment can be matched
+    /// before fetching a thread.
+    #[must_use]
+    pub fn filter<F: Fn(&Post) -> bool + Send + Sync + 'static>(mut self, filter: F) -> Self {
+        self.selection.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Leaves out pinned threads, such as the rules and the board's FAQ.
+    ///
+    /// Like [`BuildOptions::filter`], this reads the OPs from `catalog.json`.
+    #[must_use]
+    pub fn exclude_stickies(mut self) -> Self {
+        self.selection.exclude_stickies = true;
+        self
+    }
+
+    /// Leaves out threads which are closed to replies.
+    ///
+    /// Like [`BuildOptions::filter`], this reads the OPs from `catalog.json`.
+    #[must_use]
+    pub fn exclude_closed(mut self) -> Self {
+        self.selection.exclude_closed = true;
+        self
+    }
+
+    /// Leaves out threads which are archived but still listed.
+    ///
+    /// Like [`BuildOptions::filter`], this reads the OPs from `catalog.json`.
+    #[must_use]
+    pub fn exclude_archived(mut self) -> Self {
+        self.selection.exclude_archived = true;
+        self
+    }
+}
+
+/// The progress of a [`Board`] build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// The number of threads fetched so far
+    pub done: usize,
+    /// The number of threads to fetch
+    pub total: usize,
+    /// The time since the build started
+    pub elapsed: Duration,
+    /// The estimated time until the build is done
+    pub eta: Duration,
+    /// The thread which was just fetched
+    pub thread: u32,
+}
+
+impl Progress {
+    /// Creates the progress after `done` of `total` threads, estimating the time left.
+    fn new(done: usize, total: usize, elapsed: Duration, thread: u32) -> Self {
+        let left = u32::try_from(total.saturating_sub(done)).unwrap_or(u32::MAX);
+        let done_n = u32::try_from(done.max(1)).unwrap_or(u32::MAX);
+        Self {
+            done,
+            total,
+            elapsed,
+            eta: elapsed / done_n * left,
+            thread,
+        }
+    }
+}
+
+impl Persist for Board {
+    fn save<P: AsRef<Path>>(&self, path: P, format: Format) -> crate::Result<()> {
+        let metadata = Metadata::new(
+            format!("https://a.4cdn.org/{}/threads.json", self.board),
+            &self.board,
+            None,
+        );
+        let data: Vec<_> = self.threads.values().map(Thread::snapshot).collect();
+        write(&Snapshot { metadata, data }, path, format)
+    }
+
+    fn load<P: AsRef<Path>>(client: &Dot4chClient, path: P, format: Format) -> crate::Result<Self> {
+        let snapshot: Snapshot<Vec<Snapshot<Vec<Post>>>> = read(path, format)?;
+        let mut threads = HashMap::default();
+        for thread in snapshot.data {
+            let thread = Thread::from_snapshot(client, thread)?;
+            threads.insert(thread.op().id(), thread);
+        }
+        let mut board = Self::empty(client, &snapshot.metadata.board);
+        board.threads = threads;
+        board.reindex();
+        Ok(board)
+    }
 }
 
 #[async_trait(?Send)]
@@ -114,43 +1118,109 @@ impl Update for Board {
     type Output = Self;
     /// Returns an updated board.
     ///
-    /// It is recommended to call this infrequently due to API calls having cooldowns.
+    /// The `threads.json` of the board is fetched first and only threads whose
+    /// `last_modified` timestamp changed since the last update are fetched again.
+    /// New threads are added if they match the [`BuildOptions`] the board was built with.
+    ///
+    /// Threads which left the board or return a 404 are handled by the [`PrunePolicy`]
+    /// instead of failing the update.
+    ///
+    /// Cached threads without a known timestamp, such as ones loaded from disk or
+    /// inserted by hand, are updated using the `If-Modified-Since` header.
     ///
-    /// Uses `If-Modified-Since` header internally.
+    /// It is recommended to call this infrequently due to API calls having cooldowns.
     async fn update(mut self) -> crate::Result<Self::Output> {
-        // get the ID's of all the threads we need
-        // we have to call this again because there might be new thread that need to be added.
-        writeln!(io::stdout(), "Updating Board. Please wait..")?;
-        let catalog = Catalog::new(&self.client, &self.board).await?;
-        let ids: Vec<_> = catalog
-            .all_pages()
-            .into_iter()
-            .flat_map(crate::threadlist::Page::threads)
-            .map(|thread| thread.id())
-            .collect();
+        self.refresh().await?;
+        Ok(self)
+    }
+}
 
-        let mut threads = vec![];
-        for (num, (id, thread)) in self.threads.into_iter().enumerate() {
-            // update all threads with the ID
-            threads.push(thread.update().await?);
-            info!(
-                "Updating thread: {}\t Threads updated: {}/{}",
-                id,
-                (num + 1),
-                &ids.len()
-            );
+#[async_trait(?Send)]
+impl Updatable for Board {
+    /// Updates the board in place like [`Update::update`].
+    ///
+    /// The board is [`UpdateStatus::Modified`] if a thread was fetched, changed or left the board.
+    /// If the update fails, the cached threads are kept.
+    async fn update(&mut self, client: &Dot4chClient) -> crate::Result<UpdateStatus> {
+        self.client = client.clone();
+        self.refresh().await
+    }
+}
+
+impl Board {
+    /// Updates the cached threads, see [`Update::update`].
+    async fn refresh(&mut self) -> crate::Result<UpdateStatus> {
+        info!("Updating /{}/", self.board);
+        let live = self.select().await?;
+
+        let mut threads = HashMap::default();
+        let mut modified = hash::HashMap::default();
+        let mut status = UpdateStatus::NotModified;
+        let total = live.len();
+        for (num, (&id, &last_modified)) in live.iter().enumerate() {
+            let (result, cached) = match (self.threads.remove(&id), self.modified.get(&id)) {
+                (Some(thread), Some(&last)) if last == last_modified => {
+                    (Ok((thread, UpdateStatus::NotModified)), None)
+                }
+                // evicted for the budget and unchanged since
+                (None, Some(&last)) if last == last_modified => {
+                    modified.insert(id, last);
+                    continue;
+                }
+                (Some(thread), None) => {
+                    self.lru.touch(&id);
+                    (thread.clone().updated().await, Some(thread))
+                }
+                (cached, _) => {
+                    self.lru.touch(&id);
+                    info!(
+                        "Fetching thread: {}\t Threads checked: {}/{}",
+                        id,
+                        num + 1,
+                        total
+                    );
+                    let fetched = Thread::new(&self.client, &self.board, id).await;
+                    (
+                        fetched.map(|thread| (thread, UpdateStatus::Modified)),
+                        cached,
+                    )
+                }
+            };
+            match result {
+                Ok((thread, thread_status)) => {
+                    if thread_status.is_modified() {
+                        status = UpdateStatus::Modified;
+                    }
+                    threads.insert(id, thread);
+                    modified.insert(id, last_modified);
+                }
+                Err(err) if crate::is_not_found(&err) => {
+                    warn!("Thread /{}/{} is gone: {}", self.board, id, err);
+                    if let Some(thread) = cached {
+                        self.threads.insert(id, thread);
+                    }
+                }
+                Err(err) => {
+                    // put back what was taken out, the timestamps are still the old ones
+                    if let Some(thread) = cached {
+                        self.threads.insert(id, thread);
+                    }
+                    self.threads.extend(threads);
+                    return Err(err);
+                }
+            }
         }
 
-        let mut id_thread_zip = HashMap::new();
-        let threads: Vec<_> = threads.into_iter().zip(ids).collect();
-        for (thread, num) in threads {
-            id_thread_zip.insert(num, thread);
+        let gone = mem::replace(&mut self.threads, threads);
+        if !gone.is_empty() {
+            status = UpdateStatus::Modified;
         }
-        writeln!(io::stdout(), "Finished updating threads!")?;
-        Ok(Self {
-            threads: id_thread_zip,
-            board: self.board,
-            client: self.client,
-        })
+        self.modified = modified;
+        self.prune(gone).await;
+        self.shrink();
+        self.reindex();
+
+        info!("Finished updating /{}/", self.board);
+        Ok(status)
     }
 }
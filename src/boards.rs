@@ -0,0 +1,348 @@
+//! The list of all boards and their settings.
+//!
+//! This is documented as `boards.json` in the
+//! [4chan API Repository](<https://github.com/4chan/4chan-API/blob/master/pages/Boards.md>)
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, boards::Boards};
+//! # async fn boards() -> anyhow::Result<()> {
+//! # let client = Client::new();
+//! let boards = Boards::new(&client).await?;
+//!
+//! if let Some(g) = boards.get("g") {
+//!     println!("/{}/ - {} has {} pages", g.board(), g.title(), g.pages());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    default,
+    persist::{read, write, Format, Metadata, Persist, Snapshot},
+    Dot4chClient, Error, Updatable, UpdateStatus,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::{path::Path, sync::Arc};
+
+/// The API URL of the list of boards.
+const URL: &str = "https://a.4cdn.org/boards.json";
+
+/// Every board on 4chan.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Boards {
+    /// The boards in the order 4chan lists them
+    boards: Vec<BoardInfo>,
+    /// The `Last-Modified` header of the last response
+    #[serde(skip)]
+    last_modified: Option<String>,
+}
+
+impl Boards {
+    /// Fetches the list of boards.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn new(client: &Dot4chClient) -> crate::Result<Self> {
+        let fetch = async {
+            let response = client.lock().await.get(URL).await?;
+            let response = crate::check_body(response).await?;
+            let last_modified = crate::last_modified(&response);
+            let boards = crate::json::<Self>(response).await?;
+            Ok::<_, anyhow::Error>(Self {
+                last_modified,
+                ..boards
+            })
+        };
+        fetch
+            .await
+            .with_context(|| format!("fetching the list of boards ({URL})"))
+    }
+
+    /// Returns the board with the given code, such as `g`.
+    pub fn get(&self, board: &str) -> Option<&BoardInfo> {
+        self.boards.iter().find(|info| info.board == board)
+    }
+
+    /// Returns true if there is a board with the given code, such as `g`.
+    pub fn contains(&self, board: &str) -> bool {
+        self.get(board).is_some()
+    }
+
+    /// Returns the worksafe boards.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, boards::Boards};
+    /// # async fn worksafe() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let boards = Boards::new(&client).await?;
+    /// for board in boards.worksafe() {
+    ///     println!("/{}/ - {}", board.board(), board.title());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn worksafe(&self) -> impl Iterator<Item = &BoardInfo> {
+        self.filter(BoardInfo::worksafe)
+    }
+
+    /// Returns the boards which are not worksafe.
+    pub fn nsfw(&self) -> impl Iterator<Item = &BoardInfo> {
+        self.filter(|info| !info.worksafe())
+    }
+
+    /// Returns the boards matching a predicate.
+    pub fn filter<F>(&self, predicate: F) -> impl Iterator<Item = &BoardInfo>
+    where
+        F: Fn(&BoardInfo) -> bool,
+    {
+        self.boards.iter().filter(move |info| predicate(info))
+    }
+
+    /// Returns the board with the given code, such as `g`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an [`Error::UnknownBoard`] if there is no such board.
+    pub fn validate(&self, board: &str) -> Result<&BoardInfo, Error> {
+        self.get(board).ok_or_else(|| Error::UnknownBoard {
+            board: board.to_string(),
+        })
+    }
+
+    /// Returns all boards in the order 4chan lists them.
+    pub fn boards(&self) -> &[BoardInfo] {
+        &self.boards
+    }
+
+    /// Returns the number of boards.
+    pub fn len(&self) -> usize {
+        self.boards.len()
+    }
+
+    /// Returns true if there are no boards.
+    pub fn is_empty(&self) -> bool {
+        self.boards.is_empty()
+    }
+}
+
+#[async_trait(?Send)]
+impl Updatable for Boards {
+    /// Fetches the list of boards again if it changed since it was fetched.
+    async fn update(&mut self, client: &Dot4chClient) -> crate::Result<UpdateStatus> {
+        let response = crate::refresh(client, URL, "", None, self.last_modified.as_deref())
+            .await
+            .with_context(|| format!("updating the list of boards ({URL})"))?;
+        if let Some(response) = response {
+            let last_modified = crate::last_modified(&response);
+            let boards = crate::json::<Self>(response).await?;
+            *self = Self {
+                last_modified,
+                ..boards
+            };
+            Ok(UpdateStatus::Modified)
+        } else {
+            Ok(UpdateStatus::NotModified)
+        }
+    }
+}
+
+impl Persist for Boards {
+    fn save<P: AsRef<Path>>(&self, path: P, format: Format) -> crate::Result<()> {
+        let metadata = Metadata::new(URL.to_string(), "", self.last_modified.as_deref());
+        write(
+            &Snapshot {
+                metadata,
+                data: &self.boards,
+            },
+            path,
+            format,
+        )
+    }
+
+    /// Reads a list of boards, the client is not used.
+    fn load<P: AsRef<Path>>(
+        _client: &Dot4chClient,
+        path: P,
+        format: Format,
+    ) -> crate::Result<Self> {
+        let snapshot: Snapshot<Vec<BoardInfo>> = read(path, format)?;
+        Ok(Self {
+            boards: snapshot.data,
+            last_modified: snapshot.metadata.last_modified,
+        })
+    }
+}
+
+/// The settings of a single board.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BoardInfo {
+    /// The board code, such as `g`
+    board: String,
+
+    /// The readable title of the board
+    title: String,
+
+    /// If the board is worksafe
+    #[serde(default = "default::<u8>")]
+    ws_board: u8,
+
+    /// The number of threads on a single index page
+    #[serde(default = "default::<u32>")]
+    per_page: u32,
+
+    /// The number of index pages
+    #[serde(default = "default::<u32>")]
+    pages: u32,
+
+    /// The largest allowed file size in bytes
+    #[serde(default = "default::<u64>")]
+    max_filesize: u64,
+
+    /// The largest allowed comment length in characters
+    #[serde(default = "default::<u32>")]
+    max_comment_chars: u32,
+
+    /// The number of replies after which a thread stops bumping
+    #[serde(default = "default::<u32>")]
+    bump_limit: u32,
+
+    /// The number of images after which no more images can be posted
+    #[serde(default = "default::<u32>")]
+    image_limit: u32,
+
+    /// If the board is archived
+    #[serde(default = "default::<u8>")]
+    is_archived: u8,
+
+    /// The SEO meta description of the board
+    #[serde(default = "default::<String>")]
+    meta_description: String,
+
+    /// The seconds to wait between posts
+    #[serde(default = "default::<Cooldowns>")]
+    cooldowns: Cooldowns,
+}
+
+impl BoardInfo {
+    /// Returns the board code, such as `g`.
+    pub fn board(&self) -> &str {
+        &self.board
+    }
+
+    /// Returns the readable title of the board.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Returns true if the board is worksafe.
+    pub fn worksafe(&self) -> bool {
+        self.ws_board == 1
+    }
+
+    /// Returns the number of threads on a single index page.
+    pub fn per_page(&self) -> u32 {
+        self.per_page
+    }
+
+    /// Returns the number of index pages.
+    pub fn pages(&self) -> u32 {
+        self.pages
+    }
+
+    /// Returns the largest allowed file size in bytes.
+    pub fn max_filesize(&self) -> u64 {
+        self.max_filesize
+    }
+
+    /// Returns the largest allowed comment length in characters.
+    pub fn max_comment_chars(&self) -> u32 {
+        self.max_comment_chars
+    }
+
+    /// Returns the number of replies after which a thread stops bumping.
+    pub fn bump_limit(&self) -> u32 {
+        self.bump_limit
+    }
+
+    /// Returns the number of images after which no more images can be posted.
+    pub fn image_limit(&self) -> u32 {
+        self.image_limit
+    }
+
+    /// Returns true if the board has an archive.
+    pub fn is_archived(&self) -> bool {
+        self.is_archived == 1
+    }
+
+    /// Returns the SEO meta description of the board.
+    pub fn meta_description(&self) -> &str {
+        &self.meta_description
+    }
+
+    /// Returns the seconds to wait between posts.
+    pub fn cooldowns(&self) -> Cooldowns {
+        self.cooldowns
+    }
+}
+
+/// The seconds a poster has to wait between posts on a board.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Cooldowns {
+    /// The seconds between two new threads
+    #[serde(default = "default::<u32>")]
+    threads: u32,
+
+    /// The seconds between two replies
+    #[serde(default = "default::<u32>")]
+    replies: u32,
+
+    /// The seconds between two replies with an image
+    #[serde(default = "default::<u32>")]
+    images: u32,
+}
+
+impl Cooldowns {
+    /// Returns the seconds between two new threads.
+    pub fn threads(self) -> u32 {
+        self.threads
+    }
+
+    /// Returns the seconds between two replies.
+    pub fn replies(self) -> u32 {
+        self.replies
+    }
+
+    /// Returns the seconds between two replies with an image.
+    pub fn images(self) -> u32 {
+        self.images
+    }
+}
+
+/// Checks a board code against the cached list of boards if the client validates boards.
+///
+/// The list is fetched on the first check and kept for the life of the client.
+pub(crate) async fn check(client: &Dot4chClient, board: &str) -> crate::Result<()> {
+    let cached = {
+        let client = client.lock().await;
+        if !client.validate_boards {
+            return Ok(());
+        }
+        client.boards.clone()
+    };
+    let boards = if let Some(boards) = cached {
+        boards
+    } else {
+        let boards = Arc::new(Boards::new(client).await?);
+        client.lock().await.boards = Some(boards.clone());
+        boards
+    };
+    boards.validate(board)?;
+    Ok(())
+}
@@ -0,0 +1,145 @@
+//! Bounds on the memory of caches.
+//!
+//! A [`Board`](crate::board::Board) keeps every thread it fetched for as long as it lives.
+//! A long-running process on a small machine can bound it with a [`Budget`]:
+//! once the cache grows past the budget, the least recently used threads are evicted.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, board::Board, cache::Budget};
+//! # async fn budget() -> anyhow::Result<()> {
+//! # let client = Client::new();
+//! let mut board = Board::build(&client, "g").await?;
+//! board.set_budget(Budget::Bytes(64 * 1024 * 1024));
+//! board.on_evict(|id, thread| println!("evicted {} ({} bytes)", id, thread.approx_size()));
+//! # fn shareable<T: Send + Sync>(_: &T) {}
+//! # shareable(&board);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::hash::HashMap;
+use std::{
+    fmt::{self, Debug, Formatter},
+    hash::{BuildHasher, Hash},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+/// The most a cache may hold before entries are evicted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// At most this many entries
+    Entries(usize),
+    /// At most about this many bytes, as estimated by [`Thread::approx_size`](crate::thread::Thread::approx_size)
+    Bytes(usize),
+}
+
+/// A callback receiving every evicted entry.
+type EvictCallback<K, V> = Arc<dyn Fn(K, V) + Send + Sync>;
+
+/// Tracks when the entries of a map were last used and evicts the least recently used ones.
+pub(crate) struct Lru<K, V> {
+    /// The bound of the map, unbounded if `None`
+    budget: Option<Budget>,
+    /// Counts every use, so a higher tick is a more recent use
+    clock: AtomicU64,
+    /// The tick of the last use of every key
+    used: Mutex<HashMap<K, u64>>,
+    /// Called with every evicted entry
+    on_evict: Option<EvictCallback<K, V>>,
+}
+
+impl<K, V> Default for Lru<K, V> {
+    fn default() -> Self {
+        Self {
+            budget: None,
+            clock: AtomicU64::new(0),
+            used: Mutex::new(HashMap::default()),
+            on_evict: None,
+        }
+    }
+}
+
+impl<K, V> Debug for Lru<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lru")
+            .field("budget", &self.budget)
+            .field("on_evict", &self.on_evict.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Lru<K, V> {
+    /// Sets the bound of the map.
+    pub(crate) fn set_budget(&mut self, budget: Option<Budget>) {
+        self.budget = budget;
+    }
+
+    /// Sets the callback receiving every evicted entry.
+    pub(crate) fn on_evict<F: Fn(K, V) + Send + Sync + 'static>(&mut self, callback: F) {
+        self.on_evict = Some(Arc::new(callback));
+    }
+
+    /// Marks a key as just used.
+    pub(crate) fn touch(&self, key: &K) {
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed) + 1;
+        self.used
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key.clone(), tick);
+    }
+
+    /// Removes the least recently used entries of `map` until it fits the budget.
+    ///
+    /// Keys which were never used are evicted first.
+    /// Returns the evicted keys.
+    pub(crate) fn evict<S, F>(
+        &self,
+        map: &mut std::collections::HashMap<K, V, S>,
+        size: F,
+    ) -> Vec<K>
+    where
+        S: BuildHasher,
+        F: Fn(&V) -> usize,
+    {
+        let Some(budget) = self.budget else {
+            return vec![];
+        };
+        let mut used = self.used.lock().unwrap_or_else(PoisonError::into_inner);
+        used.retain(|key, _| map.contains_key(key));
+
+        let mut order: Vec<_> = map
+            .keys()
+            .map(|key| (used.get(key).copied().unwrap_or_default(), key.clone()))
+            .collect();
+        order.sort_unstable_by_key(|(tick, _)| *tick);
+
+        let mut bytes = match budget {
+            Budget::Bytes(_) => map.values().map(&size).sum(),
+            Budget::Entries(_) => 0,
+        };
+        let mut evicted = vec![];
+        for (_, key) in order {
+            let over = match budget {
+                Budget::Entries(max) => map.len() > max,
+                Budget::Bytes(max) => bytes > max,
+            };
+            if !over {
+                break;
+            }
+            if let Some(value) = map.remove(&key) {
+                bytes = bytes.saturating_sub(size(&value));
+                used.remove(&key);
+                if let Some(callback) = &self.on_evict {
+                    callback(key.clone(), value);
+                }
+                evicted.push(key);
+            }
+        }
+        evicted
+    }
+}
@@ -0,0 +1,319 @@
+//! Recording responses to a directory and replaying them offline.
+//!
+//! Requires the `cassette` feature.
+//!
+//! While a [`Client`](crate::Client) records a cassette, every response it receives is saved
+//! along with the URL and the validators of its request (`If-Modified-Since` and
+//! `If-None-Match`). Replaying the cassette later answers the same requests from the
+//! directory without touching the network, which makes integration tests and demos reproducible.
+//!
+//! Responses to the same URL are replayed in the order they were recorded,
+//! so the updates of a thread play out as they did. Once the responses of a URL run out,
+//! the last one is repeated.
+//!
+//! # Storage
+//!
+//! Every response is stored as two files named after a hash of its URL and its position
+//! among the responses to that URL:
+//!
+//! ```text
+//! <dir>/<hash>-<n>.json  the URL, validators, status and headers
+//! <dir>/<hash>-<n>.body  the body as received
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, cassette::Match, thread::Thread};
+//! # async fn cassette() -> anyhow::Result<()> {
+//! let client = Client::new();
+//! client.lock().await.record_cassette("tests/cassettes/g")?;
+//! let thread = Thread::new(&client, "g", 76759434).await?;
+//!
+//! // later, without a connection
+//! let client = Client::new();
+//! client.lock().await.replay_cassette("tests/cassettes/g", Match::Url)?;
+//! let replayed = Thread::new(&client, "g", 76759434).await?;
+//! assert_eq!(thread.op().id(), replayed.op().id());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    http::{
+        header::{IF_MODIFIED_SINCE, IF_NONE_MATCH},
+        HeaderMap, HeaderName, HeaderValue, Response, StatusCode, Url,
+    },
+    logging::error,
+    Error,
+};
+use reqwest::{Request, ResponseBuilderExt};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// How a request is matched to a recorded response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Match {
+    /// By URL alone
+    ///
+    /// The `If-Modified-Since` header of a first request depends on the time it is sent,
+    /// so replays of such requests only match by URL.
+    Url,
+    /// By URL and the `If-Modified-Since` and `If-None-Match` headers
+    UrlAndValidators,
+}
+
+/// The metadata of a recorded response.
+#[derive(Debug, Serialize, Deserialize)]
+struct Recording {
+    /// The URL of the request
+    url: String,
+    /// The validator headers of the request
+    validators: Vec<(String, String)>,
+    /// The status code of the response
+    status: u16,
+    /// The headers of the response
+    headers: Vec<(String, String)>,
+}
+
+/// A recorded response and the file of its body.
+#[derive(Debug)]
+struct Tape {
+    /// The metadata of the response
+    recording: Recording,
+    /// The file of the body
+    body: PathBuf,
+}
+
+/// What a cassette is doing.
+#[derive(Debug)]
+enum Mode {
+    /// Saving responses
+    Record {
+        /// The number of responses saved per URL
+        counts: HashMap<String, usize>,
+    },
+    /// Answering requests from the saved responses
+    Replay {
+        /// How requests are matched
+        matcher: Match,
+        /// The saved responses per URL, in the order they were recorded
+        tapes: HashMap<String, Vec<Tape>>,
+        /// The number of responses played per URL and validators
+        played: HashMap<(String, Vec<(String, String)>), usize>,
+    },
+}
+
+/// A directory of recorded responses, either being recorded or replayed.
+#[derive(Debug)]
+pub struct Cassette {
+    /// The directory of the cassette
+    dir: PathBuf,
+    /// Whether the cassette records or replays
+    mode: Mode,
+}
+
+impl Cassette {
+    /// Starts recording into a directory, creating it if needed.
+    ///
+    /// Responses already in the directory are kept and new ones are recorded after them.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directory cannot be created or read.
+    pub fn record<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let mut counts = HashMap::new();
+        for tape in load(&dir)? {
+            *counts.entry(tape.recording.url).or_default() += 1;
+        }
+        Ok(Self {
+            dir,
+            mode: Mode::Record { counts },
+        })
+    }
+
+    /// Opens a recorded directory for replay.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directory or a recording cannot be read.
+    pub fn replay<P: AsRef<Path>>(dir: P, matcher: Match) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let mut tapes: HashMap<_, Vec<_>> = HashMap::new();
+        for tape in load(&dir)? {
+            tapes
+                .entry(tape.recording.url.clone())
+                .or_default()
+                .push(tape);
+        }
+        Ok(Self {
+            dir,
+            mode: Mode::Replay {
+                matcher,
+                tapes,
+                played: HashMap::new(),
+            },
+        })
+    }
+
+    /// Returns true if the cassette answers requests instead of recording them.
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.mode, Mode::Replay { .. })
+    }
+
+    /// Answers a request from the recorded responses.
+    pub(crate) fn play(&mut self, request: &Request) -> Result<Response, Error> {
+        let Mode::Replay {
+            matcher,
+            tapes,
+            played,
+        } = &mut self.mode
+        else {
+            unreachable!("only a replaying cassette plays");
+        };
+        let url = request.url();
+        let unrecorded = || Error::Unrecorded {
+            url: url.to_string(),
+        };
+        let validators = validators(request.headers());
+        let candidates: Vec<_> = tapes
+            .get(url.as_str())
+            .into_iter()
+            .flatten()
+            .filter(|tape| *matcher == Match::Url || tape.recording.validators == validators)
+            .collect();
+        let key = match matcher {
+            Match::Url => (url.to_string(), vec![]),
+            Match::UrlAndValidators => (url.to_string(), validators),
+        };
+        let played = played.entry(key).or_default();
+        let tape = candidates
+            .get(*played)
+            .or_else(|| candidates.last())
+            .ok_or_else(unrecorded)?;
+        *played += 1;
+
+        let body = fs::read(&tape.body).map_err(|err| {
+            error!("Could not read {}: {err}", tape.body.display());
+            unrecorded()
+        })?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in &tape.recording.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                headers.append(name, value);
+            }
+        }
+        let status = StatusCode::from_u16(tape.recording.status)
+            .ok()
+            .ok_or_else(unrecorded)?;
+        Ok(rebuild(url.clone(), status, headers, body))
+    }
+
+    /// Reads the whole response, saves it and returns an identical response.
+    ///
+    /// Failing to save the response is logged and does not fail the request.
+    pub(crate) async fn record_response(
+        &mut self,
+        url: Url,
+        validators: Vec<(String, String)>,
+        response: Response,
+    ) -> reqwest::Result<Response> {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?.to_vec();
+
+        if let Mode::Record { counts } = &mut self.mode {
+            let count = counts.entry(url.to_string()).or_default();
+            let recording = Recording {
+                url: url.to_string(),
+                validators,
+                status: status.as_u16(),
+                headers: headers
+                    .iter()
+                    .map(|(name, value)| {
+                        let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                        (name.as_str().to_string(), value)
+                    })
+                    .collect(),
+            };
+            let stem = self.dir.join(format!("{:016x}-{count}", fnv(url.as_str())));
+            let saved = serde_json::to_vec_pretty(&recording)
+                .map_err(io::Error::from)
+                .and_then(|json| fs::write(stem.with_extension("json"), json))
+                .and_then(|()| fs::write(stem.with_extension("body"), &body));
+            match saved {
+                Ok(()) => *count += 1,
+                Err(err) => error!("Could not record the response of {url}: {err}"),
+            }
+        }
+
+        Ok(rebuild(url, status, headers, body))
+    }
+}
+
+/// Returns the validator headers of a request, sorted by name.
+pub(crate) fn validators(headers: &HeaderMap) -> Vec<(String, String)> {
+    let mut validators: Vec<_> = [IF_MODIFIED_SINCE, IF_NONE_MATCH]
+        .iter()
+        .filter_map(|name| {
+            let value = headers.get(name)?.to_str().ok()?;
+            Some((name.as_str().to_string(), value.to_string()))
+        })
+        .collect();
+    validators.sort();
+    validators
+}
+
+/// Reads every recorded response of a directory, in the order they were recorded.
+fn load(dir: &Path) -> io::Result<Vec<Tape>> {
+    let mut tapes = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let position = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.rsplit('-').next())
+            .and_then(|n| n.parse::<usize>().ok())
+            .unwrap_or_default();
+        let recording: Recording = serde_json::from_slice(&fs::read(&path)?)?;
+        tapes.push((
+            position,
+            Tape {
+                recording,
+                body: path.with_extension("body"),
+            },
+        ));
+    }
+    tapes.sort_by_key(|(position, _)| *position);
+    Ok(tapes.into_iter().map(|(_, tape)| tape).collect())
+}
+
+/// Builds a response out of its parts.
+fn rebuild(url: Url, status: StatusCode, headers: HeaderMap, body: Vec<u8>) -> Response {
+    let mut response = http::Response::builder()
+        .url(url)
+        .body(body)
+        .unwrap_or_default();
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    Response::from(response)
+}
+
+/// Hashes a URL into a stable file name with 64-bit FNV-1a.
+fn fnv(url: &str) -> u64 {
+    url.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
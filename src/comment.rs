@@ -0,0 +1,587 @@
+//! Parsing of the HTML escaped comments found in [`Post`]s.
+//!
+//! 4chan sends comments as an HTML fragment.
+//! Quotes, quotelinks and cross-board links are all plain tags in that fragment:
+//!
+//! ```text
+//! <a href="#p76759455" class="quotelink">&gt;&gt;76759455</a>
+//! <a href="/g/thread/76759434#p76759500" class="quotelink">&gt;&gt;76759500</a>
+//! <a href="//boards.4chan.org/a/thread/2220123#p2220145" class="quotelink">&gt;&gt;&gt;/a/2220145</a>
+//! <a href="//boards.4chan.org/a/" class="quotelink">&gt;&gt;&gt;/a/</a>
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! use dot4ch::comment::{links, Link};
+//!
+//! let com = r#"<a href="//boards.4chan.org/a/thread/2220123#p2220145" class="quotelink">&gt;&gt;&gt;/a/2220145</a><br>see <a href="//boards.4chan.org/g/" class="quotelink">&gt;&gt;&gt;/g/</a>"#;
+//!
+//! assert_eq!(
+//!     links(com),
+//!     vec![
+//!         Link::CrossBoard {
+//!             board: "a".to_string(),
+//!             post: 2220145,
+//!             thread: Some(2220123)
+//!         },
+//!         Link::Board("g".to_string()),
+//!     ]
+//! );
+//! ```
+
+use crate::{
+    board::Board,
+    post::Post,
+    thread::{Source, Thread},
+    Dot4chClient, PostNo, Result,
+};
+use std::convert::TryFrom;
+
+/// A link found inside of a comment.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Link {
+    /// A `>>12345` quotelink to a post on the same board.
+    ///
+    /// `thread` is `Some` when the post lives in a different thread.
+    Quote {
+        /// The quoted post number
+        post: u32,
+        /// The thread the quoted post lives in, if it is not the current thread
+        thread: Option<u32>,
+    },
+    /// A `>>>/g/` link to the index of a board.
+    Board(String),
+    /// A `>>>/g/12345` link to a post on another board.
+    CrossBoard {
+        /// The board the post is on
+        board: String,
+        /// The linked post number
+        post: u32,
+        /// The thread the post lives in.
+        ///
+        /// This is `None` for dead links since 4chan no longer knows the thread.
+        thread: Option<u32>,
+    },
+}
+
+impl Link {
+    /// Returns the board of the link.
+    ///
+    /// For [`Link::Quote`]s this is the board the comment was posted on.
+    pub fn board<'a>(&'a self, current: &'a str) -> &'a str {
+        match self {
+            Self::Quote { .. } => current,
+            Self::Board(board) | Self::CrossBoard { board, .. } => board,
+        }
+    }
+
+    /// Returns the linked post number, if the link points to a post.
+    pub fn post(&self) -> Option<u32> {
+        match self {
+            Self::Quote { post, .. } | Self::CrossBoard { post, .. } => Some(*post),
+            Self::Board(_) => None,
+        }
+    }
+
+    /// Fetches the [`Thread`] that the link points to.
+    ///
+    /// `board` is the board the comment was posted on.
+    ///
+    /// Returns `None` for board links and for quotes to posts in the current thread.
+    ///
+    /// Threads which are gone from 4chan are fetched from the archive registered
+    /// for their board, see [`Thread::new_or_archived`].
+    /// Dead cross-board links do not carry their thread, so it is looked up in that archive.
+    /// Without one, the linked post is assumed to be an OP.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to fetch the [`Thread`] fails.
+    pub async fn resolve(&self, client: &Dot4chClient, board: &str) -> Result<Option<Thread>> {
+        let Some((board, thread)) = self.target(client, board).await? else {
+            return Ok(None);
+        };
+        let (thread, _) = Thread::new_or_archived(client, &board, thread).await?;
+        Ok(Some(thread))
+    }
+
+    /// Fetches the post that the link points to, along with its thread and where it came from.
+    ///
+    /// `board` is the board the comment was posted on.
+    /// The thread is found as by [`Link::resolve`]. To look in a [`Board`] cache first,
+    /// see [`Board::resolve`].
+    ///
+    /// Returns `None` for board links, for quotes to posts in the current thread
+    /// and for posts which are not in their thread anymore.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to fetch the [`Thread`] fails.
+    pub async fn resolve_post(
+        &self,
+        client: &Dot4chClient,
+        board: &str,
+    ) -> Result<Option<ResolvedPost>> {
+        let (Some(post), Some((board, thread))) = (self.post(), self.target(client, board).await?)
+        else {
+            return Ok(None);
+        };
+        let (thread, source) = Thread::new_or_archived(client, &board, thread).await?;
+        Ok(ResolvedPost::find(
+            board,
+            &thread,
+            post,
+            Provenance::from(source),
+        ))
+    }
+
+    /// Returns the board and the thread number the link points to.
+    ///
+    /// Returns `None` for board links and for quotes to posts in the current thread.
+    async fn target(&self, client: &Dot4chClient, board: &str) -> Result<Option<(String, u32)>> {
+        let (board, thread) = match self {
+            Self::Quote {
+                thread: Some(thread),
+                ..
+            } => (board, *thread),
+            Self::CrossBoard {
+                board,
+                thread: Some(thread),
+                ..
+            } => (board.as_str(), *thread),
+            Self::CrossBoard {
+                board,
+                post,
+                thread: None,
+            } => {
+                let resolver = client.lock().await.resolver(board);
+                let thread = match resolver {
+                    Some(resolver) => resolver.thread_of(client, board, *post).await?,
+                    None => *post,
+                };
+                (board.as_str(), thread)
+            }
+            Self::Quote { thread: None, .. } | Self::Board(_) => return Ok(None),
+        };
+        Ok(Some((board.to_string(), thread)))
+    }
+}
+
+/// Where a [`ResolvedPost`] was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Provenance {
+    /// In a thread of a [`Board`] cache, without a request
+    Cache,
+    /// In a thread still live on 4chan
+    Live,
+    /// In a thread in the board's archive on 4chan
+    Archive,
+    /// In a thread gone from 4chan, fetched from an external archive
+    External,
+}
+
+impl From<Source> for Provenance {
+    fn from(source: Source) -> Self {
+        match source {
+            Source::Live => Self::Live,
+            Source::Archive => Self::Archive,
+            Source::External => Self::External,
+        }
+    }
+}
+
+/// The post a [`Link`] points to.
+#[derive(Debug, Clone)]
+pub struct ResolvedPost {
+    /// The board of the post
+    pub board: String,
+    /// The post
+    pub post: Post,
+    /// The thread of the post
+    pub thread: Thread,
+    /// Where the post was found
+    pub provenance: Provenance,
+}
+
+impl ResolvedPost {
+    /// Finds the post `no` in a thread.
+    fn find(board: String, thread: &Thread, no: u32, provenance: Provenance) -> Option<Self> {
+        let post = if thread.op().id() == no {
+            thread.op()
+        } else {
+            thread.find(no)?
+        };
+        Some(Self {
+            board,
+            post: post.clone(),
+            thread: thread.clone(),
+            provenance,
+        })
+    }
+}
+
+impl Board {
+    /// Finds the post a link in a comment on this board points to.
+    ///
+    /// Posts on this board are looked up in the cached threads first.
+    /// Anything else is fetched as by [`Link::resolve_post`], from 4chan or from
+    /// the external archive of its board.
+    ///
+    /// Returns `None` for board links and for posts which cannot be found,
+    /// such as deleted ones or quotes to posts of the current thread which are not cached.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, board::Board};
+    /// # async fn follow() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let board = Board::build(&client, "g").await?;
+    /// let (_, post) = board.find_post(76759434).expect("a cached post");
+    /// for link in post.links() {
+    ///     if let Some(found) = board.resolve(&link).await? {
+    ///         println!("/{}/{} from {:?}", found.board, found.post.id(), found.provenance);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to fetch the [`Thread`] fails.
+    pub async fn resolve(&self, link: &Link) -> Result<Option<ResolvedPost>> {
+        let Some(no) = link.post() else {
+            return Ok(None);
+        };
+        if link.board(self.board()) == self.board() {
+            if let Some((thread, _)) = self.find_post(no) {
+                let board = self.board().to_string();
+                return Ok(ResolvedPost::find(board, thread, no, Provenance::Cache));
+            }
+        }
+        link.resolve_post(&self.client, self.board()).await
+    }
+}
+
+/// Returns all the links found in a comment, in order of appearance.
+pub fn links(com: &str) -> Vec<Link> {
+    let mut found = vec![];
+    let mut tokens = Tokens::new(com);
+    while let Some(token) = tokens.next() {
+        let Token::Open { name, attrs } = token else {
+            continue;
+        };
+        let class = attr(attrs, "class").unwrap_or_default();
+        let is_link = (name.eq_ignore_ascii_case("a") && class == "quotelink")
+            || (name.eq_ignore_ascii_case("span") && class == "deadlink");
+        if !is_link {
+            continue;
+        }
+        let text = tokens.text_until_close(name);
+        let href = attr(attrs, "href").unwrap_or_default();
+        if let Some(link) = parse_link(&text, href) {
+            found.push(link);
+        }
+    }
+    found
+}
+
+/// Builds a [`Link`] from the text and the `href` of a quotelink.
+fn parse_link(text: &str, href: &str) -> Option<Link> {
+    let text = text.replace("&gt;", ">");
+    if let Some(rest) = text.strip_prefix(">>>/") {
+        let mut parts = rest.splitn(2, '/');
+        let board = parts.next().filter(|b| !b.is_empty())?.to_string();
+        let post = parts.next().unwrap_or_default();
+        if post.is_empty() {
+            return Some(Link::Board(board));
+        }
+        return Some(Link::CrossBoard {
+            board,
+            post: post.parse().ok()?,
+            thread: href_thread(href),
+        });
+    }
+    let post = text.strip_prefix(">>")?.trim().parse().ok()?;
+    Some(Link::Quote {
+        post,
+        thread: href_thread(href),
+    })
+}
+
+/// Returns the thread number out of a `.../thread/<no>#p<no>` href.
+fn href_thread(href: &str) -> Option<u32> {
+    let (_, rest) = href.split_at(href.find("/thread/")? + "/thread/".len());
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
+
+/// Returns a well-formed HTML fragment of the comment which is safe to embed in a web page.
+///
+/// Only the following tags are kept:
+/// - `<span class="quote">` for greentext
+/// - `<a class="quotelink">` when the `href` points to 4chan
+/// - `<s>` for spoilers
+/// - `<pre class="prettyprint">` for code
+/// - `<br>` and `<wbr>`
+///
+/// Any other tag is dropped while keeping its text,
+/// stray closing tags are removed and unclosed tags are closed at the end.
+///
+/// # Example
+///
+/// ```
+/// use dot4ch::comment::sanitize;
+///
+/// let com = r#"<span class="quote" onclick="x()">&gt;implying<br><script>alert(1)</script><a href="https://evil.com" class="quotelink">&gt;&gt;1</a>"#;
+///
+/// assert_eq!(
+///     sanitize(com),
+///     r#"<span class="quote">&gt;implying<br>alert(1)&gt;&gt;1</span>"#
+/// );
+/// ```
+pub fn sanitize(com: &str) -> String {
+    let mut out = String::with_capacity(com.len());
+    // open tags with whether they were kept or not
+    let mut open: Vec<(&str, bool)> = vec![];
+    for token in Tokens::new(com) {
+        match token {
+            Token::Text(text) => escape_text(text, &mut out),
+            Token::Open { name, attrs } => {
+                if name.eq_ignore_ascii_case("br") || name.eq_ignore_ascii_case("wbr") {
+                    out.push('<');
+                    out.push_str(&name.to_ascii_lowercase());
+                    out.push('>');
+                    continue;
+                }
+                let kept = allowed_tag(name, attrs);
+                if let Some(tag) = &kept {
+                    out.push_str(tag);
+                }
+                open.push((name, kept.is_some()));
+            }
+            Token::Close(name) => {
+                let Some(pos) = open.iter().rposition(|(n, _)| n.eq_ignore_ascii_case(name)) else {
+                    continue;
+                };
+                for (name, kept) in open.drain(pos..).rev() {
+                    close_tag(name, kept, &mut out);
+                }
+            }
+        }
+    }
+    for (name, kept) in open.into_iter().rev() {
+        close_tag(name, kept, &mut out);
+    }
+    out
+}
+
+/// Returns the opening tag that [`sanitize`] emits for a tag, or `None` if it is dropped.
+fn allowed_tag(name: &str, attrs: &str) -> Option<String> {
+    let class = attr(attrs, "class").unwrap_or_default();
+    match name.to_ascii_lowercase().as_str() {
+        "s" => Some("<s>".to_string()),
+        "span" if class == "quote" => Some(r#"<span class="quote">"#.to_string()),
+        "pre" if class == "prettyprint" => Some(r#"<pre class="prettyprint">"#.to_string()),
+        "pre" => Some("<pre>".to_string()),
+        "a" if class == "quotelink" => {
+            let href = attr(attrs, "href").filter(|href| safe_href(href))?;
+            Some(format!(r#"<a href="{href}" class="quotelink">"#))
+        }
+        _ => None,
+    }
+}
+
+/// Writes the closing tag of a tag if [`sanitize`] kept it.
+fn close_tag(name: &str, kept: bool, out: &mut String) {
+    if kept {
+        out.push_str("</");
+        out.push_str(&name.to_ascii_lowercase());
+        out.push('>');
+    }
+}
+
+/// Returns true if a quotelink `href` stays on 4chan.
+fn safe_href(href: &str) -> bool {
+    let path = match href.strip_prefix("//") {
+        Some(rest) => match rest.split_once('/') {
+            Some(("boards.4chan.org" | "boards.4channel.org", path)) => path,
+            _ => return false,
+        },
+        None if href.starts_with('/') || href.starts_with('#') => href,
+        None => return false,
+    };
+    path.chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/#._-".contains(c))
+}
+
+/// Writes text to `out`, escaping anything that is not already an HTML entity.
+fn escape_text(text: &str, out: &mut String) {
+    for (idx, c) in text.char_indices() {
+        match c {
+            '&' if is_entity(&text[idx..]) => out.push('&'),
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Returns true if `text` starts with an HTML entity such as `&gt;` or `&#039;`.
+fn is_entity(text: &str) -> bool {
+    let body = &text[1..];
+    let end = match body.find(';') {
+        Some(end) if end > 0 => end,
+        _ => return false,
+    };
+    let name = body[..end].strip_prefix('#').unwrap_or(&body[..end]);
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+impl Post {
+    /// Returns all the quotelinks and cross-board links in the comment.
+    ///
+    /// See [`links`].
+    pub fn links(&self) -> Vec<Link> {
+        links(self.content())
+    }
+
+    /// Returns the comment with only safe, well-formed HTML.
+    ///
+    /// See [`sanitize`].
+    pub fn sanitized_content(&self) -> String {
+        sanitize(self.content())
+    }
+}
+
+impl Thread {
+    /// Returns every post quoting the post `no` in this thread, in thread order.
+    ///
+    /// These are the replies the site lists under a post. A post quoting `no` several times
+    /// is returned once.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, thread::Thread};
+    /// # async fn quotes() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let thread = Thread::new(&client, "g", 76759434).await?;
+    /// let replies: Vec<_> = thread.quotes_of(thread.op().id()).map(|post| post.id()).collect();
+    /// println!("the OP got {} replies", replies.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn quotes_of(&self, no: impl Into<PostNo>) -> impl Iterator<Item = &Post> {
+        let quoting = u32::try_from(no.into().get())
+            .ok()
+            .and_then(|no| self.quote_index().get(&no));
+        quoting
+            .into_iter()
+            .flatten()
+            .filter_map(move |&idx| self.post_at(idx))
+    }
+}
+
+/// A piece of a comment's HTML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Token<'a> {
+    /// Text between tags, still HTML escaped.
+    Text(&'a str),
+    /// An opening tag, such as `<a href="#p1">`
+    Open {
+        /// The tag name
+        name: &'a str,
+        /// Everything after the tag name
+        attrs: &'a str,
+    },
+    /// A closing tag, such as `</a>`
+    Close(&'a str),
+}
+
+/// Splits a comment into [`Token`]s.
+#[derive(Debug, Clone)]
+pub(crate) struct Tokens<'a> {
+    /// The rest of the comment
+    rest: &'a str,
+}
+
+impl<'a> Tokens<'a> {
+    /// Starts tokenizing a comment.
+    pub(crate) fn new(com: &'a str) -> Self {
+        Self { rest: com }
+    }
+
+    /// Collects the text up to the closing tag of `name`.
+    fn text_until_close(&mut self, name: &str) -> String {
+        let mut text = String::new();
+        for token in self {
+            match token {
+                Token::Text(t) => text.push_str(t),
+                Token::Close(close) if close.eq_ignore_ascii_case(name) => break,
+                _ => {}
+            }
+        }
+        text
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        if !self.rest.starts_with('<') {
+            let end = self.rest.find('<').unwrap_or(self.rest.len());
+            let (text, rest) = self.rest.split_at(end);
+            self.rest = rest;
+            return Some(Token::Text(text));
+        }
+        let Some(end) = self.rest.find('>') else {
+            // an unterminated tag is just text
+            let text = self.rest;
+            self.rest = "";
+            return Some(Token::Text(text));
+        };
+        let tag = &self.rest[1..end];
+        self.rest = &self.rest[end + 1..];
+        if let Some(name) = tag.strip_prefix('/') {
+            return Some(Token::Close(name.trim()));
+        }
+        let tag = tag.trim_end_matches('/').trim();
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        Some(Token::Open {
+            name: &tag[..name_end],
+            attrs: tag[name_end..].trim(),
+        })
+    }
+}
+
+/// Returns the value of an attribute from the attributes of a tag.
+pub(crate) fn attr<'a>(attrs: &'a str, key: &str) -> Option<&'a str> {
+    let mut rest = attrs;
+    while let Some(eq) = rest.find('=') {
+        let name = rest[..eq].trim();
+        let after = rest[eq + 1..].trim_start();
+        let (value, next) = if let Some(quote @ ('"' | '\'')) = after.chars().next() {
+            let inner = &after[1..];
+            let close = inner.find(quote).unwrap_or(inner.len());
+            (&inner[..close], inner.get(close + 1..).unwrap_or_default())
+        } else {
+            let close = after.find(char::is_whitespace).unwrap_or(after.len());
+            (&after[..close], &after[close..])
+        };
+        if name.eq_ignore_ascii_case(key) {
+            return Some(value);
+        }
+        rest = next;
+    }
+    None
+}
@@ -0,0 +1,151 @@
+//! Detecting reposted files by their MD5 hash.
+//!
+//! A [`DedupIndex`] remembers where every file hash was posted,
+//! so a new post can be checked against everything ingested before.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, dedup::DedupIndex, persist::Persist, thread::Thread};
+//! # async fn dedup() -> anyhow::Result<()> {
+//! # let client = Client::new();
+//! let mut index = DedupIndex::new();
+//!
+//! let thread = Thread::new(&client, "g", 76759434).await?;
+//! let reposts = index.ingest_thread(&thread);
+//! println!("{} reposted files", reposts);
+//!
+//! if let Some(md5) = thread.op().md5hash() {
+//!     for sighting in index.sightings(md5) {
+//!         println!("seen in /{}/{}#p{}", sighting.board, sighting.thread, sighting.post);
+//!     }
+//! }
+//!
+//! index.save_json("dedup.json")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    board::Board,
+    hash::HashMap,
+    persist::{read, write, Format, Persist},
+    post::Post,
+    thread::Thread,
+    Dot4chClient, Result,
+};
+use serde::{Deserialize, Serialize};
+use std::{iter, path::Path};
+
+/// A post a file was seen in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Sighting {
+    /// The board of the post
+    pub board: String,
+    /// The OP number of the thread of the post
+    pub thread: u32,
+    /// The post number
+    pub post: u32,
+    /// UNIX timestamp of the post
+    pub time: i64,
+}
+
+/// An index of the posts every file hash was seen in.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DedupIndex {
+    /// The sightings of every MD5 hash, in the order they were ingested
+    hashes: HashMap<String, Vec<Sighting>>,
+}
+
+impl DedupIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the file of a post to the index.
+    ///
+    /// Returns true if the file was seen in another post before.
+    /// Posts without a file and posts which were already ingested are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{dedup::DedupIndex, post::Post};
+    /// let mut index = DedupIndex::new();
+    /// // a post without a file
+    /// assert!(!index.insert("g", 1, &Post::default()));
+    /// assert!(index.is_empty());
+    /// ```
+    pub fn insert(&mut self, board: &str, thread: u32, post: &Post) -> bool {
+        let Some(md5) = post.md5hash() else {
+            return false;
+        };
+        let sightings = self.hashes.entry(md5.to_string()).or_default();
+        if sightings
+            .iter()
+            .any(|seen| seen.board == board && seen.post == post.id())
+        {
+            return false;
+        }
+        sightings.push(Sighting {
+            board: board.to_string(),
+            thread,
+            post: post.id(),
+            time: post.post_time(),
+        });
+        sightings.len() > 1
+    }
+
+    /// Adds the files of every post in a thread.
+    ///
+    /// Returns the number of files which were seen before.
+    pub fn ingest_thread(&mut self, thread: &Thread) -> usize {
+        let id = thread.op().id();
+        iter::once(thread.op())
+            .chain(&thread[..])
+            .filter(|post| self.insert(thread.board(), id, post))
+            .count()
+    }
+
+    /// Adds the files of every cached thread of a board.
+    ///
+    /// Returns the number of files which were seen before.
+    pub fn ingest_board(&mut self, board: &Board) -> usize {
+        board
+            .iter()
+            .map(|(_, thread)| self.ingest_thread(thread))
+            .sum()
+    }
+
+    /// Returns every post a file was seen in, in the order they were ingested.
+    pub fn sightings(&self, md5: &str) -> &[Sighting] {
+        self.hashes.get(md5).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the post a file was first ingested from.
+    pub fn first_seen(&self, md5: &str) -> Option<&Sighting> {
+        self.sightings(md5).first()
+    }
+
+    /// Returns the number of distinct files in the index.
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Returns true if the index holds no files.
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+}
+
+impl Persist for DedupIndex {
+    fn save<P: AsRef<Path>>(&self, path: P, format: Format) -> Result<()> {
+        write(self, path, format)
+    }
+
+    /// Reads an index, the client is not used.
+    fn load<P: AsRef<Path>>(_client: &Dot4chClient, path: P, format: Format) -> Result<Self> {
+        read(path, format)
+    }
+}
@@ -0,0 +1,339 @@
+//! Differences between two snapshots of the same [`Thread`], [`Catalog`], [`Archive`] or [`Boards`].
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, Update, thread::Thread};
+//! # async fn diff() -> anyhow::Result<()> {
+//! # let client = Client::new();
+//! let older = Thread::new(&client, "g", 76759434).await?;
+//! let newer = older.clone().update().await?;
+//!
+//! let diff = newer.diff(&older);
+//! println!("{} new posts", diff.added.len());
+//! if let Some(closed) = diff.closed {
+//!     println!("closed went from {} to {}", closed.old, closed.new);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    archive::Archive,
+    boards::{BoardInfo, Boards, Cooldowns},
+    catalog::{Catalog, CatalogThread},
+    post::Post,
+    thread::Thread,
+};
+use std::collections::{HashMap, HashSet};
+
+/// A value that changed between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Change<T> {
+    /// The value in the older snapshot
+    pub old: T,
+    /// The value in the newer snapshot
+    pub new: T,
+}
+
+impl<T: PartialEq> Change<T> {
+    /// Returns a [`Change`] if the values differ.
+    fn between(old: T, new: T) -> Option<Self> {
+        if old == new {
+            None
+        } else {
+            Some(Self { old, new })
+        }
+    }
+}
+
+/// The difference between two snapshots of a [`Thread`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThreadDiff {
+    /// Post numbers which are only in the newer snapshot
+    pub added: Vec<u32>,
+    /// Post numbers which are only in the older snapshot
+    pub removed: Vec<u32>,
+    /// Change in the closed status of the OP
+    pub closed: Option<Change<bool>>,
+    /// Change in the sticky status of the OP
+    pub sticky: Option<Change<bool>>,
+    /// Change in the bump limit status of the OP
+    pub bump_limit: Option<Change<bool>>,
+    /// Change in the image limit status of the OP
+    pub image_limit: Option<Change<bool>>,
+    /// Change in the archived status of the OP
+    pub archived: Option<Change<bool>>,
+    /// Change in the number of replies
+    pub replies: Option<Change<usize>>,
+}
+
+impl ThreadDiff {
+    /// Returns true if nothing changed between the snapshots.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Thread {
+    /// Returns what changed between `older` and this snapshot of the thread.
+    pub fn diff(&self, older: &Self) -> ThreadDiff {
+        let (op, old_op) = (self.op(), older.op());
+        ThreadDiff {
+            added: self[..]
+                .iter()
+                .map(Post::id)
+                .filter(|&id| older.find(id).is_none())
+                .collect(),
+            removed: older[..]
+                .iter()
+                .map(Post::id)
+                .filter(|&id| self.find(id).is_none())
+                .collect(),
+            closed: Change::between(old_op.closed(), op.closed()),
+            sticky: Change::between(old_op.sticky(), op.sticky()),
+            bump_limit: Change::between(old_op.bump_limit(), op.bump_limit()),
+            image_limit: Change::between(old_op.image_limit(), op.image_limit()),
+            archived: Change::between(old_op.archived(), op.archived()),
+            replies: Change::between(older[..].len(), self[..].len()),
+        }
+    }
+}
+
+/// The difference between two snapshots of a [`Catalog`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CatalogDiff {
+    /// Thread numbers which are only in the newer snapshot
+    pub added: Vec<u32>,
+    /// Thread numbers which are only in the older snapshot
+    pub removed: Vec<u32>,
+    /// Threads in both snapshots whose `last_modified` changed
+    pub modified: Vec<u32>,
+    /// Threads in both snapshots whose reply count changed
+    pub replies: Vec<(u32, Change<u32>)>,
+}
+
+impl CatalogDiff {
+    /// Returns true if nothing changed between the snapshots.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns the threads which are new or changed, the ones worth fetching again.
+    ///
+    /// New threads come first. A thread is changed if its `last_modified` or its reply count differs.
+    pub fn changed(&self) -> Vec<u32> {
+        let mut changed: Vec<_> = self
+            .added
+            .iter()
+            .chain(&self.modified)
+            .chain(self.replies.iter().map(|(id, _)| id))
+            .copied()
+            .collect();
+        let mut seen = HashSet::new();
+        changed.retain(|id| seen.insert(*id));
+        changed
+    }
+}
+
+impl Catalog {
+    /// Returns what changed between `older` and this snapshot of the catalog.
+    ///
+    /// This is the cheap way to poll a board: fetch the catalog, compare it to the previous
+    /// one and only fetch the threads in [`CatalogDiff::changed`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, Update, catalog::Catalog, thread::Thread};
+    /// # async fn poll() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let mut catalog = Catalog::new(&client, "g").await?;
+    /// loop {
+    ///     let previous = catalog.clone();
+    ///     catalog = catalog.update().await?;
+    ///     let diff = catalog.diff(&previous);
+    ///     for id in diff.changed() {
+    ///         let thread = Thread::new(&client, "g", id).await?;
+    ///     }
+    ///     println!("{} threads fell off", diff.removed.len());
+    /// }
+    /// # }
+    /// ```
+    pub fn diff(&self, older: &Self) -> CatalogDiff {
+        let old: HashMap<_, _> = older.threads().map(|t| (t.id(), t)).collect();
+        let new: HashSet<_> = self.threads().map(CatalogThread::id).collect();
+
+        let mut diff = CatalogDiff {
+            removed: older
+                .threads()
+                .map(CatalogThread::id)
+                .filter(|id| !new.contains(id))
+                .collect(),
+            ..CatalogDiff::default()
+        };
+        for thread in self.threads() {
+            let Some(previous) = old.get(&thread.id()) else {
+                diff.added.push(thread.id());
+                continue;
+            };
+            if previous.last_modified() != thread.last_modified() {
+                diff.modified.push(thread.id());
+            }
+            if let Some(change) = Change::between(previous.replies(), thread.replies()) {
+                diff.replies.push((thread.id(), change));
+            }
+        }
+        diff
+    }
+}
+
+/// The difference between two snapshots of an [`Archive`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchiveDiff {
+    /// Thread numbers which got archived since the older snapshot, oldest first
+    pub added: Vec<u32>,
+    /// Thread numbers which were deleted from the archive since the older snapshot, oldest first
+    pub removed: Vec<u32>,
+}
+
+impl ArchiveDiff {
+    /// Returns true if the archive did not change between the snapshots.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Compares two sorted lists of archived thread numbers.
+    pub(crate) fn between(older: &[u32], newer: &[u32]) -> Self {
+        let only_in = |a: &[u32], b: &[u32]| {
+            a.iter()
+                .copied()
+                .filter(|id| b.binary_search(id).is_err())
+                .collect()
+        };
+        Self {
+            added: only_in(newer, older),
+            removed: only_in(older, newer),
+        }
+    }
+}
+
+impl Archive {
+    /// Returns what changed between `older` and this snapshot of the archive.
+    pub fn diff(&self, older: &Self) -> ArchiveDiff {
+        ArchiveDiff::between(older.threads(), self.threads())
+    }
+}
+
+/// The difference between two snapshots of the settings of a board.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BoardInfoDiff {
+    /// Change in the readable title
+    pub title: Option<Change<String>>,
+    /// Change in whether the board is worksafe
+    pub worksafe: Option<Change<bool>>,
+    /// Change in the number of threads on a single index page
+    pub per_page: Option<Change<u32>>,
+    /// Change in the number of index pages
+    pub pages: Option<Change<u32>>,
+    /// Change in the largest allowed file size in bytes
+    pub max_filesize: Option<Change<u64>>,
+    /// Change in the largest allowed comment length
+    pub max_comment_chars: Option<Change<u32>>,
+    /// Change in the bump limit
+    pub bump_limit: Option<Change<u32>>,
+    /// Change in the image limit
+    pub image_limit: Option<Change<u32>>,
+    /// Change in whether the board has an archive
+    pub is_archived: Option<Change<bool>>,
+    /// Change in the seconds to wait between posts
+    pub cooldowns: Option<Change<Cooldowns>>,
+}
+
+impl BoardInfoDiff {
+    /// Returns true if no setting changed between the snapshots.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl BoardInfo {
+    /// Returns which settings changed between `older` and this snapshot of the board.
+    pub fn diff(&self, older: &Self) -> BoardInfoDiff {
+        BoardInfoDiff {
+            title: Change::between(older.title(), self.title()).map(|change| Change {
+                old: change.old.to_string(),
+                new: change.new.to_string(),
+            }),
+            worksafe: Change::between(older.worksafe(), self.worksafe()),
+            per_page: Change::between(older.per_page(), self.per_page()),
+            pages: Change::between(older.pages(), self.pages()),
+            max_filesize: Change::between(older.max_filesize(), self.max_filesize()),
+            max_comment_chars: Change::between(older.max_comment_chars(), self.max_comment_chars()),
+            bump_limit: Change::between(older.bump_limit(), self.bump_limit()),
+            image_limit: Change::between(older.image_limit(), self.image_limit()),
+            is_archived: Change::between(older.is_archived(), self.is_archived()),
+            cooldowns: Change::between(older.cooldowns(), self.cooldowns()),
+        }
+    }
+}
+
+/// The difference between two snapshots of the list of [`Boards`].
+///
+/// # Example
+///
+/// ```
+/// # use dot4ch::{Client, boards::Boards};
+/// # async fn boards_diff(older: Boards) -> anyhow::Result<()> {
+/// # let client = Client::new();
+/// let newer = Boards::new(&client).await?;
+/// for (board, diff) in newer.diff(&older).changed {
+///     if let Some(size) = diff.max_filesize {
+///         println!("/{}/ now takes files up to {} bytes", board, size.new);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BoardsDiff {
+    /// Board codes which are only in the newer snapshot
+    pub added: Vec<String>,
+    /// Board codes which are only in the older snapshot
+    pub removed: Vec<String>,
+    /// Boards in both snapshots whose settings changed
+    pub changed: Vec<(String, BoardInfoDiff)>,
+}
+
+impl BoardsDiff {
+    /// Returns true if nothing changed between the snapshots.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Boards {
+    /// Returns what changed between `older` and this snapshot of the list of boards.
+    pub fn diff(&self, older: &Self) -> BoardsDiff {
+        let mut diff = BoardsDiff {
+            removed: older
+                .boards()
+                .iter()
+                .filter(|info| !self.contains(info.board()))
+                .map(|info| info.board().to_string())
+                .collect(),
+            ..BoardsDiff::default()
+        };
+        for info in self.boards() {
+            let Some(previous) = older.get(info.board()) else {
+                diff.added.push(info.board().to_string());
+                continue;
+            };
+            let changes = info.diff(previous);
+            if !changes.is_empty() {
+                diff.changed.push((info.board().to_string(), changes));
+            }
+        }
+        diff
+    }
+}
@@ -0,0 +1,398 @@
+//! The errors of requests to 4chan.
+//!
+//! Functions of the crate return an [`anyhow::Error`]. When a request to 4chan fails,
+//! that error carries an [`Error`], which can be matched on after [`Error::find`].
+//!
+//! Errors of fetches are wrapped in a context naming the operation and the URL,
+//! such as `updating thread /g/12345 (https://a.4cdn.org/g/thread/12345.json)`.
+//! Format them with `{:#}` to print the whole chain.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, Error, thread::Thread};
+//! # async fn error() {
+//! # let client = Client::new();
+//! match Thread::new(&client, "g", 1).await {
+//!     Ok(thread) => println!("{}", thread),
+//!     Err(err) => match Error::find(&err) {
+//!         Some(Error::NotFound { board, id }) => println!("/{}/{:?} is gone", board, id),
+//!         Some(Error::Timeout(_)) => println!("4chan is slow"),
+//!         _ => println!("{:#}", err),
+//!     },
+//! }
+//! # }
+//! ```
+
+use crate::http::{
+    self,
+    header::{CONTENT_TYPE, RETRY_AFTER},
+    HeaderMap, Response, StatusCode,
+};
+use chrono::{DateTime, Utc};
+use std::{
+    fmt::{self, Display, Formatter},
+    time::Duration,
+};
+
+/// The most bytes of an error response kept in [`Error::UnexpectedStatus`].
+pub const BODY_PREVIEW: usize = 512;
+
+/// A failed request to 4chan.
+///
+/// More variants may be added, so matches need a wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
+#[allow(variant_size_differences)]
+pub enum Error {
+    /// No connection to 4chan could be made, such as on DNS or TLS failures
+    Connect(http::Error),
+    /// The request timed out
+    Timeout(http::Error),
+    /// The body of the response was cut off
+    Body(http::Error),
+    /// The body of the response is not the expected JSON
+    Decode(http::Error),
+    /// Any other failure to send the request, such as a redirect loop
+    Http(http::Error),
+    /// 4chan answered with an error status code
+    ///
+    /// The headers and the start of the body tell a Cloudflare challenge page
+    /// apart from an error of the API.
+    UnexpectedStatus {
+        /// The status code of the response
+        status: StatusCode,
+        /// The headers of the response
+        headers: HeaderMap,
+        /// The first [`BODY_PREVIEW`] bytes of the body, if it was read
+        body: Option<String>,
+    },
+    /// The thread or board does not exist, or the thread fell off the board
+    NotFound {
+        /// The board of the request
+        board: String,
+        /// The thread of the request, `None` for board-wide requests such as the catalog
+        id: Option<u32>,
+    },
+    /// The board is not in the list of boards, see [`Client::set_validate_boards`](crate::Client::set_validate_boards)
+    UnknownBoard {
+        /// The board of the request
+        board: String,
+    },
+    /// An update was attempted before its 10 second cooldown passed
+    Cooldown {
+        /// The time until the update is allowed
+        remaining: Duration,
+    },
+    /// 4chan answered with `429 Too Many Requests`
+    RateLimited {
+        /// The wait 4chan asked for in its `Retry-After` header
+        retry_after: Option<Duration>,
+    },
+    /// No recorded response matches the request while replaying a cassette,
+    /// see [`Client::replay_cassette`](crate::Client::replay_cassette)
+    Unrecorded {
+        /// The URL of the request
+        url: String,
+    },
+}
+
+impl Error {
+    /// Returns the [`Error`] an error of the crate was caused by, if any.
+    pub fn find(err: &anyhow::Error) -> Option<&Self> {
+        err.chain().find_map(|cause| cause.downcast_ref::<Self>())
+    }
+
+    /// Returns the status code 4chan answered with, if the request got that far.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::UnexpectedStatus { status, .. } => Some(*status),
+            Self::NotFound { .. } => Some(StatusCode::NOT_FOUND),
+            Self::RateLimited { .. } => Some(StatusCode::TOO_MANY_REQUESTS),
+            Self::Cooldown { .. }
+            | Self::UnknownBoard { .. }
+            | Self::Unrecorded { .. }
+            | Self::Connect(_)
+            | Self::Timeout(_)
+            | Self::Body(_)
+            | Self::Decode(_)
+            | Self::Http(_) => None,
+        }
+    }
+
+    /// Returns true if the same request may succeed later.
+    ///
+    /// Timeouts, connection failures, cut off bodies, 5xx responses and rate limits
+    /// are transient. A 404 or a body which cannot be parsed will fail again.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Connect(_)
+            | Self::Timeout(_)
+            | Self::Body(_)
+            | Self::Http(_)
+            | Self::Cooldown { .. }
+            | Self::RateLimited { .. } => true,
+            Self::UnexpectedStatus { status, .. } => status.is_server_error(),
+            Self::Decode(_)
+            | Self::NotFound { .. }
+            | Self::UnknownBoard { .. }
+            | Self::Unrecorded { .. } => false,
+        }
+    }
+
+    /// Returns a stable code naming the kind of error.
+    ///
+    /// Unlike the [`Display`] output, codes do not change between releases,
+    /// so they can key metrics and alert rules.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dot4ch::{error::Category, Error};
+    ///
+    /// let err = Error::NotFound { board: "g".to_string(), id: Some(1) };
+    /// assert_eq!(err.code(), "not_found");
+    /// assert_eq!(err.category(), Category::NotFound);
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Connect(_) => "connect",
+            Self::Timeout(_) => "timeout",
+            Self::Body(_) => "body",
+            Self::Decode(_) => "decode",
+            Self::Http(_) => "http",
+            Self::UnexpectedStatus { .. } => "unexpected_status",
+            Self::NotFound { .. } => "not_found",
+            Self::UnknownBoard { .. } => "unknown_board",
+            Self::Cooldown { .. } => "cooldown",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::Unrecorded { .. } => "unrecorded",
+        }
+    }
+
+    /// Returns the broad [`Category`] of the error.
+    pub fn category(&self) -> Category {
+        match self {
+            Self::Connect(_)
+            | Self::Timeout(_)
+            | Self::Body(_)
+            | Self::Http(_)
+            | Self::Unrecorded { .. } => Category::Network,
+            Self::Decode(_) => Category::Decode,
+            Self::UnexpectedStatus { .. } => Category::Status,
+            Self::NotFound { .. } | Self::UnknownBoard { .. } => Category::NotFound,
+            Self::Cooldown { .. } | Self::RateLimited { .. } => Category::RateLimit,
+        }
+    }
+
+    /// Returns true if the thread or board does not exist.
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(StatusCode::NOT_FOUND)
+    }
+
+    /// Returns a header of an error response.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        match self {
+            Self::UnexpectedStatus { headers, .. } => {
+                headers.get(name).and_then(|value| value.to_str().ok())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the `Content-Type` of an error response.
+    ///
+    /// Challenge pages are `text/html` while the API answers with `application/json`.
+    pub fn content_type(&self) -> Option<&str> {
+        self.header(CONTENT_TYPE.as_str())
+    }
+
+    /// Returns how long to wait before trying again, if known.
+    ///
+    /// This is the rest of the cooldown, or the `Retry-After` header of an error response.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::Cooldown { remaining } => Some(*remaining),
+            Self::RateLimited { retry_after } => *retry_after,
+            Self::UnexpectedStatus { headers, .. } => parse_retry_after(headers),
+            _ => None,
+        }
+    }
+
+    /// Returns the start of the body of an error response, if it was read.
+    pub fn body(&self) -> Option<&str> {
+        match self {
+            Self::UnexpectedStatus { body, .. } => body.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Builds an error from an error response,
+    /// an [`Error::RateLimited`] for a 429 and an [`Error::UnexpectedStatus`] otherwise.
+    pub(crate) fn from_status(
+        status: StatusCode,
+        headers: HeaderMap,
+        body: Option<String>,
+    ) -> Self {
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Self::RateLimited {
+                retry_after: parse_retry_after(&headers),
+            };
+        }
+        Self::UnexpectedStatus {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    /// Builds an error from an error response without reading the body.
+    pub(crate) fn unexpected(response: &Response) -> Self {
+        Self::from_status(response.status(), response.headers().clone(), None)
+    }
+
+    /// Builds an error from an error response with the start of the body.
+    pub(crate) async fn unexpected_with_body(mut response: Response) -> Self {
+        let mut body = vec![];
+        while body.len() < BODY_PREVIEW {
+            match response.chunk().await {
+                Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                _ => break,
+            }
+        }
+        Self::from_status(
+            response.status(),
+            response.headers().clone(),
+            preview(&body),
+        )
+    }
+
+    /// Wraps a `reqwest` error.
+    pub(crate) fn from_reqwest(err: reqwest::Error) -> Self {
+        if let Some(status) = err.status() {
+            return Self::from_status(status, HeaderMap::new(), None);
+        }
+        let kind = if err.is_timeout() {
+            Self::Timeout
+        } else if err.is_connect() {
+            Self::Connect
+        } else if err.is_body() {
+            Self::Body
+        } else if err.is_decode() {
+            Self::Decode
+        } else {
+            Self::Http
+        };
+        kind(http::Error::new(err))
+    }
+}
+
+/// The broad kind of an [`Error`], see [`Error::category`].
+///
+/// More categories may be added, so matches need a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Category {
+    /// The request could not be sent or its response could not be read
+    Network,
+    /// The response is not the expected JSON
+    Decode,
+    /// 4chan answered with an error status code
+    Status,
+    /// The thread or board does not exist
+    NotFound,
+    /// The request was held back by a cooldown or rate limited by 4chan
+    RateLimit,
+}
+
+impl Category {
+    /// Returns a stable name of the category.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Network => "network",
+            Self::Decode => "decode",
+            Self::Status => "status",
+            Self::NotFound => "not_found",
+            Self::RateLimit => "rate_limit",
+        }
+    }
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Connect(err) => write!(f, "Could not connect: {err}"),
+            Self::Timeout(err) => write!(f, "Request timed out: {err}"),
+            Self::Body(err) => write!(f, "Could not read the response: {err}"),
+            Self::Decode(err) => write!(f, "Could not parse the response: {err}"),
+            Self::Http(err) => write!(f, "HTTP request failed: {err}"),
+            Self::UnexpectedStatus { status, .. } => write!(f, "Unexpected StatusCode {status}"),
+            Self::NotFound {
+                board,
+                id: Some(id),
+            } => write!(f, "/{board}/{id} was not found"),
+            Self::NotFound { board, id: None } => write!(f, "/{board}/ was not found"),
+            Self::UnknownBoard { board } => write!(f, "/{board}/ is not a board on 4chan"),
+            Self::Cooldown { remaining } => write!(
+                f,
+                "Updated too quickly, {:.1} seconds left of the cooldown",
+                remaining.as_secs_f32()
+            ),
+            Self::RateLimited {
+                retry_after: Some(wait),
+            } => write!(
+                f,
+                "Rate limited by 4chan, retry after {:.1} seconds",
+                wait.as_secs_f32()
+            ),
+            Self::RateLimited { retry_after: None } => write!(f, "Rate limited by 4chan"),
+            Self::Unrecorded { url } => write!(f, "No recorded response for {url}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Connect(err)
+            | Self::Timeout(err)
+            | Self::Body(err)
+            | Self::Decode(err)
+            | Self::Http(err) => Some(err),
+            Self::UnexpectedStatus { .. }
+            | Self::NotFound { .. }
+            | Self::UnknownBoard { .. }
+            | Self::Cooldown { .. }
+            | Self::RateLimited { .. }
+            | Self::Unrecorded { .. } => None,
+        }
+    }
+}
+
+/// Returns the first [`BODY_PREVIEW`] bytes of a body as text, `None` if it is empty.
+pub(crate) fn preview(body: &[u8]) -> Option<String> {
+    if body.is_empty() {
+        return None;
+    }
+    let body = &body[..body.len().min(BODY_PREVIEW)];
+    Some(String::from_utf8_lossy(body).into_owned())
+}
+
+/// Reads a `Retry-After` header, given either in seconds or as a date.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    date.with_timezone(&Utc)
+        .signed_duration_since(Utc::now())
+        .to_std()
+        .ok()
+}
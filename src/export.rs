@@ -0,0 +1,255 @@
+//! Flat exports of posts for data analysis tools.
+//!
+//! Posts are exported as [`Record`]s, which carry the board and thread a post belongs to.
+//! Each record becomes one row, made out of the chosen [`Column`]s.
+//!
+//! # Example
+//!
+//! ```
+//! use dot4ch::{export::{self, Column, Record}, post::Post};
+//!
+//! let post = Post::default();
+//! let records = vec![Record::new("g", 76759434, &post)];
+//!
+//! let mut csv = vec![];
+//! export::csv(records.clone(), &mut csv, &[Column::Board, Column::Thread, Column::No])?;
+//! assert_eq!(String::from_utf8(csv)?, "board,thread,no\ng,76759434,0\n");
+//!
+//! let mut ndjson = vec![];
+//! export::ndjson(records, &mut ndjson, &[Column::No, Column::Board, Column::Tripcode])?;
+//! assert_eq!(String::from_utf8(ndjson)?, "{\"no\":0,\"board\":\"g\",\"trip\":null}\n");
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use crate::{board::Board, post::Post, thread::Thread, Result};
+use serde_json::Value;
+use std::{io::Write, iter};
+
+/// A post together with the board and thread it was posted in.
+#[derive(Debug, Clone, Copy)]
+pub struct Record<'a> {
+    /// The board of the post
+    pub board: &'a str,
+    /// The OP number of the thread of the post
+    pub thread: u32,
+    /// The post
+    pub post: &'a Post,
+}
+
+impl<'a> Record<'a> {
+    /// Creates a new record.
+    pub fn new(board: &'a str, thread: u32, post: &'a Post) -> Self {
+        Self {
+            board,
+            thread,
+            post,
+        }
+    }
+
+    /// Returns the value of a column for this record.
+    pub fn value(&self, column: Column) -> Value {
+        let post = self.post;
+        let text = |s: &str| {
+            if s.is_empty() {
+                Value::Null
+            } else {
+                Value::from(s)
+            }
+        };
+        match column {
+            Column::Board => Value::from(self.board),
+            Column::Thread => Value::from(self.thread),
+            Column::No => Value::from(post.id()),
+            Column::Time => Value::from(post.post_time()),
+            Column::Name => text(post.name()),
+            Column::Tripcode => post.tripcode().map_or(Value::Null, Value::from),
+            Column::PosterId => post.poster_id().map_or(Value::Null, Value::from),
+            Column::Capcode => post.capcode().map_or(Value::Null, Value::from),
+            Column::Country => post.country().map_or(Value::Null, Value::from),
+            Column::Subject => text(post.subject()),
+            Column::Comment => text(post.content()),
+            Column::Text => text(&post.plain_text()),
+            Column::Filename => text(post.filename()),
+            Column::Ext => text(post.ext()),
+            Column::Filesize => post.filesize().map_or(Value::Null, Value::from),
+            Column::Md5 => post.md5hash().map_or(Value::Null, Value::from),
+            Column::Width => Value::from(post.image_dimensions().0),
+            Column::Height => Value::from(post.image_dimensions().1),
+            Column::Replies => Value::from(post.replies()),
+            Column::Images => Value::from(post.images()),
+        }
+    }
+}
+
+/// A column of an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Column {
+    /// The board
+    Board,
+    /// The OP number of the thread
+    Thread,
+    /// The post number
+    No,
+    /// UNIX timestamp of the post
+    Time,
+    /// The name of the poster
+    Name,
+    /// The tripcode of the poster
+    Tripcode,
+    /// The poster ID
+    PosterId,
+    /// The capcode
+    Capcode,
+    /// The country name
+    Country,
+    /// The subject
+    Subject,
+    /// The HTML escaped comment
+    Comment,
+    /// The comment as plain text
+    Text,
+    /// The filename of the attachment
+    Filename,
+    /// The extension of the attachment
+    Ext,
+    /// The size of the attachment in bytes
+    Filesize,
+    /// The MD5 hash of the attachment
+    Md5,
+    /// The width of the attachment
+    Width,
+    /// The height of the attachment
+    Height,
+    /// The number of replies, for OPs
+    Replies,
+    /// The number of image replies, for OPs
+    Images,
+}
+
+impl Column {
+    /// A set of columns covering the commonly used fields.
+    pub const DEFAULT: &'static [Self] = &[
+        Self::Board,
+        Self::Thread,
+        Self::No,
+        Self::Time,
+        Self::Name,
+        Self::Tripcode,
+        Self::PosterId,
+        Self::Country,
+        Self::Subject,
+        Self::Text,
+        Self::Filename,
+        Self::Ext,
+        Self::Md5,
+    ];
+
+    /// Returns the name of the column used in headers and keys.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Board => "board",
+            Self::Thread => "thread",
+            Self::No => "no",
+            Self::Time => "time",
+            Self::Name => "name",
+            Self::Tripcode => "trip",
+            Self::PosterId => "id",
+            Self::Capcode => "capcode",
+            Self::Country => "country",
+            Self::Subject => "sub",
+            Self::Comment => "com",
+            Self::Text => "text",
+            Self::Filename => "filename",
+            Self::Ext => "ext",
+            Self::Filesize => "fsize",
+            Self::Md5 => "md5",
+            Self::Width => "w",
+            Self::Height => "h",
+            Self::Replies => "replies",
+            Self::Images => "images",
+        }
+    }
+}
+
+/// Writes records as newline delimited JSON, one object per record.
+///
+/// # Errors
+///
+/// This function will return an error if writing to `writer` fails.
+pub fn ndjson<'a, I, W>(records: I, mut writer: W, columns: &[Column]) -> Result<()>
+where
+    I: IntoIterator<Item = Record<'a>>,
+    W: Write,
+{
+    for record in records {
+        // written by hand to keep the keys in the order of the columns
+        writer.write_all(b"{")?;
+        for (idx, &column) in columns.iter().enumerate() {
+            if idx > 0 {
+                writer.write_all(b",")?;
+            }
+            serde_json::to_writer(&mut writer, column.name())?;
+            writer.write_all(b":")?;
+            serde_json::to_writer(&mut writer, &record.value(column))?;
+        }
+        writer.write_all(b"}\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes records as CSV with a header row.
+///
+/// Missing values are written as empty fields.
+///
+/// # Errors
+///
+/// This function will return an error if writing to `writer` fails.
+pub fn csv<'a, I, W>(records: I, mut writer: W, columns: &[Column]) -> Result<()>
+where
+    I: IntoIterator<Item = Record<'a>>,
+    W: Write,
+{
+    let header: Vec<_> = columns.iter().map(|column| column.name()).collect();
+    writeln!(writer, "{}", header.join(","))?;
+    for record in records {
+        let row: Vec<_> = columns
+            .iter()
+            .map(|&column| csv_field(&record.value(column)))
+            .collect();
+        writeln!(writer, "{}", row.join(","))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Formats a value as a CSV field, quoting it if needed.
+fn csv_field(value: &Value) -> String {
+    let field = match value {
+        Value::Null => return String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+impl Thread {
+    /// Returns a [`Record`] for every post in the thread, OP first.
+    pub fn records(&self) -> impl Iterator<Item = Record<'_>> {
+        let thread = self.op().id();
+        iter::once(self.op())
+            .chain(&self[..])
+            .map(move |post| Record::new(self.board(), thread, post))
+    }
+}
+
+impl Board {
+    /// Returns a [`Record`] for every post of every thread in the board.
+    pub fn records(&self) -> impl Iterator<Item = Record<'_>> {
+        self.threads.values().flat_map(Thread::records)
+    }
+}
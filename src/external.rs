@@ -0,0 +1,29 @@
+//! Third party archives which keep threads after they are gone from 4chan.
+//!
+//! Threads and posts from an archive are mapped into the same [`Thread`]
+//! and [`Post`](crate::post::Post) types as the live API.
+//!
+//! Archives are enabled by features:
+//! - `foolfuuka`: archives running [`FoolFuuka`](<https://github.com/pleebe/FoolFuuka>),
+//!   such as 4plebs and desuarchive
+
+use crate::{thread::Thread, Dot4chClient, Result};
+use async_trait::async_trait;
+use std::fmt::Debug;
+
+#[cfg(feature = "foolfuuka")]
+pub mod foolfuuka;
+
+/// An archive which can fetch threads that are gone from 4chan.
+///
+/// Archives usually only cover some boards, so they are registered per board on the client
+/// with [`Client::add_resolver`](crate::Client::add_resolver).
+/// They are used by [`Thread::new_or_archived`] and to resolve dead links in comments.
+#[async_trait(?Send)]
+pub trait Resolver: Debug + Send + Sync {
+    /// Fetches a thread from the archive.
+    async fn thread(&self, client: &Dot4chClient, board: &str, id: u32) -> Result<Thread>;
+
+    /// Returns the number of the thread a post lives in.
+    async fn thread_of(&self, client: &Dot4chClient, board: &str, post: u32) -> Result<u32>;
+}
@@ -0,0 +1,264 @@
+//! The JSON API of `FoolFuuka` archives.
+//!
+//! Requires the `foolfuuka` feature.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, external::foolfuuka::FoolFuuka};
+//! # async fn foolfuuka() -> anyhow::Result<()> {
+//! # let client = Client::new();
+//! let archive = FoolFuuka::new("https://archive.4plebs.org");
+//! let thread = archive.thread(&client, "pol", 124205675).await?;
+//! println!("{}", thread);
+//!
+//! // or use it for every /pol/ thread which is gone from 4chan
+//! client.lock().await.add_resolver("pol", archive);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::Resolver;
+use crate::{post::Post, thread::Thread, Dot4chClient, Error, Result};
+use anyhow::Context;
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+use std::convert::TryFrom;
+
+/// A `FoolFuuka` archive.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FoolFuuka {
+    /// The URL the archive is hosted at, without a trailing slash
+    base: String,
+}
+
+impl FoolFuuka {
+    /// Creates an archive hosted at `base`, such as `https://archive.4plebs.org`.
+    pub fn new(base: &str) -> Self {
+        Self {
+            base: base.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Returns the URL the archive is hosted at.
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    /// Fetches a thread from the archive.
+    ///
+    /// The thread is marked as archived, so it is not updated from the live API.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails
+    /// or if the archive does not have the thread.
+    pub async fn thread(&self, client: &Dot4chClient, board: &str, id: u32) -> Result<Thread> {
+        let url = format!("{}/_/api/chan/thread/?board={board}&num={id}", self.base);
+        let response = fetch(client, &url, board, id).await?;
+        let thread = response
+            .get(id.to_string())
+            .ok_or_else(|| Error::NotFound {
+                board: board.to_string(),
+                id: Some(id),
+            })?;
+
+        let op = thread
+            .get("op")
+            .ok_or_else(|| anyhow::anyhow!("/{}/{} has no OP in {}", board, id, self.base))?;
+        let mut replies = vec![];
+        if let Some(Value::Object(posts)) = thread.get("posts") {
+            for post in posts.values() {
+                // ghost posts were made on the archive, not on 4chan
+                if number(post, "subnum") == 0 {
+                    replies.push(to_post(post, false)?);
+                }
+            }
+        }
+        replies.sort_by_key(Post::id);
+
+        let mut posts = vec![to_post(op, true)?];
+        posts.extend(replies);
+        Thread::from_posts(client, board, posts, None)
+    }
+
+    /// Fetches a single post from the archive.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails
+    /// or if the archive does not have the post.
+    pub async fn post(&self, client: &Dot4chClient, board: &str, no: u32) -> Result<Post> {
+        to_post(&self.raw_post(client, board, no).await?, false)
+    }
+
+    /// Fetches a single post as the archive sends it.
+    async fn raw_post(&self, client: &Dot4chClient, board: &str, no: u32) -> Result<Value> {
+        let url = format!("{}/_/api/chan/post/?board={board}&num={no}", self.base);
+        fetch(client, &url, board, no).await
+    }
+}
+
+#[async_trait(?Send)]
+impl Resolver for FoolFuuka {
+    async fn thread(&self, client: &Dot4chClient, board: &str, id: u32) -> Result<Thread> {
+        FoolFuuka::thread(self, client, board, id).await
+    }
+
+    async fn thread_of(&self, client: &Dot4chClient, board: &str, post: u32) -> Result<u32> {
+        let post = self.raw_post(client, board, post).await?;
+        Ok(u32::try_from(number(&post, "thread_num"))?)
+    }
+}
+
+/// Fetches a JSON response, naming the post and the URL in errors.
+async fn fetch(client: &Dot4chClient, url: &str, board: &str, no: u32) -> Result<Value> {
+    fetch_value(client, url, board, no)
+        .await
+        .with_context(|| format!("fetching /{board}/{no} from an archive ({url})"))
+}
+
+/// Fetches a JSON response, turning the error object of the API into an error.
+async fn fetch_value(client: &Dot4chClient, url: &str, board: &str, no: u32) -> Result<Value> {
+    let response = client.lock().await.get(url).await?;
+    let response = crate::check_in(response, board, Some(no)).await?;
+    let value = crate::json::<Value>(response).await?;
+    if let Some(error) = value.get("error").and_then(Value::as_str) {
+        if error.to_lowercase().contains("not found") {
+            return Err(Error::NotFound {
+                board: board.to_string(),
+                id: Some(no),
+            }
+            .into());
+        }
+        return Err(anyhow::anyhow!("{}: {}", url, error));
+    }
+    Ok(value)
+}
+
+/// Reads a number which `FoolFuuka` may send as a string.
+fn number(post: &Value, key: &str) -> i64 {
+    match post.get(key) {
+        Some(Value::Number(n)) => n.as_i64().unwrap_or(0),
+        Some(Value::String(s)) => s.parse().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Reads a string field, treating `null` as empty.
+fn text<'a>(post: &'a Value, key: &str) -> &'a str {
+    post.get(key).and_then(Value::as_str).unwrap_or("")
+}
+
+/// Maps a `FoolFuuka` post onto the fields of the 4chan API.
+fn to_post(post: &Value, op: bool) -> Result<Post> {
+    let time = number(post, "timestamp");
+    let mut fields = Map::new();
+    fields.insert("no".into(), json!(number(post, "num")));
+    fields.insert(
+        "resto".into(),
+        json!(if op || number(post, "op") == 1 {
+            0
+        } else {
+            number(post, "thread_num")
+        }),
+    );
+    fields.insert("now".into(), json!(text(post, "fourchan_date")));
+    fields.insert("time".into(), json!(time));
+    fields.insert("name".into(), json!(text(post, "name")));
+    fields.insert("trip".into(), json!(text(post, "trip")));
+    fields.insert("id".into(), json!(text(post, "poster_hash")));
+    fields.insert("country".into(), json!(text(post, "poster_country")));
+    fields.insert(
+        "country_name".into(),
+        json!(text(post, "poster_country_name")),
+    );
+    fields.insert("sub".into(), json!(text(post, "title")));
+    fields.insert("com".into(), json!(comment_html(text(post, "comment"))));
+    fields.insert("sticky".into(), json!(number(post, "sticky")));
+    fields.insert("closed".into(), json!(number(post, "locked")));
+    if let Some(capcode) = capcode(text(post, "capcode")) {
+        fields.insert("capcode".into(), json!(capcode));
+    }
+    if op {
+        let expired = number(post, "timestamp_expired");
+        fields.insert("archived".into(), json!(1));
+        fields.insert(
+            "archived_on".into(),
+            json!(if expired == 0 { time } else { expired }),
+        );
+    }
+    if let Some(media) = post.get("media").filter(|media| media.is_object()) {
+        let filename = text(media, "media_filename");
+        let (name, ext) = filename
+            .rfind('.')
+            .map_or((filename, ""), |idx| filename.split_at(idx));
+        let orig = text(media, "media_orig");
+        let tim = orig
+            .split('.')
+            .next()
+            .unwrap_or("")
+            .parse::<u64>()
+            .unwrap_or(0);
+        fields.insert("filename".into(), json!(name));
+        fields.insert("ext".into(), json!(ext));
+        fields.insert("tim".into(), json!(tim));
+        fields.insert("md5".into(), json!(text(media, "media_hash")));
+        fields.insert("fsize".into(), json!(number(media, "media_size")));
+        fields.insert("w".into(), json!(number(media, "media_w")));
+        fields.insert("h".into(), json!(number(media, "media_h")));
+        fields.insert("tn_w".into(), json!(number(media, "preview_w")));
+        fields.insert("tn_h".into(), json!(number(media, "preview_h")));
+        fields.insert("spoiler".into(), json!(number(media, "spoiler")));
+    }
+    Ok(serde_json::from_value(Value::Object(fields))?)
+}
+
+/// Maps the single letter capcodes of `FoolFuuka` onto the ones of the 4chan API.
+fn capcode(capcode: &str) -> Option<&'static str> {
+    match capcode {
+        "M" => Some("mod"),
+        "A" => Some("admin"),
+        "D" => Some("developer"),
+        "F" => Some("founder"),
+        "V" => Some("verified"),
+        "G" => Some("manager"),
+        _ => None,
+    }
+}
+
+/// Turns the raw comment of `FoolFuuka` into the HTML the 4chan API uses.
+fn comment_html(raw: &str) -> String {
+    raw.lines()
+        .map(|line| {
+            let escaped = escape(line);
+            if let Some(quoted) = line
+                .strip_prefix(">>")
+                .filter(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()))
+            {
+                format!("<a href=\"#p{quoted}\" class=\"quotelink\">{escaped}</a>")
+            } else if line.starts_with('>') {
+                format!("<span class=\"quote\">{escaped}</span>")
+            } else {
+                escaped
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("<br>")
+}
+
+/// Escapes text the same way the 4chan API does.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#039;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
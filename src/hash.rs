@@ -0,0 +1,35 @@
+//! The hasher of the maps in board caches and the dedup index.
+//!
+//! These maps are keyed by post numbers and MD5 hashes, which do not need the
+//! protection against collision attacks of the standard `SipHash`.
+//! With the `fxhash` feature, they use the much faster `FxHash` instead.
+//!
+//! The hasher only applies to maps kept inside the crate. Maps in public fields and
+//! signatures, such as [`Board::threads`](crate::board::Board::threads), are plain
+//! [`std::collections::HashMap`]s, so enabling the feature anywhere in a dependency graph
+//! does not change the types other crates see.
+//!
+//! The aliases here are for code which wants the same hasher as the crate.
+//!
+//! # Example
+//!
+//! ```
+//! use dot4ch::hash::HashMap;
+//!
+//! let mut replies: HashMap<u32, u32> = HashMap::default();
+//! replies.insert(76759434, 310);
+//! ```
+
+/// Builds the hasher of the maps of the crate.
+#[cfg(feature = "fxhash")]
+pub type BuildHasher = std::hash::BuildHasherDefault<rustc_hash::FxHasher>;
+
+/// Builds the hasher of the maps of the crate.
+#[cfg(not(feature = "fxhash"))]
+pub type BuildHasher = std::collections::hash_map::RandomState;
+
+/// A [`std::collections::HashMap`] using the [`BuildHasher`] of the crate.
+pub type HashMap<K, V> = std::collections::HashMap<K, V, BuildHasher>;
+
+/// A [`std::collections::HashSet`] using the [`BuildHasher`] of the crate.
+pub type HashSet<T> = std::collections::HashSet<T, BuildHasher>;
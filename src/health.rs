@@ -0,0 +1,168 @@
+//! Health reporting of a client.
+//!
+//! Every request sent by a [`Client`](crate::Client) is tallied per endpoint, an endpoint
+//! being the URL of the request with its thread and file numbers left out,
+//! such as `a.4cdn.org/g/thread/*.json`.
+//!
+//! The tallies live behind a [`Health`] handle of their own, so they can be read while
+//! the client is locked by a request in progress, such as from a health check endpoint.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::Client;
+//! # async fn health() {
+//! let client = Client::new();
+//! let health = client.lock().await.health();
+//!
+//! // elsewhere, without waiting for the client
+//! let status = health.status();
+//! if status.consecutive_failures > 3 {
+//!     println!("4chan is unreachable: {:?}", status.last_error);
+//! }
+//! for endpoint in &status.endpoints {
+//!     println!("{}: last success {:?}", endpoint.endpoint, endpoint.last_success);
+//! }
+//! # }
+//! ```
+
+use crate::{http::Response, Error};
+use chrono::{DateTime, Utc};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// The requests sent to one endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointStatus {
+    /// The URL of the endpoint without its scheme, numbers replaced by `*`
+    pub endpoint: String,
+    /// The number of requests sent
+    pub requests: u64,
+    /// The number of requests which failed or got an error status code
+    pub failures: u64,
+    /// The number of requests which failed since the last success
+    pub consecutive_failures: u32,
+    /// When a request last succeeded
+    pub last_success: Option<DateTime<Utc>>,
+    /// When a request last failed
+    pub last_failure: Option<DateTime<Utc>>,
+    /// The error of the last failed request, cleared by the next success
+    pub last_error: Option<String>,
+}
+
+/// A snapshot of the health of a client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    /// The number of requests sent
+    pub requests: u64,
+    /// The number of requests which failed since the last success to any endpoint
+    pub consecutive_failures: u32,
+    /// When a request last succeeded
+    pub last_success: Option<DateTime<Utc>>,
+    /// The error of the last failed request, cleared by the next success
+    pub last_error: Option<String>,
+    /// Every endpoint requested so far, sorted by endpoint
+    pub endpoints: Vec<EndpointStatus>,
+}
+
+/// The tallies behind a [`Health`].
+#[derive(Debug, Default)]
+struct Tally {
+    /// The number of requests which failed since the last success
+    consecutive_failures: u32,
+    /// The tallies per endpoint
+    endpoints: BTreeMap<String, EndpointStatus>,
+}
+
+/// A handle to the request tallies of a client.
+///
+/// Clones share the tallies, see [`Client::health`](crate::Client::health).
+#[derive(Debug, Clone, Default)]
+pub struct Health(Arc<Mutex<Tally>>);
+
+impl Health {
+    /// Returns a snapshot of the tallies.
+    pub fn status(&self) -> Status {
+        let tally = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let endpoints: Vec<_> = tally.endpoints.values().cloned().collect();
+        let last_failed = endpoints
+            .iter()
+            .filter(|endpoint| endpoint.last_error.is_some())
+            .max_by_key(|endpoint| endpoint.last_failure);
+        Status {
+            requests: endpoints.iter().map(|endpoint| endpoint.requests).sum(),
+            consecutive_failures: tally.consecutive_failures,
+            last_success: endpoints
+                .iter()
+                .filter_map(|endpoint| endpoint.last_success)
+                .max(),
+            last_error: last_failed
+                .filter(|_| tally.consecutive_failures > 0)
+                .and_then(|endpoint| endpoint.last_error.clone()),
+            endpoints,
+        }
+    }
+
+    /// Forgets every tally.
+    pub fn reset(&self) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = Tally::default();
+    }
+
+    /// Tallies the outcome of a request to `url`.
+    pub(crate) fn record(&self, url: &str, outcome: &Result<Response, Error>) {
+        let error = match outcome {
+            Ok(response) => crate::check(response).err().map(|err| err.to_string()),
+            Err(err) => Some(err.to_string()),
+        };
+        let endpoint = endpoint(url);
+        let now = Utc::now();
+        let mut tally = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let status = tally
+            .endpoints
+            .entry(endpoint.clone())
+            .or_insert_with(|| EndpointStatus {
+                endpoint,
+                requests: 0,
+                failures: 0,
+                consecutive_failures: 0,
+                last_success: None,
+                last_failure: None,
+                last_error: None,
+            });
+        status.requests += 1;
+        if let Some(error) = error {
+            status.failures += 1;
+            status.consecutive_failures = status.consecutive_failures.saturating_add(1);
+            status.last_failure = Some(now);
+            status.last_error = Some(error);
+            tally.consecutive_failures = tally.consecutive_failures.saturating_add(1);
+        } else {
+            status.consecutive_failures = 0;
+            status.last_success = Some(now);
+            status.last_error = None;
+            tally.consecutive_failures = 0;
+        }
+    }
+}
+
+/// Returns the endpoint of a URL, its numbered path segments replaced by `*`.
+fn endpoint(url: &str) -> String {
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.split('/')
+        .map(|segment| {
+            let (stem, ext) = segment.split_once('.').unwrap_or((segment, ""));
+            if !stem.starts_with(|c: char| c.is_ascii_digit()) {
+                return segment.to_string();
+            }
+            if ext.is_empty() {
+                "*".to_string()
+            } else {
+                format!("*.{ext}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,230 @@
+//! The HTTP types of the public API.
+//!
+//! They are re-exported from `reqwest` so downstream code can name them
+//! without depending on the same `reqwest` version as this crate.
+//!
+//! [`ConditionalGet`] sends the `If-Modified-Since` requests the models of this crate
+//! update with, so endpoints the crate does not model can be polled the same way.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, http::{ConditionalGet, Reply}};
+//! # async fn poll() -> anyhow::Result<()> {
+//! let client = Client::new();
+//! let mut boards = ConditionalGet::new("https://a.4cdn.org/boards.json", "g");
+//! loop {
+//!     match boards.json::<serde_json::Value>(&client).await? {
+//!         Reply::Modified(boards) => println!("{boards}"),
+//!         Reply::NotModified => println!("no change since {:?}", boards.last_modified()),
+//!     }
+//!     tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+//! }
+//! # }
+//! ```
+
+use crate::Dot4chClient;
+use serde::de::DeserializeOwned;
+use std::fmt::{self, Display, Formatter};
+
+pub use bytes::Bytes;
+pub use reqwest::{
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    Client, Response, StatusCode, Url,
+};
+
+/// A request which could not be sent or whose body could not be read.
+#[derive(Debug)]
+pub struct Error(reqwest::Error);
+
+impl Error {
+    /// Wraps a `reqwest` error.
+    pub(crate) fn new(err: reqwest::Error) -> Self {
+        Self(err)
+    }
+
+    /// Returns the URL of the failed request, if known.
+    pub fn url(&self) -> Option<&Url> {
+        self.0.url()
+    }
+
+    /// Returns true if the request timed out.
+    pub fn is_timeout(&self) -> bool {
+        self.0.is_timeout()
+    }
+
+    /// Returns true if the connection could not be made.
+    pub fn is_connect(&self) -> bool {
+        self.0.is_connect()
+    }
+
+    /// Returns true if the body of the response could not be read.
+    pub fn is_body(&self) -> bool {
+        self.0.is_body()
+    }
+
+    /// Returns true if the body of the response could not be parsed.
+    pub fn is_decode(&self) -> bool {
+        self.0.is_decode()
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+/// The answer to a conditional request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply<T> {
+    /// The resource changed since the `If-Modified-Since` time, with its new content
+    Modified(T),
+    /// 4chan answered `304 Not Modified`, the resource did not change
+    NotModified,
+}
+
+impl<T> Reply<T> {
+    /// Returns true if the resource changed.
+    pub fn is_modified(&self) -> bool {
+        matches!(self, Self::Modified(_))
+    }
+
+    /// Returns the new content, `None` if the resource did not change.
+    pub fn modified(self) -> Option<T> {
+        match self {
+            Self::Modified(data) => Some(data),
+            Self::NotModified => None,
+        }
+    }
+
+    /// Maps the new content, if any.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Reply<U> {
+        match self {
+            Self::Modified(data) => Reply::Modified(f(data)),
+            Self::NotModified => Reply::NotModified,
+        }
+    }
+}
+
+/// Repeated conditional GET requests to one URL.
+///
+/// Every request is sent with an `If-Modified-Since` header, the `Last-Modified` header of
+/// the previous changed response, or the time of the last request of the client before the
+/// first one. This is what the models of this crate do when they update.
+///
+/// Like any other request, each one is counted by the rate limit of the client,
+/// but the 10 second cooldown between updates is left to the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionalGet {
+    /// The URL requested
+    url: String,
+    /// The board of the URL, named by a [`crate::Error::NotFound`]
+    board: String,
+    /// The thread of the URL, if any, named by a [`crate::Error::NotFound`]
+    id: Option<u32>,
+    /// The `Last-Modified` header of the last changed response
+    last_modified: Option<String>,
+}
+
+impl ConditionalGet {
+    /// Creates requests to `url`, an endpoint of `board`.
+    pub fn new(url: impl Into<String>, board: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            board: board.into(),
+            id: None,
+            last_modified: None,
+        }
+    }
+
+    /// Sets the thread the URL belongs to, named by the error of a 404.
+    #[must_use]
+    pub fn thread(mut self, id: impl Into<crate::ThreadId>) -> Self {
+        self.id = Some(id.into().get());
+        self
+    }
+
+    /// Sets the `Last-Modified` header to send as `If-Modified-Since` on the next request,
+    /// such as one persisted from an earlier run.
+    #[must_use]
+    pub fn since(mut self, last_modified: impl Into<String>) -> Self {
+        self.last_modified = Some(last_modified.into());
+        self
+    }
+
+    /// Returns the URL requested.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the `Last-Modified` header of the last changed response.
+    pub fn last_modified(&self) -> Option<&str> {
+        self.last_modified.as_deref()
+    }
+
+    /// Sends the request and returns the response if the resource changed.
+    ///
+    /// The `Last-Modified` header of a changed response is kept for the next request.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails or 4chan answered with an
+    /// error status code, [`crate::Error::NotFound`] for a 404.
+    pub async fn send(&mut self, client: &Dot4chClient) -> crate::Result<Reply<Response>> {
+        let response = crate::refresh(
+            client,
+            &self.url,
+            &self.board,
+            self.id,
+            self.last_modified.as_deref(),
+        )
+        .await?;
+        Ok(match response {
+            Some(response) => {
+                if let Some(last_modified) = crate::last_modified(&response) {
+                    self.last_modified = Some(last_modified);
+                }
+                Reply::Modified(response)
+            }
+            None => Reply::NotModified,
+        })
+    }
+
+    /// Sends the request and parses the JSON body if the resource changed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails, 4chan answered with an
+    /// error status code or the body cannot be parsed.
+    pub async fn json<T: DeserializeOwned>(
+        &mut self,
+        client: &Dot4chClient,
+    ) -> crate::Result<Reply<T>> {
+        Ok(match self.send(client).await? {
+            Reply::Modified(response) => Reply::Modified(crate::json(response).await?),
+            Reply::NotModified => Reply::NotModified,
+        })
+    }
+
+    /// Sends the request and reads the body if the resource changed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails, 4chan answered with an
+    /// error status code or the body cannot be read.
+    pub async fn bytes(&mut self, client: &Dot4chClient) -> crate::Result<Reply<Bytes>> {
+        Ok(match self.send(client).await? {
+            Reply::Modified(response) => {
+                Reply::Modified(response.bytes().await.map_err(crate::Error::from_reqwest)?)
+            }
+            Reply::NotModified => Reply::NotModified,
+        })
+    }
+}
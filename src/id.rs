@@ -0,0 +1,114 @@
+//! Typed numbers of threads and posts.
+//!
+//! A thread is named by the number of its OP, so both are plain integers in the API.
+//! [`ThreadId`] and [`PostNo`] keep them apart: a function asking for a [`ThreadId`]
+//! cannot be handed the number of a reply by mistake.
+//!
+//! Both convert from a raw `u32`, and a [`PostNo`] also from a `u64` or an `i32`, so untyped
+//! literals still work where they are expected.
+//!
+//! # Example
+//!
+//! ```
+//! use dot4ch::{PostNo, ThreadId};
+//!
+//! let thread = ThreadId::from(76759434);
+//! let op = PostNo::from(thread);
+//!
+//! assert_eq!(thread.get(), 76759434);
+//! assert_eq!(op, PostNo(76759434));
+//! assert_eq!(serde_json::to_string(&op).unwrap(), "76759434");
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display, Formatter},
+};
+
+/// The number of a thread, which is the number of its OP.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+#[repr(transparent)]
+pub struct ThreadId(pub u32);
+
+impl ThreadId {
+    /// Returns the raw thread number.
+    pub fn get(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for ThreadId {
+    fn from(id: u32) -> Self {
+        Self(id)
+    }
+}
+
+impl From<ThreadId> for u32 {
+    fn from(id: ThreadId) -> Self {
+        id.0
+    }
+}
+
+impl Display for ThreadId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The number of a post, either an OP or a reply.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+#[repr(transparent)]
+pub struct PostNo(pub u64);
+
+impl PostNo {
+    /// Returns the raw post number.
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u32> for PostNo {
+    fn from(no: u32) -> Self {
+        Self(no.into())
+    }
+}
+
+impl From<u64> for PostNo {
+    fn from(no: u64) -> Self {
+        Self(no)
+    }
+}
+
+/// Lets untyped literals such as `thread.find(81730461)` fall back to `i32` and still convert.
+///
+/// Negative numbers, which no post has, become `PostNo(0)`, which no post has either.
+impl From<i32> for PostNo {
+    fn from(no: i32) -> Self {
+        Self(u64::try_from(no).unwrap_or_default())
+    }
+}
+
+impl From<ThreadId> for PostNo {
+    fn from(id: ThreadId) -> Self {
+        Self(id.0.into())
+    }
+}
+
+impl From<PostNo> for u64 {
+    fn from(no: PostNo) -> Self {
+        no.0
+    }
+}
+
+impl Display for PostNo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
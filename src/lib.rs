@@ -8,6 +8,7 @@
 //! - Threads
 //! - Catalog
 //! - Boards
+//! - Archives
 //!
 //! While respecting 4chan's:  
 //! - GET 1 second-per-request cooldown.
@@ -38,8 +39,27 @@
 //!     println!("{}", post.image_url(board).unwrap());
 //! }
 //! ```
-
-
+//!
+//! ## Features
+//!
+//! - `log` (default): log messages through the [`log`](https://docs.rs/log) crate
+//! - `unicode`: NFKC normalization in [`text`]
+//! - `bincode`: compact binary snapshots in [`persist`]
+//! - `tantivy`: a full-text index in [`search`]
+//! - `whatlang`: language detection of comments in [`analysis`]
+//! - `async-std`: a timer for the `async-std` runtime in [`timer`]
+//! - `fxhash`: `FxHash` for the internal maps of board caches and the dedup index, see [`hash`]
+//! - `foolfuuka`: threads from `FoolFuuka` archives in [`external`]
+//! - `cassette`: recording and replaying responses offline
+//! - `warc`: recording every response into a WARC file
+//! - `mini-blocking`: a small blocking client
+//!
+//! For the leanest dependency tree, such as on embedded targets, only the models and the
+//! client are kept with:
+//!
+//! ```toml
+//! dot4ch = { version = "2", default-features = false }
+//! ```
 
 #![deny(
     anonymous_parameters,
@@ -56,7 +76,6 @@
     unsafe_code,
     unused_extern_crates
 )]
-
 #![warn(
     clippy::dbg_macro,
     clippy::decimal_literal_representation,
@@ -82,7 +101,6 @@
     clippy::redundant_pub_crate,
     clippy::wildcard_imports
 )]
-
 #![allow(
     clippy::missing_const_for_fn,
     clippy::must_use_candidate,
@@ -90,20 +108,57 @@
     clippy::clippy::struct_excessive_bools
 )]
 
+use crate::logging::{info, trace, warn};
+use anyhow::Context;
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
-use log::{info, trace};
-use reqwest::Response;
-use std::sync::Arc;
-use tokio::{
-    sync::Mutex,
-    time::{sleep, Duration as TkDuration},
+use futures::{stream, Stream};
+use http::{Response, StatusCode};
+use serde::de::DeserializeOwned;
+use std::{
+    collections::{hash_map::RandomState, HashMap, VecDeque},
+    convert::TryFrom,
+    hash::{BuildHasher, Hasher},
+    sync::Arc,
 };
+use tokio::sync::Mutex;
 
+pub mod analysis;
+pub mod archive;
+pub mod archiver;
+#[cfg(feature = "mini-blocking")]
+pub mod blocking;
+pub mod board;
+pub mod boards;
+pub mod cache;
+#[cfg(feature = "cassette")]
+pub mod cassette;
+pub mod comment;
+pub mod dedup;
+pub mod diff;
+pub mod error;
+pub mod export;
+pub mod external;
+pub mod hash;
+pub mod health;
+pub mod http;
+pub mod id;
+mod logging;
+pub mod monitor;
+pub mod notify;
+pub mod pacing;
+pub mod persist;
+pub mod post;
+pub mod retry;
+pub mod scheduler;
+pub mod search;
+pub mod stats;
+pub mod text;
 pub mod thread;
 mod threadlist;
-pub mod post;
-pub mod board;
+pub mod timer;
+#[cfg(feature = "warc")]
+pub mod warc;
 
 /// The Catalog consists of the [`crate::threadlist::Catalog`] and [`crate::threadlist::CatalogThread`]s
 pub mod catalog {
@@ -112,7 +167,8 @@ pub mod catalog {
     pub use crate::threadlist::Page;
 }
 
-
+pub use error::Error;
+pub use id::{PostNo, ThreadId};
 
 /// Crate result type
 pub(crate) type Result<T> = anyhow::Result<T>;
@@ -127,6 +183,38 @@ pub struct Client {
     req_client: reqwest::Client,
     /// The last time a client was checked
     pub last_checked: DateTime<Utc>,
+    /// Records every response when set
+    #[cfg(feature = "warc")]
+    recorder: Option<warc::WarcWriter>,
+    /// Records responses to a directory or answers requests from it
+    #[cfg(feature = "cassette")]
+    cassette: Option<cassette::Cassette>,
+    /// The archives threads are fetched from once they are gone from 4chan, per board
+    resolvers: HashMap<String, Arc<dyn external::Resolver>>,
+    /// The archive used for boards without their own
+    fallback: Option<Arc<dyn external::Resolver>>,
+    /// Waits out the cooldowns
+    timer: Arc<dyn timer::Timer>,
+    /// Decides whether failed requests are retried
+    retry: Arc<dyn retry::RetryPolicy>,
+    /// Skips malformed posts instead of failing the whole response
+    lenient: bool,
+    /// Checks board codes against the list of boards before fetching
+    validate_boards: bool,
+    /// The list of boards, fetched on the first check
+    boards: Option<Arc<boards::Boards>>,
+    /// The most time added at random to every sleep
+    jitter: std::time::Duration,
+    /// The most requests sent back to back after the client was idle
+    burst: u32,
+    /// The requests which may be sent right away
+    permits: u32,
+    /// The interval between requests
+    pacer: pacing::Pacer,
+    /// Tallies the outcome of every request
+    health: health::Health,
+    /// Sent with every request which does not set them itself
+    headers: http::HeaderMap,
 }
 
 impl Client {
@@ -135,19 +223,47 @@ impl Client {
     /// This client handles your cooldown and requests internally.
     /// Thread safe.
     pub fn new() -> Arc<Mutex<Self>> {
+        Self::builder().build()
+    }
+
+    /// Returns a [`ClientBuilder`] to configure a client before it is shared.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder {
+            client: Self::unshared(),
+        }
+    }
+
+    /// Makes a client with the default settings.
+    fn unshared() -> Self {
         let req_client = reqwest::Client::new();
         let last_checked = Utc::now();
         let creation_time = last_checked;
-        info!("constructed chan client.");
-        Arc::new(Mutex::new(Self {
+        Self {
             creation_time,
             req_client,
             last_checked,
-        }))
+            #[cfg(feature = "warc")]
+            recorder: None,
+            #[cfg(feature = "cassette")]
+            cassette: None,
+            resolvers: HashMap::new(),
+            fallback: None,
+            timer: Arc::new(timer::TokioTimer),
+            retry: Arc::new(retry::NoRetry),
+            lenient: false,
+            validate_boards: false,
+            boards: None,
+            jitter: std::time::Duration::from_secs(0),
+            burst: 1,
+            permits: 1,
+            pacer: pacing::Pacer::default(),
+            health: health::Health::default(),
+            headers: http::HeaderMap::new(),
+        }
     }
 
     /// Returns a reference to the reqwest client in the API client.
-    pub fn req_client(&self) -> &reqwest::Client {
+    pub fn req_client(&self) -> &http::Client {
         &self.req_client
     }
 
@@ -157,30 +273,494 @@ impl Client {
     ///
     /// Returns a `Response` from the given 4chan url
     ///
+    /// Failed requests and error status codes are retried as the
+    /// [`retry::RetryPolicy`] of the client decides.
+    ///
     /// # Errors
     ///
     ///  This function will return an error if the `GET` request to the URL fails.
     pub async fn get(&mut self, url: &str) -> Result<Response> {
-        let current_time = Utc::now().signed_duration_since(self.last_checked);
+        self.get_with(url, &http::HeaderMap::new()).await
+    }
+
+    /// Sends a GET request like [`Client::get`] with extra headers.
+    ///
+    /// The headers override those set on the client with [`Client::set_header`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, http::{header::ACCEPT_LANGUAGE, HeaderMap, HeaderValue}};
+    /// # async fn get_with() -> anyhow::Result<()> {
+    /// let client = Client::new();
+    /// let mut headers = HeaderMap::new();
+    /// headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("en"));
+    /// let response = client
+    ///     .lock()
+    ///     .await
+    ///     .get_with("https://a.4cdn.org/boards.json", &headers)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    ///  This function will return an error if the `GET` request to the URL fails.
+    pub async fn get_with(&mut self, url: &str, headers: &http::HeaderMap) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            self.pace().await;
+
+            let request = self.req_client.get(url).headers(headers.clone());
+            let resp = self.send(request).await;
+            self.last_checked = Utc::now();
+            trace!(
+                "Updated the client last checked time: {}",
+                self.last_checked
+            );
+
+            let retry = match &resp {
+                Ok(resp) => check(resp)
+                    .err()
+                    .and_then(|err| self.retry.retry(&err, attempt)),
+                Err(err) => self.retry.retry(err, attempt),
+            };
+            let Some(wait) = retry else {
+                return Ok(resp?);
+            };
+            let wait = self.jittered(wait);
+            warn!("Request to {url} failed, retrying in {wait:?}");
+            self.timer.sleep(wait).await;
+        }
+    }
+
+    /// Takes a permit for the next request, waiting out the request cooldown if none is left.
+    ///
+    /// A permit comes back for every whole interval since the last request, up to the burst.
+    pub(crate) async fn pace(&mut self) {
+        let interval = self.pacer.interval();
+        if self.creation_time != self.last_checked {
+            let idle = Utc::now()
+                .signed_duration_since(self.last_checked)
+                .to_std()
+                .unwrap_or_default();
+            let idle =
+                u32::try_from(idle.as_millis() / interval.as_millis().max(1)).unwrap_or(u32::MAX);
+            self.permits = self.permits.saturating_add(idle).min(self.burst);
+        }
+
+        if self.permits == 0 {
+            trace!("Requesting responses too fast! Slowing down requests to 1 per {interval:?}");
+            let wait = self.jittered(interval);
+            self.timer.sleep(wait).await;
+        } else {
+            self.permits -= 1;
+        }
+    }
+
+    /// Sends a GET request like [`Client::get`] and returns the whole body.
+    ///
+    /// The body is handed over in the [`http::Bytes`] it was read into, without being copied.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails, the status code is an error
+    /// or the body cannot be read.
+    pub async fn get_bytes(&mut self, url: &str) -> Result<http::Bytes> {
+        let response = check_body(self.get(url).await?).await?;
+        Ok(response.bytes().await.map_err(Error::from_reqwest)?)
+    }
 
-        if (current_time < Duration::seconds(1)) && (self.creation_time != self.last_checked) {
-            trace!("Requesting responses too fast! Slowing down requests to 1 per second");
-            sleep(TkDuration::from_secs(1)).await;
+    /// Sends a GET request like [`Client::get`] and appends the body to `buf`.
+    ///
+    /// The chunks are copied straight into `buf` as they arrive, so a single buffer
+    /// can be cleared and reused across downloads.
+    ///
+    /// Returns the length of the body.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails, the status code is an error
+    /// or the body cannot be read.
+    pub async fn get_into(&mut self, url: &str, buf: &mut Vec<u8>) -> Result<usize> {
+        let mut response = check_body(self.get(url).await?).await?;
+        let start = buf.len();
+        if let Some(len) = response.content_length() {
+            buf.reserve(usize::try_from(len).unwrap_or_default());
+        }
+        while let Some(chunk) = response.chunk().await.map_err(Error::from_reqwest)? {
+            buf.extend_from_slice(&chunk);
         }
+        Ok(buf.len() - start)
+    }
 
-        let resp = self.req_client.get(url).send().await?;
-        self.last_checked = Utc::now();
-        trace!(
-            "Updated the client last checked time: {}",
-            self.last_checked
-        );
-        Ok(resp)
+    /// Sends a request built from the reqwest client.
+    ///
+    /// Every request of the crate goes through here, and is tallied in [`Client::health`].
+    pub(crate) async fn send(
+        &mut self,
+        request: reqwest::RequestBuilder,
+    ) -> std::result::Result<Response, Error> {
+        let mut request = request.build().map_err(Error::from_reqwest)?;
+        for name in self.headers.keys() {
+            if !request.headers().contains_key(name) {
+                for value in self.headers.get_all(name) {
+                    request.headers_mut().append(name, value.clone());
+                }
+            }
+        }
+        let url = request.url().to_string();
+        let start = std::time::Instant::now();
+        let response = self.dispatch(request).await;
+        self.pacer.observe(&response, start.elapsed());
+        self.health.record(&url, &response);
+        response
+    }
+
+    /// Sends a request through the cassette or WARC recorder, if any.
+    async fn dispatch(
+        &mut self,
+        request: reqwest::Request,
+    ) -> std::result::Result<Response, Error> {
+        #[cfg(feature = "cassette")]
+        if let Some(cassette) = &mut self.cassette {
+            if cassette.is_replaying() {
+                return cassette.play(&request);
+            }
+            let url = request.url().clone();
+            let validators = cassette::validators(request.headers());
+            let response = self
+                .req_client
+                .execute(request)
+                .await
+                .map_err(Error::from_reqwest)?;
+            return cassette
+                .record_response(url, validators, response)
+                .await
+                .map_err(Error::from_reqwest);
+        }
+        let response = self
+            .req_client
+            .execute(request)
+            .await
+            .map_err(Error::from_reqwest)?;
+        #[cfg(feature = "warc")]
+        if let Some(recorder) = &mut self.recorder {
+            return recorder.record(response).await.map_err(Error::from_reqwest);
+        }
+        Ok(response)
+    }
+
+    /// Starts recording every response into a WARC file.
+    ///
+    /// Records are appended if the file already exists.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file cannot be opened.
+    #[cfg(feature = "warc")]
+    pub fn record_warc<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<()> {
+        self.recorder = Some(warc::WarcWriter::open(path)?);
+        info!("recording responses into a WARC file.");
+        Ok(())
+    }
+
+    /// Stops recording responses.
+    #[cfg(feature = "warc")]
+    pub fn stop_recording(&mut self) {
+        self.recorder = None;
+    }
+
+    /// Starts recording every response into a cassette directory, see [`cassette`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directory cannot be created or read.
+    #[cfg(feature = "cassette")]
+    pub fn record_cassette<P: AsRef<std::path::Path>>(&mut self, dir: P) -> Result<()> {
+        self.cassette = Some(cassette::Cassette::record(dir)?);
+        info!("recording responses into a cassette.");
+        Ok(())
+    }
+
+    /// Answers every request from a recorded cassette directory instead of the network.
+    ///
+    /// Requests without a recorded response fail with an [`Error::Unrecorded`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the cassette cannot be read.
+    #[cfg(feature = "cassette")]
+    pub fn replay_cassette<P: AsRef<std::path::Path>>(
+        &mut self,
+        dir: P,
+        matcher: cassette::Match,
+    ) -> Result<()> {
+        self.cassette = Some(cassette::Cassette::replay(dir, matcher)?);
+        info!("replaying responses from a cassette.");
+        Ok(())
+    }
+
+    /// Stops recording or replaying a cassette.
+    #[cfg(feature = "cassette")]
+    pub fn eject_cassette(&mut self) {
+        self.cassette = None;
+    }
+
+    /// Sets the archive threads of a board are fetched from once they are gone from 4chan.
+    ///
+    /// Replaces the archive previously set for the board.
+    pub fn add_resolver<R: external::Resolver + 'static>(&mut self, board: &str, resolver: R) {
+        self.resolvers.insert(board.to_string(), Arc::new(resolver));
+    }
+
+    /// Removes the archive of a board.
+    ///
+    /// Returns false if the board had no archive of its own.
+    pub fn remove_resolver(&mut self, board: &str) -> bool {
+        self.resolvers.remove(board).is_some()
+    }
+
+    /// Sets the archive used for boards without an archive of their own.
+    pub fn set_resolver<R: external::Resolver + 'static>(&mut self, resolver: R) {
+        self.fallback = Some(Arc::new(resolver));
+    }
+
+    /// Sets whether malformed posts are skipped instead of failing the whole response.
+    ///
+    /// In lenient mode, a post of a thread or an entry of a catalog which does not match
+    /// the expected schema is left out and recorded as a [`post::SkippedPost`],
+    /// see [`thread::Thread::skipped`] and [`catalog::Catalog::skipped`].
+    /// A malformed OP still fails the thread.
+    ///
+    /// Off by default.
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Sets whether board codes are checked against the list of boards before fetching.
+    ///
+    /// When on, fetching a thread, catalog or archive of a board which does not exist
+    /// fails early with an [`Error::UnknownBoard`] instead of a 404.
+    /// The list is fetched once, on the first check.
+    ///
+    /// Off by default.
+    pub fn set_validate_boards(&mut self, validate: bool) {
+        self.validate_boards = validate;
+    }
+
+    /// Sets a header sent with every request, replacing its previous value.
+    ///
+    /// Headers the crate sets on a request, such as `If-Modified-Since`,
+    /// and those passed to [`Client::get_with`] take precedence.
+    pub fn set_header(&mut self, name: http::HeaderName, value: http::HeaderValue) {
+        self.headers.insert(name, value);
+    }
+
+    /// Stops sending a header set with [`Client::set_header`].
+    ///
+    /// Returns false if the header was not set.
+    pub fn remove_header(&mut self, name: &http::HeaderName) -> bool {
+        self.headers.remove(name).is_some()
+    }
+
+    /// Returns the headers sent with every request.
+    pub fn headers(&self) -> &http::HeaderMap {
+        &self.headers
+    }
+
+    /// Sets the policy deciding whether failed requests are retried, see [`retry`].
+    pub fn set_retry_policy<P: retry::RetryPolicy + 'static>(&mut self, policy: P) {
+        self.retry = Arc::new(policy);
+    }
+
+    /// Sets the timer which waits out the cooldowns, see [`timer`].
+    pub fn set_timer<T: timer::Timer + 'static>(&mut self, timer: T) {
+        self.timer = Arc::new(timer);
+    }
+
+    /// Sets the most time added at random to every sleep of the crate.
+    ///
+    /// Request and update cooldowns, retries and [`scheduler`] intervals are stretched by
+    /// a random part of `jitter`, so that many watchers sharing a client do not all wake
+    /// up at once and queue for the next request. Sleeps are never shortened.
+    ///
+    /// No jitter by default.
+    pub fn set_jitter(&mut self, jitter: std::time::Duration) {
+        self.jitter = jitter;
+    }
+
+    /// Sets how many requests may be sent back to back after the client was idle.
+    ///
+    /// A permit is saved for every interval without a request, up to `burst`,
+    /// so a thread and a few of its thumbnails can be fetched at once
+    /// while staying at one request per second on average. Once the permits are used up,
+    /// requests are a second apart again. The client starts over with all `burst` permits.
+    ///
+    /// Every request of the client takes a permit, conditional updates of threads,
+    /// catalogs and archives included.
+    ///
+    /// A burst of 1 by default, a burst of 0 is taken as 1.
+    pub fn set_burst(&mut self, burst: u32) {
+        self.burst = burst.max(1);
+        self.permits = self.burst;
+    }
+
+    /// Sets how the interval between requests adapts to server trouble, see [`pacing`].
+    ///
+    /// The interval starts over at a second.
+    pub fn set_pacing(&mut self, pacing: pacing::Adaptive) {
+        self.pacer.set_policy(Some(pacing));
+    }
+
+    /// Goes back to a fixed interval of a second between requests.
+    pub fn fixed_pacing(&mut self) {
+        self.pacer.set_policy(None);
+    }
+
+    /// Sets a callback receiving every change of the interval between requests.
+    ///
+    /// Replaces the previous callback.
+    pub fn on_pacing<F: Fn(&pacing::Change) + Send + Sync + 'static>(&mut self, callback: F) {
+        self.pacer.set_callback(Arc::new(callback));
+    }
+
+    /// Returns the current interval between requests.
+    pub fn interval(&self) -> std::time::Duration {
+        self.pacer.interval()
+    }
+
+    /// Returns a handle to the tallies of every request sent, see [`health`].
+    ///
+    /// The handle can be kept and read without locking the client.
+    pub fn health(&self) -> health::Health {
+        self.health.clone()
+    }
+
+    /// Returns a snapshot of the health of the client, see [`health`].
+    pub fn status(&self) -> health::Status {
+        self.health.status()
+    }
+
+    /// Adds a random part of the jitter to a sleep.
+    pub(crate) fn jittered(&self, duration: std::time::Duration) -> std::time::Duration {
+        let jitter = u64::try_from(self.jitter.as_nanos()).unwrap_or(u64::MAX);
+        if jitter == 0 {
+            return duration;
+        }
+        // the keys of a new `RandomState` are random, so is the hash of nothing
+        let random = RandomState::new().build_hasher().finish();
+        duration + std::time::Duration::from_nanos(random % jitter)
+    }
+
+    /// Returns the archive threads of a board are fetched from once they are gone from 4chan.
+    pub(crate) fn resolver(&self, board: &str) -> Option<Arc<dyn external::Resolver>> {
+        self.resolvers
+            .get(board)
+            .or(self.fallback.as_ref())
+            .cloned()
     }
 }
 
 /// Type alias for an client in an Arc<Mutex<Client>>
 type Dot4chClient = Arc<Mutex<Client>>;
 
+/// Waits for `duration` and some jitter on the timer of the client.
+pub(crate) async fn sleep(client: &Dot4chClient, duration: std::time::Duration) {
+    let (timer, duration) = {
+        let client = client.lock().await;
+        (client.timer.clone(), client.jittered(duration))
+    };
+    timer.sleep(duration).await;
+}
+
+/// Builds a [`Client`], applying settings before it is shared.
+///
+/// # Example
+///
+/// ```
+/// # use dot4ch::{Client, http::{header::USER_AGENT, HeaderValue}, retry::Backoff};
+/// # use std::time::Duration;
+/// let client = Client::builder()
+///     .header(USER_AGENT, HeaderValue::from_static("my-archiver/1.0"))
+///     .jitter(Duration::from_millis(500))
+///     .burst(3)
+///     .retry_policy(Backoff::new(3))
+///     .lenient(true)
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct ClientBuilder {
+    /// The client being configured
+    client: Client,
+}
+
+impl ClientBuilder {
+    /// Sets the most time added at random to every sleep, see [`Client::set_jitter`].
+    #[must_use]
+    pub fn jitter(mut self, jitter: std::time::Duration) -> Self {
+        self.client.set_jitter(jitter);
+        self
+    }
+
+    /// Sets how many requests may be sent back to back, see [`Client::set_burst`].
+    #[must_use]
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.client.set_burst(burst);
+        self
+    }
+
+    /// Sets how the interval between requests adapts, see [`Client::set_pacing`].
+    #[must_use]
+    pub fn pacing(mut self, pacing: pacing::Adaptive) -> Self {
+        self.client.set_pacing(pacing);
+        self
+    }
+
+    /// Sets the policy deciding whether failed requests are retried, see [`retry`].
+    #[must_use]
+    pub fn retry_policy<P: retry::RetryPolicy + 'static>(mut self, policy: P) -> Self {
+        self.client.set_retry_policy(policy);
+        self
+    }
+
+    /// Sets the timer which waits out the cooldowns, see [`timer`].
+    #[must_use]
+    pub fn timer<T: timer::Timer + 'static>(mut self, timer: T) -> Self {
+        self.client.set_timer(timer);
+        self
+    }
+
+    /// Sets whether malformed posts are skipped, see [`Client::set_lenient`].
+    #[must_use]
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.client.set_lenient(lenient);
+        self
+    }
+
+    /// Sets a header sent with every request, see [`Client::set_header`].
+    #[must_use]
+    pub fn header(mut self, name: http::HeaderName, value: http::HeaderValue) -> Self {
+        self.client.set_header(name, value);
+        self
+    }
+
+    /// Sets whether board codes are checked before fetching, see [`Client::set_validate_boards`].
+    #[must_use]
+    pub fn validate_boards(mut self, validate: bool) -> Self {
+        self.client.set_validate_boards(validate);
+        self
+    }
+
+    /// Returns the configured client, ready to be shared.
+    pub fn build(self) -> Arc<Mutex<Client>> {
+        info!("constructed chan client.");
+        Arc::new(Mutex::new(self.client))
+    }
+}
+
 /// Returns an If-Modified-Since header to be used in requests.
 pub(crate) async fn header(client: &Dot4chClient) -> String {
     trace!("Sending request with If-Modified-Since header");
@@ -194,6 +774,236 @@ pub(crate) async fn header(client: &Dot4chClient) -> String {
     )
 }
 
+/// Returns the `Last-Modified` header of a response, if it has one.
+pub(crate) fn last_modified(response: &Response) -> Option<String> {
+    response
+        .headers()
+        .get(reqwest::header::LAST_MODIFIED)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Returns the rest of the 10 second cooldown of an update, `None` once it passed.
+pub(crate) fn cooldown(last: DateTime<Utc>) -> Option<std::time::Duration> {
+    let elapsed = Utc::now().signed_duration_since(last);
+    Duration::seconds(10)
+        .checked_sub(&elapsed)
+        .and_then(|remaining| remaining.to_std().ok())
+        .filter(|remaining| !remaining.is_zero())
+}
+
+/// Returns true if 4chan answered with an error status code.
+fn is_error(response: &Response) -> bool {
+    response.status().is_client_error() || response.status().is_server_error()
+}
+
+/// Returns an error if 4chan answered with an error status code.
+///
+/// The body is not read, so the response can still be used.
+pub(crate) fn check(response: &Response) -> std::result::Result<(), Error> {
+    if is_error(response) {
+        return Err(Error::unexpected(response));
+    }
+    Ok(())
+}
+
+/// Returns the response if 4chan answered with a success status code.
+///
+/// Otherwise returns an error with the start of the body.
+pub(crate) async fn check_body(response: Response) -> std::result::Result<Response, Error> {
+    if is_error(&response) {
+        return Err(Error::unexpected_with_body(response).await);
+    }
+    Ok(response)
+}
+
+/// Like [`check_body`], but turns a 404 into [`Error::NotFound`] for the board and thread.
+pub(crate) async fn check_in(
+    response: Response,
+    board: &str,
+    id: Option<u32>,
+) -> std::result::Result<Response, Error> {
+    if response.status() == StatusCode::NOT_FOUND {
+        return Err(Error::NotFound {
+            board: board.to_string(),
+            id,
+        });
+    }
+    check_body(response).await
+}
+
+/// Reads the JSON body of a response.
+pub(crate) async fn json<T: DeserializeOwned>(response: Response) -> std::result::Result<T, Error> {
+    response.json::<T>().await.map_err(Error::from_reqwest)
+}
+
+/// Splits the bytes of a JSON array of objects into its elements as they arrive.
+#[derive(Debug, Default)]
+pub(crate) struct ArraySplitter {
+    /// The bytes which are not part of a finished element yet
+    buf: Vec<u8>,
+    /// How many bytes of `buf` were scanned
+    scanned: usize,
+    /// The nesting depth at the end of the scanned bytes, 1 inside the array
+    depth: usize,
+    /// If the scanned bytes end inside a string
+    in_string: bool,
+    /// If the scanned bytes end right after a backslash in a string
+    escaped: bool,
+    /// Where the element being scanned starts in `buf`
+    start: Option<usize>,
+    /// If the closing bracket of the array was seen
+    done: bool,
+}
+
+impl ArraySplitter {
+    /// Adds a chunk of the body, returning the elements it finished.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> Vec<Vec<u8>> {
+        self.buf.extend_from_slice(chunk);
+        let mut elements = vec![];
+        for idx in self.scanned..self.buf.len() {
+            let byte = self.buf[idx];
+            if self.in_string {
+                match byte {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match byte {
+                b'"' => self.in_string = true,
+                b'{' | b'[' => {
+                    self.depth += 1;
+                    if self.depth == 2 {
+                        self.start = Some(idx);
+                    }
+                }
+                b'}' | b']' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    match (self.depth, self.start) {
+                        (0, _) => self.done = true,
+                        (1, Some(start)) => {
+                            elements.push(self.buf[start..=idx].to_vec());
+                            self.start = None;
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        // only keep the unfinished element
+        let keep = self.start.unwrap_or(self.buf.len());
+        self.buf.drain(..keep);
+        self.start = self.start.map(|_| 0);
+        self.scanned = self.buf.len();
+        elements
+    }
+
+    /// Returns true once the whole array was read.
+    pub(crate) fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
+/// Parses the elements of a JSON array of objects in a response as its body arrives,
+/// without holding the whole body in memory.
+pub(crate) fn stream_array<T: DeserializeOwned>(
+    response: Response,
+) -> impl Stream<Item = Result<T>> {
+    let state = (
+        response,
+        ArraySplitter::default(),
+        VecDeque::<Vec<u8>>::new(),
+    );
+    stream::try_unfold(
+        state,
+        |(mut response, mut splitter, mut parsed)| async move {
+            loop {
+                if let Some(element) = parsed.pop_front() {
+                    let element = serde_json::from_slice::<T>(&element)?;
+                    return Ok(Some((element, (response, splitter, parsed))));
+                }
+                match response.chunk().await.map_err(Error::from_reqwest)? {
+                    Some(chunk) => parsed.extend(splitter.feed(&chunk)),
+                    None if splitter.is_done() => return Ok(None),
+                    None => return Err(anyhow::anyhow!("The JSON array was cut off")),
+                }
+            }
+        },
+    )
+}
+
+/// Fetches a JSON endpoint of 4chan along with its `Last-Modified` header.
+///
+/// Errors are wrapped in a context naming the operation, such as
+/// `fetching thread /g/12345`, and the URL.
+pub(crate) async fn fetch_json<T: DeserializeOwned>(
+    client: &Dot4chClient,
+    url: &str,
+    board: &str,
+    id: Option<u32>,
+    operation: &str,
+) -> Result<(T, Option<String>)> {
+    let fetch = async {
+        let response = client.lock().await.get(url).await?;
+        let response = check_in(response, board, id).await?;
+        let last_modified = last_modified(&response);
+        Ok::<_, anyhow::Error>((json::<T>(response).await?, last_modified))
+    };
+    fetch.await.with_context(|| format!("{operation} ({url})"))
+}
+
+/// Sends the conditional request updating a model from a JSON endpoint.
+///
+/// The `If-Modified-Since` header is the `Last-Modified` header of the previous response,
+/// or the time of the last request of the client if there was none.
+///
+/// Returns `None` if the endpoint did not change, otherwise the response to parse.
+pub(crate) async fn refresh(
+    client: &Dot4chClient,
+    url: &str,
+    board: &str,
+    id: Option<u32>,
+    last_modified: Option<&str>,
+) -> Result<Option<Response>> {
+    let since = match last_modified {
+        Some(last_modified) => last_modified.to_string(),
+        None => header(client).await,
+    };
+    let response = get_since(client, url, &since).await?;
+    let response = check_in(response, board, id).await?;
+    match response.status() {
+        StatusCode::OK => Ok(Some(response)),
+        StatusCode::NOT_MODIFIED => Ok(None),
+        _ => Err(Error::unexpected(&response).into()),
+    }
+}
+
+/// Sends a GET request with an `If-Modified-Since` header, paced like [`Client::get`].
+pub(crate) async fn get_since(
+    client: &Dot4chClient,
+    url: &str,
+    since: &str,
+) -> std::result::Result<Response, Error> {
+    let mut client = client.lock().await;
+    client.pace().await;
+    let request = client
+        .req_client()
+        .get(url)
+        .header(http::header::IF_MODIFIED_SINCE, since);
+    let response = client.send(request).await;
+    client.last_checked = Utc::now();
+    response
+}
+
+/// Returns true if the error was caused by a 404 response.
+pub(crate) fn is_not_found(err: &anyhow::Error) -> bool {
+    Error::find(err).is_some_and(Error::is_not_found)
+}
+
 /// Helper trait that sends a GET request from the reqwest client
 /// with a If-Modified-Since header.
 #[async_trait(?Send)]
@@ -203,7 +1013,7 @@ pub trait IfModifiedSince {
         client: &Dot4chClient,
         url: &str,
         header: &str,
-    ) -> std::result::Result<Response, reqwest::Error>;
+    ) -> std::result::Result<Response, Error>;
 }
 
 /// Update trait specifies if something can be updated or not.
@@ -265,6 +1075,73 @@ pub trait Update {
     async fn update(mut self) -> Result<Self::Output>;
 }
 
+/// Whether an update found anything new.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UpdateStatus {
+    /// The endpoint changed and the model was replaced with its new content
+    Modified,
+    /// The endpoint answered `304 Not Modified`, the model is unchanged
+    NotModified,
+}
+
+impl UpdateStatus {
+    /// Returns true if the update found anything new.
+    pub fn is_modified(self) -> bool {
+        self == Self::Modified
+    }
+}
+
+impl<T> From<&http::Reply<T>> for UpdateStatus {
+    fn from(reply: &http::Reply<T>) -> Self {
+        if reply.is_modified() {
+            Self::Modified
+        } else {
+            Self::NotModified
+        }
+    }
+}
+
+/// Updating a model in place, telling whether it changed.
+///
+/// Implemented by [`Thread`](thread::Thread), [`LazyThread`](thread::LazyThread),
+/// [`Catalog`](catalog::Catalog), [`Archive`](archive::Archive), [`Board`](board::Board)
+/// and [`Boards`](boards::Boards), so schedulers and generic helpers can drive them alike.
+///
+/// The method shares its name with [`Update::update`], which takes `self` by value;
+/// with both traits in scope, call it as `Updatable::update(&mut model, &client)`.
+///
+/// # Example
+///
+/// ```
+/// # use dot4ch::{Client, thread::Thread, Updatable, UpdateStatus};
+/// # use std::sync::Arc;
+/// # use tokio::sync::Mutex;
+/// async fn poll<T: Updatable>(model: &mut T, client: &Arc<Mutex<Client>>) -> anyhow::Result<bool> {
+///     Ok(model.update(client).await? == UpdateStatus::Modified)
+/// }
+///
+/// # async fn update() -> anyhow::Result<()> {
+/// let client = Client::new();
+/// let mut thread = Thread::new(&client, "g", 76759434).await?;
+/// if poll(&mut thread, &client).await? {
+///     println!("{} replies", thread.op().replies());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait(?Send)]
+pub trait Updatable {
+    /// Updates `self` in place with `client`, which it keeps for later requests.
+    ///
+    /// Returns [`UpdateStatus::NotModified`] if the endpoint did not change.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an [`Error::Cooldown`] if a thread, catalog or archive
+    /// was updated less than 10 seconds ago, or an error if the request fails.
+    async fn update(&mut self, client: &Dot4chClient) -> Result<UpdateStatus>;
+}
+
 /// Another helper trait for the [`Update`] trait.
 #[async_trait(?Send)]
 pub trait Procedures {
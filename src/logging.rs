@@ -0,0 +1,25 @@
+//! The log macros of the crate.
+//!
+//! With the `log` feature they are those of the `log` crate. Without it they compile
+//! to nothing, their arguments are only type checked.
+
+// `error` is only logged by the `cassette` and `warc` features
+#[cfg(feature = "log")]
+#[allow(unused_imports)]
+pub(crate) use log::{debug, error, info, trace, warn};
+
+/// Discards a log message.
+#[cfg(not(feature = "log"))]
+macro_rules! discard {
+    ($($arg:tt)+) => {
+        if false {
+            let _ = format!($($arg)+);
+        }
+    };
+}
+
+#[cfg(not(feature = "log"))]
+#[allow(unused_imports)]
+pub(crate) use {
+    discard as debug, discard as error, discard as info, discard as trace, discard as warn,
+};
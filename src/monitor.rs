@@ -0,0 +1,685 @@
+//! Monitoring catalogs and threads across several boards.
+//!
+//! A [`Monitor`] keeps the catalogs of the watched boards and the watched threads up to date
+//! under one client, and reports what changed as a single list of [`Event`]s per poll,
+//! or as one merged stream with [`Monitor::events`].
+//!
+//! A catalog or thread still in its cooldown is skipped until the next poll
+//! instead of holding up the others.
+//! A failed update is reported as [`Event::Failed`] and tried again on the next poll.
+//!
+//! # Backpressure
+//!
+//! With [`Monitor::set_budget`], a poll updates at most that many catalogs and threads.
+//! The rest wait in a queue for the next poll, in this order:
+//! - updates past their deadline, see [`Monitor::set_deadline`], longest waiting first,
+//! - then by [`Priority`],
+//! - then longest waiting first.
+//!
+//! The depth of the queue is reported in [`Status::queue`].
+//!
+//! # Shutting down
+//!
+//! Dropping a monitor in the middle of a poll loses the events of that poll.
+//! [`Monitor::shutdown`] instead stops polling, drains the queue for a grace period
+//! and returns the last events, and the stream of [`Monitor::events`] ends
+//! after the poll in progress once its [`Shutdown`] signal is triggered.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, monitor::{Event, Monitor}};
+//! # use futures::StreamExt;
+//! # async fn monitor() -> anyhow::Result<()> {
+//! let client = Client::new();
+//! let mut monitor = Monitor::new(&client);
+//! monitor.watch_board("g");
+//! monitor.watch_board("sci");
+//! monitor.watch_thread("g", 76759434);
+//!
+//! let events = monitor.events();
+//! futures::pin_mut!(events);
+//! while let Some(event) = events.next().await {
+//!     match event {
+//!         Event::NewThread { board, thread } => println!("new thread /{}/{}", board, thread.id()),
+//!         Event::NewPosts { board, thread, posts } => {
+//!             println!("{} new posts in /{}/{}", posts.len(), board, thread)
+//!         }
+//!         _ => {}
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    catalog::{Catalog, CatalogThread},
+    logging::{debug, warn},
+    post::Post,
+    scheduler::Shutdown,
+    thread::Thread,
+    Dot4chClient, Error, UpdateStatus,
+};
+use chrono::{DateTime, Utc};
+use futures::{stream, Stream, StreamExt};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashSet},
+    time::{Duration, Instant},
+};
+
+/// The least time between two polls, the cooldown of a catalog or thread update.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The default time after which a queued update is overdue.
+const DEADLINE: Duration = Duration::from_secs(30);
+
+/// A change on a watched board or thread.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Event {
+    /// A thread appeared in the catalog of a watched board
+    NewThread {
+        /// The board of the thread
+        board: String,
+        /// The thread as listed in the catalog
+        thread: CatalogThread,
+    },
+    /// A watched thread got new posts
+    NewPosts {
+        /// The board of the thread
+        board: String,
+        /// The OP number of the thread
+        thread: u32,
+        /// The new posts, oldest first
+        posts: Vec<Post>,
+    },
+    /// A watched thread got archived and is no longer watched
+    Archived {
+        /// The board of the thread
+        board: String,
+        /// The OP number of the thread
+        thread: u32,
+    },
+    /// A watched thread is gone from 4chan and is no longer watched
+    Gone {
+        /// The board of the thread
+        board: String,
+        /// The OP number of the thread
+        thread: u32,
+    },
+    /// Updating a catalog or thread failed, it is tried again on the next poll
+    Failed {
+        /// The board of the catalog or thread
+        board: String,
+        /// The thread, `None` for the catalog
+        thread: Option<u32>,
+        /// The error, formatted with its whole chain
+        error: String,
+    },
+}
+
+/// How urgently a catalog or thread is updated when a poll cannot update everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    /// Updated after everything else
+    Low,
+    /// The priority of [`Monitor::watch_board`] and [`Monitor::watch_thread`]
+    #[default]
+    Normal,
+    /// Updated before everything else which is not overdue
+    High,
+}
+
+/// The state of a watched board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardStatus {
+    /// The board
+    pub board: String,
+    /// The number of threads in the catalog, `None` if the catalog is not watched or not fetched yet
+    pub catalog_threads: Option<usize>,
+    /// The watched threads of the board
+    pub watched_threads: Vec<u32>,
+    /// When the catalog or a thread of the board was last updated
+    pub last_update: Option<DateTime<Utc>>,
+    /// The error of the last failed update, cleared by the next successful one
+    pub last_error: Option<String>,
+    /// The number of updates of the board which failed since the last successful one
+    pub consecutive_failures: u32,
+}
+
+/// The queue of updates left for later polls, as of the last poll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QueueMetrics {
+    /// The number of updates done by the last poll
+    pub done: usize,
+    /// The number of due updates left for a later poll
+    pub depth: usize,
+    /// How many of the queued updates are past their deadline
+    pub overdue: usize,
+    /// The longest time a queued update has waited since its last update
+    pub oldest_wait: Duration,
+}
+
+/// A snapshot of the state of a [`Monitor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    /// Every watched board, in the order they were first watched
+    pub boards: Vec<BoardStatus>,
+    /// The number of polls so far
+    pub polls: u64,
+    /// The queue of updates after the last poll
+    pub queue: QueueMetrics,
+}
+
+/// When a watched catalog or thread is updated.
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    /// How urgently it is updated
+    priority: Priority,
+    /// When it was last updated, or watched if it never was
+    since: Instant,
+}
+
+impl Slot {
+    /// Creates the slot of something watched just now.
+    fn new(priority: Priority) -> Self {
+        Self {
+            priority,
+            since: Instant::now(),
+        }
+    }
+}
+
+/// A watched thread.
+#[derive(Debug)]
+struct WatchedThread {
+    /// When the thread is updated
+    slot: Slot,
+    /// The thread, `None` until fetched
+    thread: Option<Thread>,
+}
+
+/// A watched board.
+#[derive(Debug)]
+struct Watched {
+    /// The board
+    board: String,
+    /// When the catalog is updated, `None` if it is not watched
+    catalog_slot: Option<Slot>,
+    /// The catalog, once fetched
+    catalog: Option<Catalog>,
+    /// The watched threads
+    threads: BTreeMap<u32, WatchedThread>,
+    /// When the catalog or a thread was last updated
+    last_update: Option<DateTime<Utc>>,
+    /// The error of the last failed update
+    last_error: Option<String>,
+    /// The number of updates which failed since the last successful one
+    consecutive_failures: u32,
+}
+
+impl Watched {
+    /// Starts watching nothing on a board.
+    fn new(board: &str) -> Self {
+        Self {
+            board: board.to_string(),
+            catalog_slot: None,
+            catalog: None,
+            threads: BTreeMap::new(),
+            last_update: None,
+            last_error: None,
+            consecutive_failures: 0,
+        }
+    }
+
+    /// Returns the updates of the board which are not in their cooldown.
+    fn due(&self, board: usize) -> impl Iterator<Item = Job> + '_ {
+        let catalog = self
+            .catalog_slot
+            .filter(|_| {
+                self.catalog
+                    .as_ref()
+                    .is_none_or(|catalog| catalog.cooldown().is_none())
+            })
+            .map(|slot| Job {
+                board,
+                thread: None,
+                slot,
+            });
+        let threads = self
+            .threads
+            .iter()
+            .filter(|(_, watched)| {
+                watched
+                    .thread
+                    .as_ref()
+                    .is_none_or(|thread| thread.cooldown().is_none())
+            })
+            .map(move |(&id, watched)| Job {
+                board,
+                thread: Some(id),
+                slot: watched.slot,
+            });
+        catalog.into_iter().chain(threads)
+    }
+
+    /// Records the outcome of an update, turning an error into an event.
+    fn outcome(&mut self, thread: Option<u32>, result: crate::Result<()>) -> Option<Event> {
+        match result {
+            Ok(()) => {
+                self.last_update = Some(Utc::now());
+                self.last_error = None;
+                self.consecutive_failures = 0;
+                None
+            }
+            Err(err) => {
+                let error = format!("{err:#}");
+                warn!("Could not update /{}/: {}", self.board, error);
+                self.last_error = Some(error.clone());
+                self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+                Some(Event::Failed {
+                    board: self.board.clone(),
+                    thread,
+                    error,
+                })
+            }
+        }
+    }
+}
+
+/// A due update of a catalog or thread.
+#[derive(Debug, Clone, Copy)]
+struct Job {
+    /// The index of the board
+    board: usize,
+    /// The thread, `None` for the catalog
+    thread: Option<u32>,
+    /// When the catalog or thread is updated
+    slot: Slot,
+}
+
+impl Job {
+    /// Returns true if the update waited longer than the deadline.
+    fn is_overdue(&self, deadline: Duration) -> bool {
+        self.slot.since.elapsed() > deadline
+    }
+}
+
+/// Watches catalogs and threads across several boards under one client.
+#[derive(Debug)]
+pub struct Monitor {
+    /// the client
+    client: Dot4chClient,
+    /// The watched boards, in the order they were first watched
+    boards: Vec<Watched>,
+    /// The least time between two polls
+    interval: Duration,
+    /// The most updates of a poll, unbounded if `None`
+    budget: Option<usize>,
+    /// The time after which a queued update is overdue
+    deadline: Duration,
+    /// When the last poll happened
+    last_poll: Option<Instant>,
+    /// The number of polls so far
+    polls: u64,
+    /// The queue after the last poll
+    queue: QueueMetrics,
+    /// Stops polling once triggered
+    shutdown: Shutdown,
+}
+
+impl Monitor {
+    /// Creates a monitor which watches nothing yet.
+    pub fn new(client: &Dot4chClient) -> Self {
+        Self {
+            client: client.clone(),
+            boards: vec![],
+            interval: POLL_INTERVAL,
+            budget: None,
+            deadline: DEADLINE,
+            last_poll: None,
+            polls: 0,
+            queue: QueueMetrics::default(),
+            shutdown: Shutdown::new(),
+        }
+    }
+
+    /// Sets the least time between two polls.
+    ///
+    /// A catalog or thread is still not updated more often than its 10 second cooldown,
+    /// a shorter interval only skips it on more polls.
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// Sets the most catalogs and threads a poll updates, leaving the rest queued.
+    ///
+    /// Unbounded by default.
+    pub fn set_budget(&mut self, updates: usize) {
+        self.budget = Some(updates.max(1));
+    }
+
+    /// Sets the time since its last update after which a queued update is overdue.
+    ///
+    /// Overdue updates go before any other, whatever their [`Priority`].
+    /// 30 seconds by default.
+    pub fn set_deadline(&mut self, deadline: Duration) {
+        self.deadline = deadline;
+    }
+
+    /// Watches the catalog of a board for new threads.
+    pub fn watch_board(&mut self, board: &str) {
+        self.watch_board_with(board, Priority::Normal);
+    }
+
+    /// Watches the catalog of a board for new threads with a [`Priority`].
+    pub fn watch_board_with(&mut self, board: &str, priority: Priority) {
+        self.watched(board).catalog_slot = Some(Slot::new(priority));
+    }
+
+    /// Watches a thread for new posts.
+    pub fn watch_thread(&mut self, board: &str, id: u32) {
+        self.watch_thread_with(board, id, Priority::Normal);
+    }
+
+    /// Watches a thread for new posts with a [`Priority`].
+    pub fn watch_thread_with(&mut self, board: &str, id: u32, priority: Priority) {
+        let watched = self
+            .watched(board)
+            .threads
+            .entry(id)
+            .or_insert(WatchedThread {
+                slot: Slot::new(priority),
+                thread: None,
+            });
+        watched.slot.priority = priority;
+    }
+
+    /// Stops watching the catalog and every thread of a board.
+    ///
+    /// Returns false if the board was not watched.
+    pub fn unwatch_board(&mut self, board: &str) -> bool {
+        let len = self.boards.len();
+        self.boards.retain(|watched| watched.board != board);
+        len != self.boards.len()
+    }
+
+    /// Stops watching a thread.
+    ///
+    /// Returns false if the thread was not watched.
+    pub fn unwatch_thread(&mut self, board: &str, id: u32) -> bool {
+        self.boards
+            .iter_mut()
+            .find(|watched| watched.board == board)
+            .is_some_and(|watched| watched.threads.remove(&id).is_some())
+    }
+
+    /// Returns a snapshot of every watched board and of the queue.
+    ///
+    /// The health of the requests themselves is reported by the client,
+    /// see [`Client::status`](crate::Client::status).
+    pub fn status(&self) -> Status {
+        Status {
+            boards: self
+                .boards
+                .iter()
+                .map(|watched| BoardStatus {
+                    board: watched.board.clone(),
+                    catalog_threads: watched
+                        .catalog
+                        .as_ref()
+                        .map(|catalog| catalog.threads().count()),
+                    watched_threads: watched.threads.keys().copied().collect(),
+                    last_update: watched.last_update,
+                    last_error: watched.last_error.clone(),
+                    consecutive_failures: watched.consecutive_failures,
+                })
+                .collect(),
+            polls: self.polls,
+            queue: self.queue,
+        }
+    }
+
+    /// Updates the watched catalogs and threads which are due and returns what changed.
+    ///
+    /// The first poll of a catalog only takes note of the threads in it,
+    /// later polls report the threads which appeared since.
+    /// Catalogs and threads still in their cooldown are skipped, and updates over the
+    /// budget are queued for the next poll.
+    ///
+    /// Waits if called sooner than the interval after the previous poll.
+    /// Returns nothing once the monitor is shut down.
+    pub async fn poll(&mut self) -> Vec<Event> {
+        let wait = self
+            .last_poll
+            .and_then(|last| self.interval.checked_sub(last.elapsed()));
+        if let Some(wait) = wait {
+            debug!("Polling too quickly! Waiting for {wait:?}");
+            tokio::select! {
+                () = self.shutdown.triggered() => {}
+                () = crate::sleep(&self.client, wait) => {}
+            }
+        }
+        if self.shutdown.is_triggered() {
+            return vec![];
+        }
+        self.last_poll = Some(Instant::now());
+        self.polls += 1;
+
+        let mut jobs = self.jobs();
+        let queued = jobs.split_off(self.budget.unwrap_or(usize::MAX).min(jobs.len()));
+        let mut events = vec![];
+        for job in &jobs {
+            events.extend(self.run(job).await);
+        }
+        self.measure(jobs.len(), &queued);
+        events
+    }
+
+    /// Returns a signal which shuts the monitor down, see [`Monitor::shutdown`].
+    ///
+    /// Triggering it ends the stream of [`Monitor::events`] after the poll in progress
+    /// and its events.
+    pub fn shutdown_signal(&self) -> Shutdown {
+        self.shutdown.clone()
+    }
+
+    /// Stops polling and drains the updates which are due, for up to `grace`.
+    ///
+    /// Updates are started in queue order, ignoring the budget, until `grace` runs out.
+    /// An update in progress at that point is finished. Returns the events of the drained
+    /// updates, and the monitor keeps the final state of its catalogs and threads.
+    pub async fn shutdown(&mut self, grace: Duration) -> Vec<Event> {
+        self.shutdown.trigger();
+        let started = Instant::now();
+        let mut jobs = self.jobs().into_iter();
+        let mut done = 0;
+        let mut events = vec![];
+        while started.elapsed() < grace {
+            let Some(job) = jobs.next() else {
+                break;
+            };
+            events.extend(self.run(&job).await);
+            done += 1;
+        }
+        let queued: Vec<_> = jobs.collect();
+        self.measure(done, &queued);
+        if !queued.is_empty() {
+            warn!(
+                "Shut down with {} updates left after {grace:?}",
+                queued.len()
+            );
+        }
+        events
+    }
+
+    /// Turns the monitor into a stream of the events of every poll.
+    ///
+    /// The stream ends once the [`Monitor::shutdown_signal`] is triggered,
+    /// after the events of the poll in progress.
+    pub fn events(self) -> impl Stream<Item = Event> {
+        stream::unfold(self, |mut monitor| async move {
+            if monitor.shutdown.is_triggered() {
+                return None;
+            }
+            let events = monitor.poll().await;
+            Some((stream::iter(events), monitor))
+        })
+        .flatten()
+    }
+
+    /// Returns the updates which are due, in the order they are run.
+    fn jobs(&self) -> Vec<Job> {
+        let deadline = self.deadline;
+        let mut jobs: Vec<_> = self
+            .boards
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, watched)| watched.due(idx))
+            .collect();
+        jobs.sort_by_key(|job| {
+            // overdue updates go by age alone
+            let overdue = job.is_overdue(deadline);
+            let priority = if overdue {
+                Priority::High
+            } else {
+                job.slot.priority
+            };
+            (Reverse(overdue), Reverse(priority), job.slot.since)
+        });
+        jobs
+    }
+
+    /// Runs a single update and returns its events.
+    async fn run(&mut self, job: &Job) -> Vec<Event> {
+        let watched = &mut self.boards[job.board];
+        match job.thread {
+            None => update_catalog(&self.client, watched).await,
+            Some(id) => update_thread(&self.client, watched, id).await,
+        }
+    }
+
+    /// Records the queue left after running `done` updates.
+    fn measure(&mut self, done: usize, queued: &[Job]) {
+        let deadline = self.deadline;
+        self.queue = QueueMetrics {
+            done,
+            depth: queued.len(),
+            overdue: queued.iter().filter(|job| job.is_overdue(deadline)).count(),
+            oldest_wait: queued
+                .iter()
+                .map(|job| job.slot.since.elapsed())
+                .max()
+                .unwrap_or_default(),
+        };
+        if self.queue.depth > 0 {
+            debug!(
+                "{} updates queued, {} overdue",
+                self.queue.depth, self.queue.overdue
+            );
+        }
+    }
+
+    /// Returns a watched board, watching it if it is not yet.
+    fn watched(&mut self, board: &str) -> &mut Watched {
+        let known = self
+            .boards
+            .iter()
+            .position(|watched| watched.board == board);
+        let idx = known.unwrap_or_else(|| {
+            self.boards.push(Watched::new(board));
+            self.boards.len() - 1
+        });
+        &mut self.boards[idx]
+    }
+}
+
+/// Updates the catalog of a board and reports the new threads.
+async fn update_catalog(client: &Dot4chClient, watched: &mut Watched) -> Vec<Event> {
+    if let Some(slot) = &mut watched.catalog_slot {
+        slot.since = Instant::now();
+    }
+    let board = watched.board.clone();
+    let Some(catalog) = &mut watched.catalog else {
+        let result = Catalog::new(client, &board).await.map(|catalog| {
+            watched.catalog = Some(catalog);
+        });
+        return watched.outcome(None, result).into_iter().collect();
+    };
+    let known: HashSet<_> = catalog.threads().map(CatalogThread::id).collect();
+    let result = catalog.try_update().await;
+    let new: Vec<_> = match result {
+        Ok(UpdateStatus::Modified) => catalog
+            .threads()
+            .filter(|thread| !known.contains(&thread.id()))
+            .map(|&thread| Event::NewThread {
+                board: board.clone(),
+                thread,
+            })
+            .collect(),
+        Ok(UpdateStatus::NotModified) | Err(_) => vec![],
+    };
+    match watched.outcome(None, result.map(|_| ())) {
+        Some(failed) => vec![failed],
+        None => new,
+    }
+}
+
+/// Updates a watched thread and reports its new posts.
+async fn update_thread(client: &Dot4chClient, watched: &mut Watched, id: u32) -> Vec<Event> {
+    let board = watched.board.clone();
+    let Some(slot) = watched.threads.get_mut(&id) else {
+        return vec![];
+    };
+    slot.slot.since = Instant::now();
+
+    let mut events = vec![];
+    let mut archived = false;
+    let result = match &mut slot.thread {
+        None => Thread::new(client, &board, id).await.map(|thread| {
+            archived = thread.op().archived();
+            slot.thread = Some(thread);
+        }),
+        // an archived thread cannot be updated, it only needs to be reported
+        Some(thread) if thread.op().archived() => {
+            archived = true;
+            Ok(())
+        }
+        Some(thread) => {
+            let last = thread.last_post().map_or(id, Post::id);
+            let result = thread.try_update().await;
+            let modified = matches!(result, Ok(UpdateStatus::Modified));
+            let posts: Vec<_> = thread
+                .posts()
+                .filter(|post| modified && post.id() > last)
+                .cloned()
+                .collect();
+            if !posts.is_empty() {
+                events.push(Event::NewPosts {
+                    board: board.clone(),
+                    thread: id,
+                    posts,
+                });
+            }
+            archived = result.is_ok() && thread.op().archived();
+            result.map(|_| ())
+        }
+    };
+    if archived {
+        events.push(Event::Archived {
+            board: board.clone(),
+            thread: id,
+        });
+        watched.threads.remove(&id);
+    }
+    if let Err(err) = &result {
+        if Error::find(err).is_some_and(Error::is_not_found) {
+            events.push(Event::Gone { board, thread: id });
+            watched.threads.remove(&id);
+            return events;
+        }
+    }
+    events.extend(watched.outcome(Some(id), result));
+    events
+}
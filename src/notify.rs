@@ -0,0 +1,245 @@
+//! Keyword subscriptions on boards.
+//!
+//! A [`Notifier`] holds regex subscriptions per board and reports every
+//! new thread or post matching one of them as an [`Event`].
+//!
+//! Subjects and comments are matched as plain text, see [`Post::plain_text`].
+//! A post fires at most once per subscription.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, notify::{Notifier, Scope}};
+//! # use regex::Regex;
+//! # async fn notify() -> anyhow::Result<()> {
+//! # let client = Client::new();
+//! let mut notifier = Notifier::new(&client);
+//! let rust = notifier.subscribe("g", Regex::new("(?i)\\brust\\b")?, Scope::Posts);
+//!
+//! loop {
+//!     for event in notifier.poll().await? {
+//!         if event.subscription == rust {
+//!             println!("/{}/{}#p{}", event.board, event.thread, event.post.id());
+//!         }
+//!     }
+//! }
+//! # }
+//! ```
+
+use crate::{
+    board::{ops, CatalogOp},
+    logging::{debug, warn},
+    post::Post,
+    thread::Thread,
+    Dot4chClient,
+};
+use regex::Regex;
+use std::{
+    collections::{HashMap, HashSet},
+    iter,
+    time::{Duration, Instant},
+};
+
+/// The least time between two polls, as `catalog.json` is cached for 10 seconds.
+const POLL_COOLDOWN: Duration = Duration::from_secs(10);
+
+/// Identifies a subscription of a [`Notifier`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubscriptionId(usize);
+
+/// What a subscription is matched against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// The subject and comment of new threads in the catalog
+    Threads,
+    /// Every new post, OPs included
+    Posts,
+}
+
+/// A post matching a subscription.
+#[derive(Debug, Clone)]
+pub struct Event {
+    /// The subscription which matched
+    pub subscription: SubscriptionId,
+    /// The board of the post
+    pub board: String,
+    /// The OP number of the thread of the post
+    pub thread: u32,
+    /// The matching post
+    pub post: Post,
+}
+
+/// A registered subscription.
+#[derive(Debug)]
+struct Subscription {
+    /// The ID handed out on subscribing
+    id: SubscriptionId,
+    /// The board it watches
+    board: String,
+    /// The pattern to match
+    pattern: Regex,
+    /// What the pattern is matched against
+    scope: Scope,
+}
+
+impl Subscription {
+    /// Returns true if the subscription matches the post.
+    fn matches(&self, post: &Post) -> bool {
+        self.pattern.is_match(post.subject()) || self.pattern.is_match(&post.plain_text())
+    }
+}
+
+/// What a [`Notifier`] remembers about a board.
+#[derive(Debug, Default)]
+struct BoardState {
+    /// The reply count of every thread at the last poll
+    replies: HashMap<u32, u32>,
+    /// The subscriptions each post of a thread fired for
+    fired: HashMap<u32, HashSet<(SubscriptionId, u32)>>,
+}
+
+/// Polls boards and reports posts matching the subscriptions.
+#[derive(Debug)]
+pub struct Notifier {
+    /// the client
+    client: Dot4chClient,
+    /// The registered subscriptions
+    subscriptions: Vec<Subscription>,
+    /// The ID of the next subscription
+    next_id: usize,
+    /// The state of every watched board
+    boards: HashMap<String, BoardState>,
+    /// When the last poll happened
+    last_poll: Option<Instant>,
+}
+
+impl Notifier {
+    /// Creates a notifier without any subscriptions.
+    pub fn new(client: &Dot4chClient) -> Self {
+        Self {
+            client: client.clone(),
+            subscriptions: vec![],
+            next_id: 0,
+            boards: HashMap::new(),
+            last_poll: None,
+        }
+    }
+
+    /// Subscribes to posts on a board matching `pattern`.
+    pub fn subscribe(&mut self, board: &str, pattern: Regex, scope: Scope) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.subscriptions.push(Subscription {
+            id,
+            board: board.to_string(),
+            pattern,
+            scope,
+        });
+        id
+    }
+
+    /// Removes a subscription.
+    ///
+    /// Returns false if there was no such subscription.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let len = self.subscriptions.len();
+        self.subscriptions
+            .retain(|subscription| subscription.id != id);
+        let boards: HashSet<_> = self.subscriptions.iter().map(|s| s.board.clone()).collect();
+        self.boards.retain(|board, _| boards.contains(board));
+        len != self.subscriptions.len()
+    }
+
+    /// Checks every subscribed board once and returns the new matches.
+    ///
+    /// The first poll of a board matches the OPs and their most recent replies in the catalog.
+    /// Later polls match everything posted since, fetching a thread only when the catalog
+    /// does not show all of its new replies.
+    ///
+    /// Waits if called less than 10 seconds after the previous poll.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the catalog of a board cannot be fetched.
+    pub async fn poll(&mut self) -> crate::Result<Vec<Event>> {
+        let wait = self
+            .last_poll
+            .and_then(|last| POLL_COOLDOWN.checked_sub(last.elapsed()));
+        if let Some(wait) = wait {
+            debug!("Polling too quickly! Waiting for {wait:?}");
+            crate::sleep(&self.client, wait).await;
+        }
+        self.last_poll = Some(Instant::now());
+
+        let boards: HashSet<_> = self.subscriptions.iter().map(|s| s.board.clone()).collect();
+        let mut events = vec![];
+        for board in boards {
+            events.extend(self.poll_board(&board).await?);
+        }
+        Ok(events)
+    }
+
+    /// Checks one board for new matches.
+    async fn poll_board(&mut self, board: &str) -> crate::Result<Vec<Event>> {
+        let catalog = ops(&self.client, board).await?;
+        let mut state = self.boards.remove(board).unwrap_or_default();
+        let first = state.replies.is_empty();
+
+        let mut events = vec![];
+        let mut replies = HashMap::new();
+        for entry in catalog.into_iter().flat_map(|page| page.threads) {
+            let thread = entry.op.id();
+            let known = state.replies.get(&thread).copied().unwrap_or(0);
+            let new = entry.op.replies().saturating_sub(known);
+            replies.insert(thread, entry.op.replies());
+            let posts = self.new_posts(board, entry, first, new).await;
+            let fired = state.fired.entry(thread).or_default();
+
+            for post in posts {
+                for subscription in self.subscriptions.iter().filter(|s| s.board == board) {
+                    let is_op = post.id() == thread;
+                    if subscription.scope == Scope::Threads && !is_op {
+                        continue;
+                    }
+                    if subscription.matches(&post) && fired.insert((subscription.id, post.id())) {
+                        events.push(Event {
+                            subscription: subscription.id,
+                            board: board.to_string(),
+                            thread,
+                            post: post.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        // forget threads which left the board
+        state.fired.retain(|thread, _| replies.contains_key(thread));
+        state.replies = replies;
+        self.boards.insert(board.to_string(), state);
+        Ok(events)
+    }
+
+    /// Returns the posts of a catalog entry which need matching.
+    ///
+    /// The whole thread is fetched if it got more new replies than the catalog shows.
+    async fn new_posts(&self, board: &str, entry: CatalogOp, first: bool, new: u32) -> Vec<Post> {
+        let wants_posts = self
+            .subscriptions
+            .iter()
+            .any(|s| s.board == board && s.scope == Scope::Posts);
+        let shown = entry.last_replies.len();
+        if first || !wants_posts || new as usize <= shown {
+            return iter::once(entry.op).chain(entry.last_replies).collect();
+        }
+        match Thread::new(&self.client, board, entry.op.id()).await {
+            Ok(thread) => iter::once(thread.op())
+                .chain(&thread[..])
+                .cloned()
+                .collect(),
+            Err(err) => {
+                warn!("Could not fetch /{}/{}: {}", board, entry.op.id(), err);
+                iter::once(entry.op).chain(entry.last_replies).collect()
+            }
+        }
+    }
+}
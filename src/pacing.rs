@@ -0,0 +1,206 @@
+//! Widening the request interval while 4chan struggles.
+//!
+//! The client sends a request a second at most. With [`Adaptive`] pacing, the interval
+//! is doubled whenever a response is rate limited (429), the site is unavailable (503)
+//! or a response is abnormally slow, and halved again after a run of healthy responses,
+//! until it is back to a second.
+//!
+//! Every change of the interval is reported as a [`Change`] to the callback set with
+//! [`Client::on_pacing`](crate::Client::on_pacing).
+//!
+//! The interval is fixed by default.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, pacing::Adaptive};
+//! # use std::time::Duration;
+//! # async fn pacing() {
+//! let client = Client::new();
+//! let mut client = client.lock().await;
+//! client.set_pacing(Adaptive::new().max(Duration::from_secs(30)));
+//! client.on_pacing(|change| {
+//!     println!("{:?}: now {:?} between requests", change.cause, change.interval);
+//! });
+//! # }
+//! ```
+
+use crate::{
+    http::{Response, StatusCode},
+    logging::debug,
+    Error,
+};
+use std::{
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+    time::Duration,
+};
+
+/// The interval between requests when 4chan is healthy.
+pub(crate) const BASE: Duration = Duration::from_secs(1);
+
+/// Why the request interval changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cause {
+    /// A response was rate limited (429)
+    RateLimited,
+    /// A response said the site is unavailable (503)
+    Unavailable,
+    /// A response took this long, or timed out
+    Slow(Duration),
+    /// Enough responses in a row were healthy
+    Recovered,
+}
+
+/// A change of the request interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Change {
+    /// The interval before the change
+    pub previous: Duration,
+    /// The interval after the change
+    pub interval: Duration,
+    /// What caused the change
+    pub cause: Cause,
+}
+
+/// Widens the request interval on server trouble and narrows it back once it is over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Adaptive {
+    /// The response time above which a response counts as slow
+    slow: Duration,
+    /// The longest interval
+    max: Duration,
+    /// The healthy responses in a row before the interval is halved
+    recovery: u32,
+}
+
+impl Default for Adaptive {
+    fn default() -> Self {
+        Self {
+            slow: Duration::from_secs(5),
+            max: Duration::from_mins(1),
+            recovery: 10,
+        }
+    }
+}
+
+impl Adaptive {
+    /// Doubles the interval up to a minute, for 429s, 503s and responses slower than 5 seconds,
+    /// and halves it after 10 healthy responses in a row.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the response time above which a response counts as slow.
+    #[must_use]
+    pub fn slow(mut self, slow: Duration) -> Self {
+        self.slow = slow;
+        self
+    }
+
+    /// Sets the longest interval between two requests.
+    #[must_use]
+    pub fn max(mut self, max: Duration) -> Self {
+        self.max = max;
+        self
+    }
+
+    /// Sets how many healthy responses in a row halve the interval.
+    #[must_use]
+    pub fn recovery(mut self, recovery: u32) -> Self {
+        self.recovery = recovery.max(1);
+        self
+    }
+
+    /// Returns what is wrong with a response, `None` if it is healthy.
+    fn trouble(&self, outcome: &Result<Response, Error>, latency: Duration) -> Option<Cause> {
+        let status = match outcome {
+            Ok(response) => Some(response.status()),
+            Err(Error::Timeout(_)) => return Some(Cause::Slow(latency)),
+            Err(err) => err.status(),
+        };
+        match status {
+            Some(StatusCode::TOO_MANY_REQUESTS) => Some(Cause::RateLimited),
+            Some(StatusCode::SERVICE_UNAVAILABLE) => Some(Cause::Unavailable),
+            _ if latency > self.slow => Some(Cause::Slow(latency)),
+            _ => None,
+        }
+    }
+}
+
+/// A callback receiving every [`Change`] of the interval.
+pub(crate) type ChangeCallback = Arc<dyn Fn(&Change) + Send + Sync>;
+
+/// The request interval of a client.
+#[derive(Default)]
+pub(crate) struct Pacer {
+    /// How the interval adapts, `None` if it is fixed
+    policy: Option<Adaptive>,
+    /// The current interval, `None` while it is a second
+    interval: Option<Duration>,
+    /// The healthy responses since the last trouble or change
+    healthy: u32,
+    /// Called on every change of the interval
+    callback: Option<ChangeCallback>,
+}
+
+impl Debug for Pacer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pacer")
+            .field("policy", &self.policy)
+            .field("interval", &self.interval())
+            .field("healthy", &self.healthy)
+            .field("callback", &self.callback.is_some())
+            .finish()
+    }
+}
+
+impl Pacer {
+    /// Sets how the interval adapts and starts over at a second.
+    pub(crate) fn set_policy(&mut self, policy: Option<Adaptive>) {
+        self.policy = policy;
+        self.interval = None;
+        self.healthy = 0;
+    }
+
+    /// Sets the callback receiving every change of the interval.
+    pub(crate) fn set_callback(&mut self, callback: ChangeCallback) {
+        self.callback = Some(callback);
+    }
+
+    /// Returns the current interval.
+    pub(crate) fn interval(&self) -> Duration {
+        self.interval.unwrap_or(BASE)
+    }
+
+    /// Adapts the interval to the outcome of a request, reporting the change if any.
+    pub(crate) fn observe(&mut self, outcome: &Result<Response, Error>, latency: Duration) {
+        let Some(policy) = self.policy else {
+            return;
+        };
+        let previous = self.interval();
+        let (interval, cause) = if let Some(cause) = policy.trouble(outcome, latency) {
+            self.healthy = 0;
+            (previous.saturating_mul(2).min(policy.max).max(BASE), cause)
+        } else {
+            self.healthy = self.healthy.saturating_add(1);
+            if self.healthy < policy.recovery || previous <= BASE {
+                return;
+            }
+            self.healthy = 0;
+            ((previous / 2).max(BASE), Cause::Recovered)
+        };
+        if interval == previous {
+            return;
+        }
+        self.interval = Some(interval);
+        debug!("request interval changed from {previous:?} to {interval:?} ({cause:?})");
+        if let Some(callback) = &self.callback {
+            callback(&Change {
+                previous,
+                interval,
+                cause,
+            });
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Saving snapshots to disk and loading them back.
+//!
+//! A snapshot keeps the API URL and the `Last-Modified` validator of the data next to it,
+//! so a loaded [`Thread`](crate::thread::Thread) or [`Catalog`](crate::catalog::Catalog)
+//! can be updated with an `If-Modified-Since` request just like a freshly fetched one.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, Update, persist::Persist, thread::Thread};
+//! # async fn persist() -> anyhow::Result<()> {
+//! let client = Client::new();
+//! let thread = Thread::new(&client, "g", 76759434).await?;
+//!
+//! thread.save_json("thread.json")?;
+//!
+//! /* some time later, possibly in another process */
+//!
+//! let thread = Thread::load_json(&client, "thread.json")?;
+//! let thread = thread.update().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Dot4chClient, Result};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+/// The file format of a snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// Human readable JSON
+    Json,
+    /// Compact binary [bincode](<https://docs.rs/bincode>),
+    /// prefixed with a header holding the snapshot version.
+    ///
+    /// Requires the `bincode` feature.
+    #[cfg(feature = "bincode")]
+    Bincode,
+}
+
+/// Types which can be saved to and loaded from disk.
+pub trait Persist: Sized {
+    /// Writes a snapshot to `path` in the given format, replacing the file if it exists.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file cannot be written.
+    fn save<P: AsRef<Path>>(&self, path: P, format: Format) -> Result<()>;
+
+    /// Reads a snapshot written by [`Persist::save`] in the same format.
+    ///
+    /// The `client` is used for any further updates.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file cannot be read
+    /// or if it does not contain a snapshot of this type.
+    fn load<P: AsRef<Path>>(client: &Dot4chClient, path: P, format: Format) -> Result<Self>;
+
+    /// Writes a JSON snapshot to `path`, replacing the file if it exists.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file cannot be written.
+    fn save_json<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.save(path, Format::Json)
+    }
+
+    /// Reads a JSON snapshot written by [`Persist::save_json`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file cannot be read
+    /// or if it does not contain a snapshot of this type.
+    fn load_json<P: AsRef<Path>>(client: &Dot4chClient, path: P) -> Result<Self> {
+        Self::load(client, path, Format::Json)
+    }
+
+    /// Writes a binary snapshot to `path`, replacing the file if it exists.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file cannot be written.
+    #[cfg(feature = "bincode")]
+    fn save_bincode<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.save(path, Format::Bincode)
+    }
+
+    /// Reads a binary snapshot written by [`Persist::save_bincode`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file cannot be read,
+    /// if it was written by an incompatible version of this crate
+    /// or if it does not contain a snapshot of this type.
+    #[cfg(feature = "bincode")]
+    fn load_bincode<P: AsRef<Path>>(client: &Dot4chClient, path: P) -> Result<Self> {
+        Self::load(client, path, Format::Bincode)
+    }
+}
+
+/// Information stored alongside the data of a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+    /// The API URL the data was fetched from
+    pub url: String,
+    /// The board the data belongs to
+    pub board: String,
+    /// The `Last-Modified` header of the last response.
+    ///
+    /// This is sent as `If-Modified-Since` on the next update.
+    pub last_modified: Option<String>,
+    /// When the snapshot was taken
+    pub saved_at: DateTime<Utc>,
+}
+
+impl Metadata {
+    /// Creates the metadata of a snapshot taken now.
+    pub(crate) fn new(url: String, board: &str, last_modified: Option<&str>) -> Self {
+        Self {
+            url,
+            board: board.to_string(),
+            last_modified: last_modified.map(str::to_string),
+            saved_at: Utc::now(),
+        }
+    }
+}
+
+/// The data of a snapshot with its [`Metadata`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Snapshot<T> {
+    /// The metadata
+    pub(crate) metadata: Metadata,
+    /// The data
+    pub(crate) data: T,
+}
+
+/// Marks the start of a binary snapshot.
+#[cfg(feature = "bincode")]
+const MAGIC: &[u8; 4] = b"D4CH";
+
+/// The version of the binary snapshot layout.
+///
+/// Bump this whenever a persisted type changes its fields.
+#[cfg(feature = "bincode")]
+const VERSION: u32 = 1;
+
+/// Writes `value` to `path` in the given format.
+pub(crate) fn write<T: Serialize, P: AsRef<Path>>(
+    value: &T,
+    path: P,
+    format: Format,
+) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        Format::Json => serde_json::to_writer(&mut writer, value)?,
+        #[cfg(feature = "bincode")]
+        Format::Bincode => {
+            writer.write_all(MAGIC)?;
+            writer.write_all(&VERSION.to_le_bytes())?;
+            bincode::serialize_into(&mut writer, value)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a value from `path` in the given format.
+pub(crate) fn read<T: DeserializeOwned, P: AsRef<Path>>(path: P, format: Format) -> Result<T> {
+    let reader = BufReader::new(File::open(path)?);
+    match format {
+        Format::Json => Ok(serde_json::from_reader(reader)?),
+        #[cfg(feature = "bincode")]
+        Format::Bincode => {
+            use std::io::Read;
+
+            let mut reader = reader;
+            let mut header = [0; 8];
+            reader.read_exact(&mut header)?;
+            if &header[..4] != MAGIC {
+                return Err(anyhow::anyhow!("Not a dot4ch binary snapshot"));
+            }
+            let mut version = [0; 4];
+            version.copy_from_slice(&header[4..]);
+            let version = u32::from_le_bytes(version);
+            if version != VERSION {
+                return Err(anyhow::anyhow!(
+                    "Binary snapshot version {} is not supported, expected {}",
+                    version,
+                    VERSION
+                ));
+            }
+            Ok(bincode::deserialize_from(reader)?)
+        }
+    }
+}
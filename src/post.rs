@@ -4,6 +4,8 @@
 //!
 //! Posts are usually used in [`crate::thread::Thread`]s which is why they do not have a `new()` but they do have an [`Default`] implementation.
 //!
+//! Posts for tests are made with [`Post::builder`], without fetching or writing JSON.
+//!
 //! ## 4chan API:
 //! /<board>/<thread>/<op ID>.json files are a representation of a single OP and all the replies, which form a thread.
 //!
@@ -17,13 +19,248 @@
 //! assert_eq!(z.id(), 0);
 //! ```
 
-use crate::default;
-use serde::{Deserialize, Serialize};
-use std::fmt::{Display, Formatter};
+use crate::{default, logging::warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    convert::{Infallible, TryFrom},
+    fmt::{Display, Formatter},
+    str::FromStr,
+    sync::Arc,
+};
 
-/// The Post represents a derserialized post from a thread.
+/// Declares an enum of the values of a string field, mapping unknown values to `Other`.
+///
+/// The API adds values without notice, so parsing one of these never fails.
+macro_rules! open_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident => $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(from = "String", into = "String")]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            /// A value this version of the crate does not know
+            Other(String),
+        }
+
+        impl $name {
+            /// Returns the value as the API sends it.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $value,)*
+                    Self::Other(value) => value,
+                }
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                match value {
+                    $($value => Self::$variant,)*
+                    _ => Self::Other(value.to_string()),
+                }
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                match value.as_str() {
+                    $($value => Self::$variant,)*
+                    _ => Self::Other(value),
+                }
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                match value {
+                    $name::Other(value) => value,
+                    known => known.as_str().to_string(),
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Infallible;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                Ok(Self::from(value))
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    };
+}
+
+open_enum! {
+    /// The capcode of a post made by staff.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dot4ch::post::Capcode;
+    ///
+    /// let known = [
+    ///     ("mod", Capcode::Mod),
+    ///     ("admin", Capcode::Admin),
+    ///     ("admin_highlight", Capcode::AdminHighlight),
+    ///     ("manager", Capcode::Manager),
+    ///     ("developer", Capcode::Developer),
+    ///     ("founder", Capcode::Founder),
+    ///     ("verified", Capcode::Verified),
+    /// ];
+    /// for (value, capcode) in known {
+    ///     assert_eq!(Capcode::from(value), capcode);
+    ///     assert_eq!(capcode.as_str(), value);
+    ///     let json = format!("\"{}\"", value);
+    ///     assert_eq!(serde_json::from_str::<Capcode>(&json).unwrap(), capcode);
+    ///     assert_eq!(serde_json::to_string(&capcode).unwrap(), json);
+    /// }
+    ///
+    /// // values added to the API later still parse
+    /// let janitor: Capcode = serde_json::from_str("\"janitor\"").unwrap();
+    /// assert_eq!(janitor, Capcode::Other("janitor".to_string()));
+    /// assert_eq!(serde_json::to_string(&janitor).unwrap(), "\"janitor\"");
+    /// ```
+    pub enum Capcode {
+        /// A moderator
+        Mod => "mod",
+        /// An administrator
+        Admin => "admin",
+        /// An administrator with a highlighted post
+        AdminHighlight => "admin_highlight",
+        /// A manager
+        Manager => "manager",
+        /// A developer
+        Developer => "developer",
+        /// The founder of the site
+        Founder => "founder",
+        /// A verified user
+        Verified => "verified",
+    }
+}
+
+open_enum! {
+    /// The category of a `.swf` upload on /f/.
+    ///
+    /// The API also sends `Other`, which is kept as `Tag::Other("Other")`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dot4ch::post::Tag;
+    ///
+    /// let known = [
+    ///     ("Hentai", Tag::Hentai),
+    ///     ("Japanese", Tag::Japanese),
+    ///     ("Anime", Tag::Anime),
+    ///     ("Game", Tag::Game),
+    ///     ("Loop", Tag::Loop),
+    ///     ("Porn", Tag::Porn),
+    ///     ("Other", Tag::Other("Other".to_string())),
+    /// ];
+    /// for (value, tag) in known {
+    ///     assert_eq!(value.parse::<Tag>().unwrap(), tag);
+    ///     assert_eq!(tag.to_string(), value);
+    ///     let json = format!("\"{}\"", value);
+    ///     assert_eq!(serde_json::from_str::<Tag>(&json).unwrap(), tag);
+    ///     assert_eq!(serde_json::to_string(&tag).unwrap(), json);
+    /// }
+    ///
+    /// let new: Tag = serde_json::from_str("\"Flash\"").unwrap();
+    /// assert_eq!(new, Tag::Other("Flash".to_string()));
+    /// ```
+    pub enum Tag {
+        /// Hentai
+        Hentai => "Hentai",
+        /// Japanese
+        Japanese => "Japanese",
+        /// Anime
+        Anime => "Anime",
+        /// Game
+        Game => "Game",
+        /// Loop
+        Loop => "Loop",
+        /// Porn
+        Porn => "Porn",
+    }
+}
+
+open_enum! {
+    /// The type of the file of a post, from its extension.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dot4ch::post::FileKind;
+    ///
+    /// let known = [
+    ///     (".jpg", FileKind::Jpg),
+    ///     (".png", FileKind::Png),
+    ///     (".gif", FileKind::Gif),
+    ///     (".webm", FileKind::Webm),
+    ///     (".mp4", FileKind::Mp4),
+    ///     (".pdf", FileKind::Pdf),
+    ///     (".swf", FileKind::Swf),
+    /// ];
+    /// for (value, kind) in known {
+    ///     assert_eq!(FileKind::from(value), kind);
+    ///     assert_eq!(kind.as_str(), value);
+    ///     let json = format!("\"{}\"", value);
+    ///     assert_eq!(serde_json::from_str::<FileKind>(&json).unwrap(), kind);
+    /// }
+    ///
+    /// assert!(FileKind::Webm.is_animated());
+    /// assert!(!FileKind::Png.is_animated());
+    /// assert_eq!(FileKind::from(".avif"), FileKind::Other(".avif".to_string()));
+    /// ```
+    pub enum FileKind {
+        /// A JPEG image
+        Jpg => ".jpg",
+        /// A PNG image
+        Png => ".png",
+        /// A GIF image, which may be animated
+        Gif => ".gif",
+        /// A `WebM` video
+        Webm => ".webm",
+        /// An MP4 video
+        Mp4 => ".mp4",
+        /// A PDF document, only on /po/
+        Pdf => ".pdf",
+        /// A Flash file, only on /f/
+        Swf => ".swf",
+    }
+}
+
+impl FileKind {
+    /// Returns true for still images and GIFs.
+    pub fn is_image(&self) -> bool {
+        matches!(self, Self::Jpg | Self::Png | Self::Gif)
+    }
+
+    /// Returns true for videos.
+    pub fn is_video(&self) -> bool {
+        matches!(self, Self::Webm | Self::Mp4)
+    }
+
+    /// Returns true for files which may move, which are GIFs, videos and Flash.
+    pub fn is_animated(&self) -> bool {
+        matches!(self, Self::Gif | Self::Webm | Self::Mp4 | Self::Swf)
+    }
+}
+
+/// A post as the API sends it, with every field flat.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct Post {
+struct RawPost {
     /// The numeric post ID
     no: u32,
 
@@ -179,12 +416,268 @@ pub struct Post {
     archived_on: i64,
 }
 
+/// The Post represents a derserialized post from a thread.
+///
+/// The file of a post and the fields only OPs have are kept behind an [`Arc`],
+/// so a text reply takes a fraction of the memory and cloning a post does not copy them.
+/// It is still (de)serialized as the flat object of the API.
+///
+/// ```
+/// use dot4ch::post::Post;
+///
+/// let json = r#"{"no":2,"resto":1,"now":"","time":0,"filename":"a","ext":".png","tim":3}"#;
+/// let post: Post = serde_json::from_str(json).unwrap();
+/// assert_eq!(post.image_url("g").as_deref(), Some("https://i.4cdn.org/g/3.png"));
+/// assert!(!post.closed());
+///
+/// let value = serde_json::to_value(&post).unwrap();
+/// assert_eq!(value["ext"], ".png");
+/// assert_eq!(value["closed"], 0);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(from = "RawPost", into = "RawPost")]
+pub struct Post {
+    /// The numeric post ID
+    no: u32,
+    /// For replies: this is the ID of the thread being replied to.
+    ///
+    /// For OP: this value is zero
+    resto: u32,
+    /// MM/DD/YY(Day)HH:MM (:SS on some boards), EST/EDT timezone
+    now: Box<str>,
+    /// UNIX timestamp the post was created
+    time: i64,
+    /// Name user posted with. Defaults to `Anonymous`
+    name: Box<str>,
+    /// The user's tripcode
+    trip: Box<str>,
+    /// The poster's ID
+    id: Box<str>,
+    /// The capcode identifier for a post
+    capcode: Box<str>,
+    /// Poster's ISO 3166-1 alpha-2 country code
+    country: Box<str>,
+    /// Poster's country name
+    country_name: Box<str>,
+    /// Poster's board flag code
+    board_flag: Box<str>,
+    /// Poster's board flag name
+    flag_name: Box<str>,
+    /// OP Subject text
+    sub: Box<str>,
+    /// Comment (HTML escaped)
+    com: Box<str>,
+    /// Year 4chan pass bought
+    since4pass: u16,
+    /// The file of the post, if it has one
+    file: Option<Arc<Attachment>>,
+    /// The fields only OPs have
+    op: Option<Arc<OpExtras>>,
+}
+
+/// The file of a [`Post`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Attachment {
+    /// Unix timestamp + microtime that an image was uploaded
+    tim: u64,
+    /// Filename as it appeared on the poster's device
+    filename: String,
+    /// Filetype
+    ext: String,
+    /// Size of uploaded file in bytes
+    fsize: u32,
+    /// 24 character, packed base64 MD5 hash of file
+    md5: String,
+    /// Image Width Dimension
+    w: u32,
+    /// Image Height Dimension
+    h: u32,
+    /// Thumbnail image width dimension
+    tn_w: u32,
+    /// Thumbnail image height dimension
+    tn_h: u32,
+    /// If the file was deleted from the post
+    filedeleted: u8,
+    /// If the image was spoiler'd or not
+    spoiler: u8,
+    /// The custom spoiler ID for a spoilered image
+    custom_spoiler: u8,
+    /// Mobile optimized image exists for post
+    m_img: u8,
+    /// The category of .swf upload
+    tag: String,
+}
+
+/// The fields of a [`Post`] which only OPs have.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct OpExtras {
+    /// If the thread is being pinned to the top of the page
+    sticky: u8,
+    /// If the thread is closed to replies
+    closed: u8,
+    /// Total number of replies to a thread
+    replies: u32,
+    /// Total number of image replies to a thread
+    images: u32,
+    /// If a thread has reached bumplimit, it will no longer bump
+    bumplimit: u8,
+    /// If an image has reached image limit, no more image replies can be made
+    imagelimit: u8,
+    /// SEO URL slug for thread
+    semantic_url: String,
+    /// Number of unique posters in a thread
+    unique_ips: u16,
+    /// Thread has reached the board's archive
+    archived: u8,
+    /// UNIX timestamp the post was archived
+    archived_on: i64,
+}
+
+impl From<RawPost> for Post {
+    fn from(raw: RawPost) -> Self {
+        let file = Attachment {
+            tim: raw.tim,
+            filename: raw.filename,
+            ext: raw.ext,
+            fsize: raw.fsize,
+            md5: raw.md5,
+            w: raw.w,
+            h: raw.h,
+            tn_w: raw.tn_w,
+            tn_h: raw.tn_h,
+            filedeleted: raw.filedeleted,
+            spoiler: raw.spoiler,
+            custom_spoiler: raw.custom_spoiler,
+            m_img: raw.m_img,
+            tag: raw.tag,
+        };
+        let op = OpExtras {
+            sticky: raw.sticky,
+            closed: raw.closed,
+            replies: raw.replies,
+            images: raw.images,
+            bumplimit: raw.bumplimit,
+            imagelimit: raw.imagelimit,
+            semantic_url: raw.semantic_url,
+            unique_ips: raw.unique_ips,
+            archived: raw.archived,
+            archived_on: raw.archived_on,
+        };
+        Self {
+            no: raw.no,
+            resto: raw.resto,
+            now: raw.now.into(),
+            time: raw.time,
+            name: raw.name.into(),
+            trip: raw.trip.into(),
+            id: raw.id.into(),
+            capcode: raw.capcode.into(),
+            country: raw.country.into(),
+            country_name: raw.country_name.into(),
+            board_flag: raw.board_flag.into(),
+            flag_name: raw.flag_name.into(),
+            sub: raw.sub.into(),
+            com: raw.com.into(),
+            since4pass: raw.since4pass,
+            file: (file != Attachment::default()).then(|| Arc::new(file)),
+            op: (raw.resto == 0 || op != OpExtras::default()).then(|| Arc::new(op)),
+        }
+    }
+}
+
+impl From<Post> for RawPost {
+    fn from(post: Post) -> Self {
+        let file = post.file.as_deref().cloned().unwrap_or_default();
+        let op = post.op.as_deref().cloned().unwrap_or_default();
+        Self {
+            no: post.no,
+            resto: post.resto,
+            sticky: op.sticky,
+            closed: op.closed,
+            now: post.now.to_string(),
+            time: post.time,
+            name: post.name.to_string(),
+            trip: post.trip.to_string(),
+            id: post.id.to_string(),
+            capcode: post.capcode.to_string(),
+            country: post.country.to_string(),
+            country_name: post.country_name.to_string(),
+            board_flag: post.board_flag.to_string(),
+            flag_name: post.flag_name.to_string(),
+            sub: post.sub.to_string(),
+            com: post.com.to_string(),
+            tim: file.tim,
+            filename: file.filename,
+            ext: file.ext,
+            fsize: file.fsize,
+            md5: file.md5,
+            w: file.w,
+            h: file.h,
+            tn_w: file.tn_w,
+            tn_h: file.tn_h,
+            filedeleted: file.filedeleted,
+            spoiler: file.spoiler,
+            custom_spoiler: file.custom_spoiler,
+            replies: op.replies,
+            images: op.images,
+            bumplimit: op.bumplimit,
+            imagelimit: op.imagelimit,
+            tag: file.tag,
+            semantic_url: op.semantic_url,
+            since4pass: post.since4pass,
+            unique_ips: op.unique_ips,
+            m_img: file.m_img,
+            archived: op.archived,
+            archived_on: op.archived_on,
+        }
+    }
+}
+
 impl Post {
+    /// Returns a builder of a post numbered `no`, such as for a test fixture.
+    ///
+    /// The post is an OP until [`PostBuilder::reply_to`] is called.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dot4ch::post::{Capcode, Post};
+    ///
+    /// let post = Post::builder(12346)
+    ///     .reply_to(12345)
+    ///     .content("&gt;&gt;12345 based")
+    ///     .capcode(Capcode::Mod)
+    ///     .build();
+    /// assert_eq!(post.id(), 12346);
+    /// assert_eq!(post.name(), "Anonymous");
+    /// assert_eq!(post.capcode_kind(), Some(Capcode::Mod));
+    /// ```
+    pub fn builder(no: u32) -> PostBuilder {
+        PostBuilder(RawPost {
+            no,
+            name: "Anonymous".to_string(),
+            ..RawPost::default()
+        })
+    }
+
     /// Returns the post number of a Post
     pub fn id(&self) -> u32 {
         self.no
     }
 
+    /// Returns the name the user posted with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the poster's ID on boards with IDs enabled. `None` otherwise.
+    pub fn poster_id(&self) -> Option<&str> {
+        if self.id.is_empty() {
+            return None;
+        }
+        Some(&self.id)
+    }
+
     /// Returns the subject from the text.
     ///
     /// Returns an empty str if there isnt any.
@@ -207,49 +700,109 @@ impl Post {
 
     /// Returns the filename if there is one or an empty string otherwise.
     pub fn filename(&self) -> &str {
-        &self.filename
+        self.file.as_ref().map_or("", |file| &file.filename)
     }
 
     /// Returns the filename's extension if there is a file.
     ///
     /// Returns an empty &str otherwise.
     pub fn ext(&self) -> &str {
-        &self.ext
+        self.file.as_ref().map_or("", |file| &file.ext)
+    }
+
+    /// Returns the SEO URL slug of a thread, only present on OPs.
+    pub fn semantic_url(&self) -> Option<&str> {
+        self.op
+            .as_ref()
+            .map(|op| op.semantic_url.as_str())
+            .filter(|slug| !slug.is_empty())
+    }
+
+    /// Returns an estimate of the memory used by the post, in bytes.
+    ///
+    /// Counts the post itself and the text of its fields, but not allocator overhead.
+    pub fn approx_size(&self) -> usize {
+        let text = [
+            &self.now,
+            &self.name,
+            &self.trip,
+            &self.id,
+            &self.capcode,
+            &self.country,
+            &self.country_name,
+            &self.board_flag,
+            &self.flag_name,
+            &self.sub,
+            &self.com,
+        ]
+        .iter()
+        .map(|field| field.len())
+        .sum::<usize>();
+        let file = self.file.as_ref().map_or(0, |file| {
+            size_of::<Attachment>()
+                + file.filename.len()
+                + file.ext.len()
+                + file.md5.len()
+                + file.tag.len()
+        });
+        let op = self
+            .op
+            .as_ref()
+            .map_or(0, |op| size_of::<OpExtras>() + op.semantic_url.len());
+        size_of::<Self>() + text + file + op
+    }
+
+    /// Returns the [`FileKind`] of the file if there is one. `None` otherwise.
+    pub fn file_kind(&self) -> Option<FileKind> {
+        if self.ext().is_empty() {
+            return None;
+        }
+        Some(FileKind::from(self.ext()))
     }
 
     /// Returns the number of replies to the Post
     pub fn replies(&self) -> u32 {
-        self.replies
+        self.op.as_ref().map_or(0, |op| op.replies)
+    }
+
+    /// Returns the number of image replies to the Post
+    pub fn images(&self) -> u32 {
+        self.op.as_ref().map_or(0, |op| op.images)
+    }
+
+    /// Returns the number of unique posters in a thread, only present on OPs.
+    pub fn unique_ips(&self) -> Option<u16> {
+        self.op
+            .as_ref()
+            .map(|op| op.unique_ips)
+            .filter(|ips| *ips != 0)
     }
 
     /// Returns true if the post is archived. False othwrwise.
     pub fn archived(&self) -> bool {
-        if self.archived == 1 {
-            return true;
-        }
-        false
+        self.op.as_ref().is_some_and(|op| op.archived == 1)
     }
 
     /// Returns the dimensions of an image in a tuple: (WIDTH, HEIGHT)
     pub fn image_dimensions(&self) -> (u32, u32) {
-        (self.w, self.h)
+        self.file.as_ref().map_or((0, 0), |file| (file.w, file.h))
     }
 
     /// Returns the UNIX timestamp of when the post was archived
     pub fn archived_on(&self) -> i64 {
-        self.archived_on
+        self.op.as_ref().map_or(0, |op| op.archived_on)
     }
 
     /// Returns the 4chan image url from the supplied post.
     pub fn image_url(&self, board: &str) -> Option<String> {
-        if self.filename.is_empty() {
-            None
-        } else {
-            Some(format!(
-                "https://i.4cdn.org/{}/{}{}",
-                board, &self.tim, &self.ext
-            ))
-        }
+        let file = self
+            .file
+            .as_ref()
+            .filter(|file| !file.filename.is_empty())?;
+        Some(format!(
+            "https://i.4cdn.org/{}/{}{}",
+            board, &file.tim, &file.ext
+        ))
     }
 
     /// Returns a UNIX Timestamp of when the post was created
@@ -259,18 +812,12 @@ impl Post {
 
     /// Returns a true if the thread is pinned
     pub fn sticky(&self) -> bool {
-        if self.sticky != 0 {
-            return true;
-        }
-        false
+        self.op.as_ref().is_some_and(|op| op.sticky != 0)
     }
 
     /// Returns true if the thread is closed to replies
     pub fn closed(&self) -> bool {
-        if self.closed != 0 {
-            return true;
-        }
-        false
+        self.op.as_ref().is_some_and(|op| op.closed != 0)
     }
 
     /// Returns the tripcode if the poster has one. Returns `None` otherwise.
@@ -289,6 +836,17 @@ impl Post {
         Some(&self.capcode)
     }
 
+    /// Returns the [`Capcode`] of a post made by staff. `None` otherwise.
+    pub fn capcode_kind(&self) -> Option<Capcode> {
+        self.capcode().map(Capcode::from)
+    }
+
+    /// Returns the [`Tag`] of a `.swf` upload on /f/. `None` otherwise.
+    pub fn tag(&self) -> Option<Tag> {
+        let file = self.file.as_ref().filter(|file| !file.tag.is_empty())?;
+        Some(Tag::from(file.tag.as_str()))
+    }
+
     /// Returns the poster's country name if there is one avaliable. `None` otherwise.
     pub fn country(&self) -> Option<&str> {
         if self.country_name.is_empty() {
@@ -297,44 +855,52 @@ impl Post {
         Some(&self.country_name)
     }
 
-    /// Returns the post's file's MD5 hash if there is one.
-    pub fn md5hash(&self) -> Option<&str> {
-        if self.md5.is_empty() {
+    /// Returns the poster's ISO 3166-1 alpha-2 country code if there is one. `None` otherwise.
+    pub fn country_code(&self) -> Option<&str> {
+        if self.country.is_empty() {
             return None;
         }
-        Some(&self.md5)
+        Some(&self.country)
+    }
+
+    /// Returns the code and name of the poster's board flag
+    /// on boards with custom flags. `None` otherwise.
+    pub fn board_flag(&self) -> Option<(&str, &str)> {
+        if self.board_flag.is_empty() {
+            return None;
+        }
+        Some((&self.board_flag, &self.flag_name))
+    }
+
+    /// Returns the post's file's MD5 hash if there is one.
+    pub fn md5hash(&self) -> Option<&str> {
+        self.file
+            .as_ref()
+            .map(|file| file.md5.as_str())
+            .filter(|md5| !md5.is_empty())
     }
 
     /// Returns true if the file in the post was deleted.
     pub fn file_deleted(&self) -> bool {
-        if self.filedeleted != 0 {
-            return true;
-        }
-        false
+        self.file.as_ref().is_some_and(|file| file.filedeleted != 0)
     }
 
     /// Returns a filesize of a post if it has one.
     pub fn filesize(&self) -> Option<u32> {
-        if self.fsize != 0 {
-            return Some(self.fsize);
-        }
-        None
+        self.file
+            .as_ref()
+            .map(|file| file.fsize)
+            .filter(|fsize| *fsize != 0)
     }
 
     /// Returns true if the thread has reached image limit, false otherwise
     pub fn image_limit(&self) -> bool {
-        if self.imagelimit != 0 {
-            return true;
-        }
-        false
+        self.op.as_ref().is_some_and(|op| op.imagelimit != 0)
     }
 
     /// Returns true if the thread has reached bump limit, false otherwise
     pub fn bump_limit(&self) -> bool {
-        if self.bumplimit != 0 {
-            return true;
-        }
-        false
+        self.op.as_ref().is_some_and(|op| op.bumplimit != 0)
     }
 }
 
@@ -348,3 +914,163 @@ impl Display for Post {
         write!(f, "{}", fmt)
     }
 }
+
+/// Builds a [`Post`] field by field, see [`Post::builder`].
+///
+/// Fields which are not set are empty, as in a post from the API without them.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct PostBuilder(RawPost);
+
+impl PostBuilder {
+    /// Makes the post a reply in the thread `op`.
+    pub fn reply_to(mut self, op: u32) -> Self {
+        self.0.resto = op;
+        self
+    }
+
+    /// Sets the UNIX timestamp the post was created.
+    pub fn post_time(mut self, time: i64) -> Self {
+        self.0.time = time;
+        self
+    }
+
+    /// Sets the time as 4chan formats it, such as `01/02/21(Sat)13:45:00`.
+    pub fn time_now(mut self, now: &str) -> Self {
+        self.0.now = now.to_string();
+        self
+    }
+
+    /// Sets the name the user posted with.
+    pub fn name(mut self, name: &str) -> Self {
+        self.0.name = name.to_string();
+        self
+    }
+
+    /// Sets the tripcode of the poster.
+    pub fn tripcode(mut self, trip: &str) -> Self {
+        self.0.trip = trip.to_string();
+        self
+    }
+
+    /// Sets the poster's ID.
+    pub fn poster_id(mut self, id: &str) -> Self {
+        self.0.id = id.to_string();
+        self
+    }
+
+    /// Sets the capcode of a post made by staff.
+    pub fn capcode(mut self, capcode: Capcode) -> Self {
+        self.0.capcode = capcode.into();
+        self
+    }
+
+    /// Sets the poster's ISO 3166-1 alpha-2 country code and country name.
+    pub fn country(mut self, code: &str, name: &str) -> Self {
+        self.0.country = code.to_string();
+        self.0.country_name = name.to_string();
+        self
+    }
+
+    /// Sets the subject.
+    pub fn subject(mut self, subject: &str) -> Self {
+        self.0.sub = subject.to_string();
+        self
+    }
+
+    /// Sets the comment, HTML escaped as the API sends it.
+    pub fn content(mut self, com: &str) -> Self {
+        self.0.com = com.to_string();
+        self
+    }
+
+    /// Attaches a file uploaded at `tim` with its filename and extension, such as `.png`.
+    pub fn file(mut self, tim: u64, filename: &str, ext: &str) -> Self {
+        self.0.tim = tim;
+        self.0.filename = filename.to_string();
+        self.0.ext = ext.to_string();
+        self
+    }
+
+    /// Sets the size in bytes and the dimensions of the file.
+    pub fn file_size(mut self, fsize: u32, width: u32, height: u32) -> Self {
+        self.0.fsize = fsize;
+        self.0.w = width;
+        self.0.h = height;
+        self
+    }
+
+    /// Sets the base64 MD5 hash of the file.
+    pub fn md5hash(mut self, md5: &str) -> Self {
+        self.0.md5 = md5.to_string();
+        self
+    }
+
+    /// Sets the number of replies and image replies of a thread.
+    pub fn replies(mut self, replies: u32, images: u32) -> Self {
+        self.0.replies = replies;
+        self.0.images = images;
+        self
+    }
+
+    /// Pins the thread.
+    pub fn sticky(mut self, sticky: bool) -> Self {
+        self.0.sticky = sticky.into();
+        self
+    }
+
+    /// Closes the thread to replies.
+    pub fn closed(mut self, closed: bool) -> Self {
+        self.0.closed = closed.into();
+        self
+    }
+
+    /// Marks the thread as archived at a UNIX timestamp.
+    pub fn archived_on(mut self, archived_on: i64) -> Self {
+        self.0.archived = 1;
+        self.0.archived_on = archived_on;
+        self
+    }
+
+    /// Builds the post.
+    pub fn build(self) -> Post {
+        Post::from(self.0)
+    }
+}
+
+/// An entry of a response which could not be parsed and was skipped.
+///
+/// Entries are only skipped in lenient mode, see [`Client::set_lenient`](crate::Client::set_lenient).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SkippedPost {
+    /// The post or thread number, if it could be read
+    pub no: Option<u32>,
+    /// The position of the entry in the response
+    pub index: usize,
+    /// Why the entry could not be parsed
+    pub reason: String,
+}
+
+/// Parses every entry of a JSON array on its own, skipping the malformed ones.
+pub(crate) fn parse_each<T: DeserializeOwned>(values: Vec<Value>) -> (Vec<T>, Vec<SkippedPost>) {
+    let mut parsed = vec![];
+    let mut skipped = vec![];
+    for (index, value) in values.into_iter().enumerate() {
+        let no = value
+            .get("no")
+            .and_then(Value::as_u64)
+            .and_then(|no| u32::try_from(no).ok());
+        match serde_json::from_value(value) {
+            Ok(entry) => parsed.push(entry),
+            Err(err) => {
+                warn!("Skipping malformed entry {index} ({no:?}): {err}");
+                skipped.push(SkippedPost {
+                    no,
+                    index,
+                    reason: err.to_string(),
+                });
+            }
+        }
+    }
+    (parsed, skipped)
+}
@@ -0,0 +1,127 @@
+//! Retrying failed requests.
+//!
+//! The [`RetryPolicy`] of the client decides whether a failed GET request is sent again
+//! and how long to wait before. Only transient failures are worth retrying,
+//! see [`Error::is_retryable`].
+//!
+//! Requests are not retried by default.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, retry::Backoff};
+//! # use std::time::Duration;
+//! # async fn retry() {
+//! let client = Client::new();
+//! client
+//!     .lock()
+//!     .await
+//!     .set_retry_policy(Backoff::new(3).base(Duration::from_secs(2)));
+//! # }
+//! ```
+
+use crate::Error;
+use std::{fmt::Debug, time::Duration};
+
+/// Decides whether a failed request is retried.
+pub trait RetryPolicy: Debug + Send + Sync {
+    /// Returns how long to wait before retrying, or `None` to give up.
+    ///
+    /// `attempt` is the number of the failed attempt, starting at 1.
+    fn retry(&self, err: &Error, attempt: u32) -> Option<Duration>;
+}
+
+/// Never retries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct NoRetry;
+
+impl RetryPolicy for NoRetry {
+    fn retry(&self, _err: &Error, _attempt: u32) -> Option<Duration> {
+        None
+    }
+}
+
+/// Retries transient failures with an exponential backoff.
+///
+/// A `Retry-After` longer than the backoff is waited out in full.
+///
+/// # Example
+///
+/// ```
+/// use dot4ch::{http::{HeaderMap, StatusCode}, retry::{Backoff, RetryPolicy}, Error};
+/// use std::time::Duration;
+///
+/// let backoff = Backoff::new(2).base(Duration::from_secs(1));
+/// let busy = Error::UnexpectedStatus {
+///     status: StatusCode::SERVICE_UNAVAILABLE,
+///     headers: HeaderMap::new(),
+///     body: None,
+/// };
+///
+/// assert_eq!(backoff.retry(&busy, 1), Some(Duration::from_secs(1)));
+/// assert_eq!(backoff.retry(&busy, 2), Some(Duration::from_secs(2)));
+/// assert_eq!(backoff.retry(&busy, 3), None);
+///
+/// let gone = Error::NotFound { board: "g".to_string(), id: Some(1) };
+/// assert_eq!(backoff.retry(&gone, 1), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Backoff {
+    /// The number of retries before giving up
+    retries: u32,
+    /// The wait before the first retry, doubled on every retry
+    base: Duration,
+    /// The longest wait
+    max: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            base: Duration::from_secs(1),
+            max: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Backoff {
+    /// Retries up to `retries` times, waiting 1 second before the first retry.
+    pub fn new(retries: u32) -> Self {
+        Self {
+            retries,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the wait before the first retry.
+    #[must_use]
+    pub fn base(mut self, base: Duration) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Sets the longest wait between two attempts.
+    #[must_use]
+    pub fn max(mut self, max: Duration) -> Self {
+        self.max = max;
+        self
+    }
+}
+
+impl RetryPolicy for Backoff {
+    fn retry(&self, err: &Error, attempt: u32) -> Option<Duration> {
+        if attempt > self.retries || !err.is_retryable() {
+            return None;
+        }
+        let factor = 1_u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        let wait = self.base.checked_mul(factor).unwrap_or(self.max);
+        // never retry sooner than 4chan asked for
+        Some(
+            wait.min(self.max)
+                .max(err.retry_after().unwrap_or_default()),
+        )
+    }
+}
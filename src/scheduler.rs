@@ -0,0 +1,531 @@
+//! Updating a thread or catalog in the background.
+//!
+//! [`spawn`] runs the update loop every watcher ends up writing:
+//! the target is updated on a tokio timer, cooldowns and rate limits are waited out
+//! and the latest state is published to the returned [`Handle`].
+//! The loop sleeps on the timer of the client, with its jitter, see [`Client::set_jitter`](crate::Client::set_jitter).
+//!
+//! With [`spawn_adaptive`], the interval follows the activity of the target:
+//! it grows for quiet threads and shrinks again once replies come in.
+//!
+//! Updates are not `Send`, so the task is spawned with [`tokio::task::spawn_local`]
+//! and must run inside a [`tokio::task::LocalSet`].
+//!
+//! [`throttle`] runs futures of your own, such as requests to other endpoints,
+//! under the same request cooldown as the crate.
+//!
+//! A [`Refreshable`] bundles a target with its client and interval, and keeps it fresh
+//! on demand, in a spawned loop or as a stream.
+//!
+//! [`Handle::shutdown`] stops the loop without cutting off the update in progress.
+//! A [`Shutdown`] signal does the same for the long running loops elsewhere in the crate,
+//! such as [`Monitor::events`](crate::monitor::Monitor::events) and
+//! [`Archiver::run_until`](crate::archiver::Archiver::run_until).
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, scheduler, thread::Thread};
+//! # use std::time::Duration;
+//! # async fn schedule() -> anyhow::Result<()> {
+//! let client = Client::new();
+//! let local = tokio::task::LocalSet::new();
+//! local
+//!     .run_until(async {
+//!         let thread = Thread::new(&client, "g", 76759434).await?;
+//!         let mut handle = scheduler::spawn(thread, &client, Duration::from_secs(30));
+//!         while handle.changed().await {
+//!             let thread = handle.latest();
+//!             println!("{} replies", thread.op().replies());
+//!             if thread.op().closed() {
+//!                 handle.stop();
+//!             }
+//!         }
+//!         let thread = handle.await_finished().await?;
+//!         Ok(())
+//!     })
+//!     .await
+//! # }
+//! ```
+
+use crate::{
+    archive::Archive,
+    catalog::Catalog,
+    logging::{debug, warn},
+    thread::{LazyThread, Thread},
+    Dot4chClient, Error, Update,
+};
+use chrono::Utc;
+use futures::{
+    stream::{self, Stream, StreamExt},
+    Future,
+};
+use std::{convert::TryFrom, sync::Arc, time::Duration};
+use tokio::{
+    sync::watch,
+    task::{self, JoinHandle},
+    time,
+};
+
+/// A running update loop of a thread or catalog.
+#[derive(Debug)]
+pub struct Handle<T> {
+    /// Tells the loop to stop
+    stop: watch::Sender<bool>,
+    /// The latest state of the target
+    latest: watch::Receiver<T>,
+    /// The task running the loop
+    task: JoinHandle<crate::Result<T>>,
+}
+
+/// Spawns a task updating `target` every `interval`.
+///
+/// The first update happens one interval after spawning, and every next one
+/// an interval after the previous update finished. An interval shorter than the cooldown of the target is stretched to the cooldown,
+/// as every update waits it out.
+///
+/// Errors which are worth retrying, such as timeouts and rate limits, are logged and
+/// the update is tried again on the next tick, after the `Retry-After` time if 4chan sent one.
+/// Any other error ends the loop and is returned by [`Handle::await_finished`].
+///
+/// Dropping the [`Handle`] stops the loop as well.
+///
+/// # Panics
+///
+/// This function panics if it is not called inside a [`tokio::task::LocalSet`].
+pub fn spawn<T>(target: T, client: &Dot4chClient, interval: Duration) -> Handle<T>
+where
+    T: Update<Output = T> + Clone + 'static,
+{
+    run(target, client, interval, move |_, _, _| interval)
+}
+
+/// Spawns a task updating `target` on an interval following its [`Activity`].
+///
+/// The first update happens `pacing.min` after spawning.
+/// Every update which brings no activity doubles the interval up to `pacing.max`,
+/// every update which does halves it down to `pacing.min`.
+///
+/// Otherwise the loop behaves like the one of [`spawn`].
+///
+/// # Panics
+///
+/// This function panics if it is not called inside a [`tokio::task::LocalSet`].
+pub fn spawn_adaptive<T>(target: T, client: &Dot4chClient, pacing: Adaptive) -> Handle<T>
+where
+    T: Update<Output = T> + Activity + Clone + 'static,
+{
+    run(target, client, pacing.min, move |interval, old, new| {
+        let next = pacing.next(interval, new.activity() > old.activity());
+        debug!("Next update in {:.1} seconds", next.as_secs_f32());
+        next
+    })
+}
+
+/// Runs the update loop, asking `next` for the interval after every successful update.
+fn run<T, F>(target: T, client: &Dot4chClient, interval: Duration, mut next: F) -> Handle<T>
+where
+    T: Update<Output = T> + Clone + 'static,
+    F: FnMut(Duration, &T, &T) -> Duration + 'static,
+{
+    let (stop, mut stopped) = watch::channel(false);
+    let (publish, latest) = watch::channel(target.clone());
+    let client = client.clone();
+    let task = task::spawn_local(async move {
+        let mut target = target;
+        let mut interval = interval;
+        loop {
+            tokio::select! {
+                _ = stopped.changed() => break,
+                () = crate::sleep(&client, interval) => {}
+            }
+            match target.clone().update().await {
+                Ok(updated) => {
+                    interval = next(interval, &target, &updated);
+                    target = updated;
+                    let _ = publish.send(target.clone());
+                }
+                Err(err) => {
+                    let Some(error) = Error::find(&err).filter(|error| error.is_retryable()) else {
+                        return Err(err);
+                    };
+                    warn!("Scheduled update failed, trying again: {err}");
+                    if let Some(wait) = error.retry_after() {
+                        debug!(
+                            "Waiting {:.1} seconds before the next update",
+                            wait.as_secs_f32()
+                        );
+                        crate::sleep(&client, wait).await;
+                    }
+                }
+            }
+        }
+        Ok(target)
+    });
+    Handle { stop, latest, task }
+}
+
+impl<T: Clone> Handle<T> {
+    /// Returns the state of the target after the last successful update.
+    pub fn latest(&self) -> T {
+        self.latest.borrow().clone()
+    }
+
+    /// Waits for the next successful update.
+    ///
+    /// Returns false once the loop finished and no more updates will come.
+    pub async fn changed(&mut self) -> bool {
+        self.latest.changed().await.is_ok()
+    }
+
+    /// Stops the loop after the update in progress, if any.
+    pub fn stop(&self) {
+        let _ = self.stop.send(true);
+    }
+
+    /// Waits for the loop to finish and returns the final state of the target.
+    ///
+    /// Call [`Handle::stop`] first, the loop only finishes on its own if an update fails.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the error which ended the loop,
+    /// or an error if the task panicked.
+    pub async fn await_finished(self) -> crate::Result<T> {
+        self.task.await?
+    }
+
+    /// Stops the loop and gives the update in progress up to `grace` to finish.
+    ///
+    /// Returns the final state of the target. If the update does not finish in time,
+    /// it is cancelled and the state after the last successful update is returned.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the error which ended the loop,
+    /// or an error if the task panicked.
+    pub async fn shutdown(self, grace: Duration) -> crate::Result<T> {
+        self.stop();
+        let latest = self.latest();
+        let mut task = self.task;
+        if let Ok(finished) = time::timeout(grace, &mut task).await {
+            return finished?;
+        }
+        warn!("Scheduled update did not finish within {grace:?}, cancelling it");
+        task.abort();
+        Ok(latest)
+    }
+}
+
+/// A target kept fresh by updating it every interval.
+///
+/// # Example
+///
+/// ```
+/// # use dot4ch::{Client, scheduler::Refreshable, thread::Thread};
+/// # use futures::{pin_mut, StreamExt};
+/// # use std::time::Duration;
+/// # async fn refreshable() -> anyhow::Result<()> {
+/// let client = Client::new();
+/// let thread = Thread::new(&client, "g", 76759434).await?;
+/// let mut thread = Refreshable::new(thread, &client).refresh_every(Duration::from_secs(20));
+///
+/// // update on demand
+/// println!("{} replies", thread.refresh().await?.op().replies());
+///
+/// // or every interval
+/// let updates = thread.into_stream();
+/// pin_mut!(updates);
+/// while let Some(thread) = updates.next().await {
+///     println!("{} replies", thread?.op().replies());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Refreshable<T> {
+    /// The state of the target after the last successful update
+    target: T,
+    /// The client whose timer the interval is slept on
+    client: Dot4chClient,
+    /// The time between two updates
+    interval: Duration,
+}
+
+impl<T> Refreshable<T>
+where
+    T: Update<Output = T> + Clone + 'static,
+{
+    /// Wraps a target updated every 30 seconds.
+    pub fn new(target: T, client: &Dot4chClient) -> Self {
+        Self {
+            target,
+            client: client.clone(),
+            interval: Duration::from_secs(30),
+        }
+    }
+
+    /// Sets the time between two updates.
+    ///
+    /// An interval shorter than the cooldown of the target is stretched to the cooldown,
+    /// as every update waits it out.
+    #[must_use]
+    pub fn refresh_every(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Returns the state of the target after the last successful update.
+    pub fn get(&self) -> &T {
+        &self.target
+    }
+
+    /// Returns the target.
+    pub fn into_inner(self) -> T {
+        self.target
+    }
+
+    /// Updates the target now and returns its new state.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the update fails,
+    /// in which case the previous state is kept.
+    pub async fn refresh(&mut self) -> crate::Result<&T> {
+        self.target = self.target.clone().update().await?;
+        Ok(&self.target)
+    }
+
+    /// Spawns a task updating the target every interval, see [`spawn`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is not called inside a [`tokio::task::LocalSet`].
+    pub fn spawn(self) -> Handle<T> {
+        spawn(self.target, &self.client, self.interval)
+    }
+
+    /// Returns a stream updating the target every interval and yielding every new state.
+    ///
+    /// Errors which are worth retrying are yielded and the stream goes on,
+    /// any other error is yielded last.
+    pub fn into_stream(self) -> impl Stream<Item = crate::Result<T>> {
+        stream::unfold(Some(self), |refreshable| async move {
+            let mut refreshable = refreshable?;
+            crate::sleep(&refreshable.client, refreshable.interval).await;
+            match refreshable.refresh().await {
+                Ok(target) => {
+                    let target = target.clone();
+                    Some((Ok(target), Some(refreshable)))
+                }
+                Err(err) => {
+                    let retryable = Error::find(&err).is_some_and(Error::is_retryable);
+                    Some((Err(err), retryable.then_some(refreshable)))
+                }
+            }
+        })
+    }
+}
+
+/// Runs a stream of futures one at a time under the request cooldown of the client.
+///
+/// Each future waits for a permit like a request of the crate would, at least a second
+/// after the previous request, then runs without holding the client, so a long download
+/// does not hold up the requests of the crate. Custom requests and media downloads
+/// can so share the cooldown with threads and catalogs fetched meanwhile,
+/// and the futures may use the client themselves.
+///
+/// The outcome of the futures is unknown to the client, so a 429 or a slow response
+/// among them does not widen the interval of [`Client::set_pacing`](crate::Client::set_pacing).
+///
+/// # Example
+///
+/// ```
+/// # use dot4ch::{Client, scheduler};
+/// # use futures::{pin_mut, stream, StreamExt};
+/// # async fn throttle() -> anyhow::Result<()> {
+/// let client = Client::new();
+/// let http = reqwest::Client::new();
+/// let urls = ["https://i.4cdn.org/g/1546293948883.png", "https://i.4cdn.org/g/1546293948884.png"];
+/// let downloads = scheduler::throttle(
+///     &client,
+///     stream::iter(urls).map(|url| http.get(url).send()),
+/// );
+/// pin_mut!(downloads);
+/// while let Some(response) = downloads.next().await {
+///     println!("{}", response?.status());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn throttle<S>(
+    client: &Dot4chClient,
+    futures: S,
+) -> impl Stream<Item = <S::Item as Future>::Output>
+where
+    S: Stream,
+    S::Item: Future,
+{
+    let client = client.clone();
+    futures.then(move |future| {
+        let client = client.clone();
+        async move {
+            {
+                let mut client = client.lock().await;
+                client.pace().await;
+                client.last_checked = Utc::now();
+            }
+            let output = future.await;
+            client.lock().await.last_checked = Utc::now();
+            output
+        }
+    })
+}
+
+/// A signal asking long running loops to finish their current step and stop.
+///
+/// Clones share the signal, so one can be handed to a loop and another kept to trigger it.
+///
+/// # Example
+///
+/// ```
+/// # use dot4ch::{Client, archiver::Archiver, scheduler::Shutdown};
+/// # use std::time::Duration;
+/// # async fn shutdown() -> anyhow::Result<()> {
+/// let client = Client::new();
+/// let shutdown = Shutdown::new();
+/// let trigger = shutdown.clone();
+/// tokio::spawn(async move {
+///     tokio::time::sleep(Duration::from_secs(3600)).await;
+///     trigger.trigger();
+/// });
+///
+/// // the pass in progress is written to disk before returning
+/// Archiver::new(&client, "g", "archive/g").run_until(&shutdown).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    /// Sets the signal, shared by every clone
+    trigger: Arc<watch::Sender<bool>>,
+    /// Watches the signal
+    triggered: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    /// Creates a signal which is not triggered yet.
+    pub fn new() -> Self {
+        let (trigger, triggered) = watch::channel(false);
+        Self {
+            trigger: Arc::new(trigger),
+            triggered,
+        }
+    }
+
+    /// Triggers the signal for every clone.
+    pub fn trigger(&self) {
+        let _ = self.trigger.send(true);
+    }
+
+    /// Returns true once the signal is triggered.
+    pub fn is_triggered(&self) -> bool {
+        *self.triggered.borrow()
+    }
+
+    /// Waits until the signal is triggered.
+    pub async fn triggered(&self) {
+        let mut triggered = self.triggered.clone();
+        while !*triggered.borrow() {
+            // the sender lives as long as `self`, so this never fails
+            if triggered.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The bounds of an adaptive polling interval, see [`spawn_adaptive`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Adaptive {
+    /// The interval of an active target
+    min: Duration,
+    /// The interval of a quiet target
+    max: Duration,
+}
+
+impl Adaptive {
+    /// Creates bounds polling between every `min` and every `max`.
+    ///
+    /// `max` is raised to `min` if it is shorter.
+    pub fn new(min: Duration, max: Duration) -> Self {
+        Self {
+            min,
+            max: max.max(min),
+        }
+    }
+
+    /// Returns the interval after `interval`, depending on whether the last update was active.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::scheduler::Adaptive;
+    /// # use std::time::Duration;
+    /// let pacing = Adaptive::new(Duration::from_secs(10), Duration::from_secs(60));
+    /// let quiet = pacing.next(Duration::from_secs(40), false);
+    /// assert_eq!(quiet, Duration::from_secs(60));
+    /// assert_eq!(pacing.next(quiet, true), Duration::from_secs(30));
+    /// ```
+    pub fn next(&self, interval: Duration, active: bool) -> Duration {
+        let next = if active {
+            interval / 2
+        } else {
+            interval.saturating_mul(2)
+        };
+        next.max(self.min).min(self.max)
+    }
+}
+
+/// A measure of the activity of an updated target, growing as it gets new posts.
+pub trait Activity {
+    /// Returns a number which grows with every new post or change of the target.
+    fn activity(&self) -> u64;
+}
+
+impl Activity for Thread {
+    /// The number of posts of the thread.
+    fn activity(&self) -> u64 {
+        u64::try_from(self.posts().count()).unwrap_or(u64::MAX)
+    }
+}
+
+impl Activity for LazyThread {
+    /// The number of posts of the thread.
+    fn activity(&self) -> u64 {
+        u64::try_from(self.len()).unwrap_or(u64::MAX)
+    }
+}
+
+impl Activity for Catalog {
+    /// The `last_modified` timestamp of the most recently bumped thread.
+    fn activity(&self) -> u64 {
+        self.threads()
+            .map(|thread| u64::try_from(thread.last_modified()).unwrap_or_default())
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+impl Activity for Archive {
+    /// The number of the most recently archived thread.
+    fn activity(&self) -> u64 {
+        self.threads().last().copied().map_or(0, u64::from)
+    }
+}
@@ -0,0 +1,335 @@
+//! Searching a whole board with a regex, or the posts of a single thread.
+//!
+//! Subjects are matched as they are, comments are matched as plain text,
+//! see [`Post::plain_text`]. [`across_boards`] searches the catalogs of several boards in turn.
+//!
+//! [`Thread::search`] looks through a thread already fetched for a substring or a regex
+//! and returns where each post matched.
+//!
+//! With the `tantivy` feature, posts can also be kept in a full-text `Index`.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, search::{self, Scope}};
+//! # use futures::{pin_mut, StreamExt};
+//! # use regex::Regex;
+//! # async fn search() -> anyhow::Result<()> {
+//! # let client = Client::new();
+//! let matches = search::board(&client, "g", Regex::new("(?i)thinkpad")?, Scope::Threads);
+//! pin_mut!(matches);
+//!
+//! // matches arrive as the threads are fetched
+//! while let Some(found) = matches.next().await {
+//!     let found = found?;
+//!     println!("/{}/{}#p{}", found.board, found.thread, found.post.id());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{board::ops, post::Post, text::Pipeline, thread::Thread, Dot4chClient};
+use futures::stream::{self, Stream, StreamExt};
+use regex::Regex;
+use std::{collections::VecDeque, iter, ops::Range};
+
+#[cfg(feature = "tantivy")]
+mod index;
+#[cfg(feature = "tantivy")]
+pub use index::{Index, PostRef};
+
+/// How much of a board is searched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// The OPs and the most recent replies shown in the catalog, a single request
+    Catalog,
+    /// Every post of every thread, one request per thread
+    Threads,
+}
+
+/// The part of a post which matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    /// The subject
+    Subject,
+    /// The comment
+    Comment,
+    /// The name, only searched by [`Thread::search`]
+    Name,
+}
+
+/// A post matching a search.
+#[derive(Debug, Clone)]
+pub struct Match {
+    /// The board of the post
+    pub board: String,
+    /// The OP number of the thread of the post
+    pub thread: u32,
+    /// The matching post
+    pub post: Post,
+    /// The part of the post which matched
+    pub field: Field,
+}
+
+/// The state of a running search.
+struct Search {
+    /// the client
+    client: Dot4chClient,
+    /// The board searched
+    board: String,
+    /// The pattern to match
+    regex: Regex,
+    /// How much of the board is searched
+    scope: Scope,
+    /// Posts waiting to be matched along with their thread
+    posts: VecDeque<(u32, Post)>,
+    /// Threads waiting to be fetched, `None` until the catalog is fetched
+    threads: Option<VecDeque<u32>>,
+}
+
+impl Search {
+    /// Returns a [`Match`] if the post matches.
+    fn check(&self, thread: u32, post: Post) -> Option<Match> {
+        let field = if self.regex.is_match(post.subject()) {
+            Field::Subject
+        } else if self.regex.is_match(&post.plain_text()) {
+            Field::Comment
+        } else {
+            return None;
+        };
+        Some(Match {
+            board: self.board.clone(),
+            thread,
+            post,
+            field,
+        })
+    }
+
+    /// Returns the next match, fetching the catalog or the next thread when out of posts.
+    ///
+    /// Returns `None` once everything was searched.
+    async fn next(&mut self) -> Option<crate::Result<Match>> {
+        loop {
+            if let Some((thread, post)) = self.posts.pop_front() {
+                if let Some(found) = self.check(thread, post) {
+                    return Some(Ok(found));
+                }
+                continue;
+            }
+            let Some(threads) = &mut self.threads else {
+                let catalog = match ops(&self.client, &self.board).await {
+                    Ok(catalog) => catalog,
+                    Err(err) => {
+                        self.threads = Some(VecDeque::new());
+                        return Some(Err(err));
+                    }
+                };
+                let mut threads = VecDeque::new();
+                for entry in catalog.into_iter().flat_map(|page| page.threads) {
+                    let id = entry.op.id();
+                    match self.scope {
+                        Scope::Catalog => self.posts.extend(
+                            iter::once(entry.op)
+                                .chain(entry.last_replies)
+                                .map(|post| (id, post)),
+                        ),
+                        Scope::Threads => threads.push_back(id),
+                    }
+                }
+                self.threads = Some(threads);
+                continue;
+            };
+            let id = threads.pop_front()?;
+            match Thread::new(&self.client, &self.board, id).await {
+                Ok(thread) => self.posts.extend(
+                    iter::once(thread.op())
+                        .chain(&thread[..])
+                        .map(|post| (id, post.clone())),
+                ),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// Searches the subjects and comments of a board.
+///
+/// Returns a stream of matches in catalog order, yielding each match as soon as it is found.
+/// Requests go through the client one at a time, so they respect the request cooldown.
+///
+/// A thread which cannot be fetched yields an error and the search continues with the next one.
+pub fn board(
+    client: &Dot4chClient,
+    board: &str,
+    regex: Regex,
+    scope: Scope,
+) -> impl Stream<Item = crate::Result<Match>> {
+    let search = Search {
+        client: client.clone(),
+        board: board.to_string(),
+        regex,
+        scope,
+        posts: VecDeque::new(),
+        threads: None,
+    };
+    stream::unfold(search, |mut search| async move {
+        let next = search.next().await?;
+        Some((next, search))
+    })
+}
+
+/// Searches the catalogs of several boards.
+///
+/// Returns a stream of matches tagged with their board, one board after another
+/// in the given order. The catalogs are fetched through the client one at a time.
+///
+/// A catalog which cannot be fetched yields an error and the search continues with the next board.
+///
+/// # Example
+///
+/// ```
+/// # use dot4ch::{Client, search};
+/// # use futures::{pin_mut, StreamExt};
+/// # use regex::Regex;
+/// # async fn search() -> anyhow::Result<()> {
+/// # let client = Client::new();
+/// let matches = search::across_boards(&client, &["g", "diy", "sci"], Regex::new("(?i)arduino")?);
+/// pin_mut!(matches);
+/// while let Some(found) = matches.next().await {
+///     let found = found?;
+///     println!("/{}/{}", found.board, found.thread);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn across_boards(
+    client: &Dot4chClient,
+    boards: &[&str],
+    regex: Regex,
+) -> impl Stream<Item = crate::Result<Match>> {
+    let client = client.clone();
+    let boards: Vec<_> = boards.iter().map(ToString::to_string).collect();
+    stream::iter(boards).flat_map(move |name| board(&client, &name, regex.clone(), Scope::Catalog))
+}
+
+/// What [`Thread::search`] looks for.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// A plain substring, matched case-sensitively
+    Text(String),
+    /// A regular expression
+    Regex(Regex),
+}
+
+impl Pattern {
+    /// Returns the pattern as a regex, escaping a substring.
+    fn regex(&self) -> Regex {
+        match self {
+            Self::Text(text) => {
+                Regex::new(&regex::escape(text)).expect("an escaped substring is a valid regex")
+            }
+            Self::Regex(regex) => regex.clone(),
+        }
+    }
+}
+
+impl From<&str> for Pattern {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<String> for Pattern {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<Regex> for Pattern {
+    fn from(regex: Regex) -> Self {
+        Self::Regex(regex)
+    }
+}
+
+/// The matches in one field of a post.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMatch {
+    /// The field which matched
+    pub field: Field,
+    /// The plain text of the field which was searched
+    pub text: String,
+    /// The byte ranges of every match in `text`, in order
+    pub spans: Vec<Range<usize>>,
+}
+
+impl FieldMatch {
+    /// Returns the matched parts of the text.
+    pub fn matches(&self) -> impl Iterator<Item = &str> {
+        self.spans.iter().map(move |span| &self.text[span.clone()])
+    }
+}
+
+/// A post of a thread matching a [`Thread::search`].
+#[derive(Debug, Clone)]
+pub struct PostMatch<'a> {
+    /// The matching post
+    pub post: &'a Post,
+    /// Every field which matched, in the order subject, name, comment
+    pub fields: Vec<FieldMatch>,
+}
+
+impl Thread {
+    /// Searches the subject, name and comment of every post, OP first.
+    ///
+    /// Each field is turned into plain text with the default [`Pipeline`] before it is searched,
+    /// so the spans of a match point into that text, see [`FieldMatch::text`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, thread::Thread};
+    /// # use regex::Regex;
+    /// # async fn search() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let thread = Thread::new(&client, "g", 76759434).await?;
+    ///
+    /// for found in thread.search("thinkpad") {
+    ///     println!("found in >>{}", found.post.id());
+    /// }
+    /// for found in thread.search(Regex::new(r"(?i)x\d{3}")?) {
+    ///     for field in &found.fields {
+    ///         println!("{:?}: {:?}", field.field, field.matches().collect::<Vec<_>>());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search(&self, pattern: impl Into<Pattern>) -> Vec<PostMatch<'_>> {
+        let regex = pattern.into().regex();
+        let pipeline = Pipeline::default();
+        self.posts()
+            .filter_map(|post| {
+                let fields: Vec<_> = [
+                    (Field::Subject, post.subject()),
+                    (Field::Name, post.name()),
+                    (Field::Comment, post.content()),
+                ]
+                .iter()
+                .filter(|(_, html)| !html.is_empty())
+                .filter_map(|&(field, html)| {
+                    let text = pipeline.apply(html);
+                    let spans: Vec<_> = regex.find_iter(&text).map(|m| m.range()).collect();
+                    if spans.is_empty() {
+                        return None;
+                    }
+                    Some(FieldMatch { field, text, spans })
+                })
+                .collect();
+                if fields.is_empty() {
+                    return None;
+                }
+                Some(PostMatch { post, fields })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,241 @@
+//! A full-text index of posts backed by tantivy.
+
+use crate::{post::Post, thread::Thread, Result};
+use std::{convert::TryFrom, fmt, iter, path::Path};
+use tantivy::{
+    collector::TopDocs,
+    directory::MmapDirectory,
+    doc,
+    query::QueryParser,
+    schema::{Field, Schema, Value, INDEXED, STORED, STRING, TEXT},
+    Index as Tantivy, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term,
+};
+
+/// The memory budget of the index writer in bytes.
+const WRITER_MEMORY: usize = 50_000_000;
+
+/// A post found by a query on an [`Index`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostRef {
+    /// The board of the post
+    pub board: String,
+    /// The OP number of the thread of the post
+    pub thread: u32,
+    /// The post number
+    pub post: u32,
+    /// UNIX timestamp of the post
+    pub time: i64,
+    /// How well the post matched the query
+    pub score: f32,
+}
+
+/// The fields of the index.
+#[derive(Debug, Clone, Copy)]
+struct Fields {
+    /// `board/post`, used to replace a post which is ingested again
+    key: Field,
+    /// The board
+    board: Field,
+    /// The OP number of the thread
+    thread: Field,
+    /// The post number
+    post: Field,
+    /// UNIX timestamp of the post
+    time: Field,
+    /// The subject
+    subject: Field,
+    /// The comment as plain text
+    comment: Field,
+    /// The name and tripcode
+    name: Field,
+}
+
+impl Fields {
+    /// Returns the schema of the index and its fields.
+    fn schema() -> (Schema, Self) {
+        let mut builder = Schema::builder();
+        let fields = Self {
+            key: builder.add_text_field("key", STRING),
+            board: builder.add_text_field("board", STRING | STORED),
+            thread: builder.add_u64_field("thread", INDEXED | STORED),
+            post: builder.add_u64_field("post", STORED),
+            time: builder.add_i64_field("time", INDEXED | STORED),
+            subject: builder.add_text_field("subject", TEXT),
+            comment: builder.add_text_field("comment", TEXT),
+            name: builder.add_text_field("name", TEXT),
+        };
+        (builder.build(), fields)
+    }
+}
+
+/// A full-text index over the subjects, comments and names of posts.
+///
+/// Posts can be ingested again as threads get updated, a post is only indexed once.
+/// Changes become searchable after [`Index::commit`].
+///
+/// Requires the `tantivy` feature.
+///
+/// # Example
+///
+/// ```
+/// # use dot4ch::{Client, search::Index, thread::Thread};
+/// # async fn index() -> anyhow::Result<()> {
+/// # let client = Client::new();
+/// let mut index = Index::open("index/g")?;
+///
+/// let thread = Thread::new(&client, "g", 76759434).await?;
+/// index.add_thread(&thread)?;
+/// index.commit()?;
+///
+/// for found in index.query("thinkpad AND battery", 10)? {
+///     println!("/{}/{}#p{}", found.board, found.thread, found.post);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Index {
+    /// The tantivy index
+    tantivy: Tantivy,
+    /// The fields of the index
+    fields: Fields,
+    /// Adds and removes documents
+    writer: IndexWriter,
+    /// Searches the committed documents
+    reader: IndexReader,
+}
+
+impl fmt::Debug for Index {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Index")
+            .field("tantivy", &self.tantivy)
+            .field("fields", &self.fields)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Index {
+    /// Opens the index in a directory, creating it if needed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directory cannot be opened
+    /// or holds an index with another schema.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        std::fs::create_dir_all(&path)?;
+        let (schema, fields) = Fields::schema();
+        let directory = MmapDirectory::open(path)?;
+        Self::with_index(Tantivy::open_or_create(directory, schema)?, fields)
+    }
+
+    /// Creates an index which is only kept in memory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the index writer cannot be created.
+    pub fn in_memory() -> Result<Self> {
+        let (schema, fields) = Fields::schema();
+        Self::with_index(Tantivy::create_in_ram(schema), fields)
+    }
+
+    /// Wraps a tantivy index.
+    fn with_index(tantivy: Tantivy, fields: Fields) -> Result<Self> {
+        let writer = tantivy.writer(WRITER_MEMORY)?;
+        let reader = tantivy
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        Ok(Self {
+            tantivy,
+            fields,
+            writer,
+            reader,
+        })
+    }
+
+    /// Adds a post, replacing it if it was added before.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the index writer failed.
+    pub fn add_post(&mut self, board: &str, thread: u32, post: &Post) -> Result<()> {
+        let fields = self.fields;
+        let key = format!("{}/{}", board, post.id());
+        self.writer
+            .delete_term(Term::from_field_text(fields.key, &key));
+        let name = match post.tripcode() {
+            Some(trip) => format!("{} {}", post.name(), trip),
+            None => post.name().to_string(),
+        };
+        self.writer.add_document(doc!(
+            fields.key => key,
+            fields.board => board,
+            fields.thread => u64::from(thread),
+            fields.post => u64::from(post.id()),
+            fields.time => post.post_time(),
+            fields.subject => post.subject(),
+            fields.comment => post.plain_text(),
+            fields.name => name,
+        ))?;
+        Ok(())
+    }
+
+    /// Adds every post of a thread.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the index writer failed.
+    pub fn add_thread(&mut self, thread: &Thread) -> Result<()> {
+        let id = thread.op().id();
+        for post in iter::once(thread.op()).chain(&thread[..]) {
+            self.add_post(thread.board(), id, post)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the added posts to the index and makes them searchable.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the index cannot be written.
+    pub fn commit(&mut self) -> Result<()> {
+        self.writer.commit()?;
+        self.reader.reload()?;
+        Ok(())
+    }
+
+    /// Returns the best matches of a query, best first.
+    ///
+    /// The query uses the [tantivy query syntax](<https://docs.rs/tantivy/latest/tantivy/query/struct.QueryParser.html>)
+    /// and searches the subject, comment and name by default.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the query is invalid or the search fails.
+    pub fn query(&self, query: &str, limit: usize) -> Result<Vec<PostRef>> {
+        let fields = self.fields;
+        let parser = QueryParser::for_index(
+            &self.tantivy,
+            vec![fields.subject, fields.comment, fields.name],
+        );
+        let query = parser.parse_query(query)?;
+        let searcher = self.reader.searcher();
+
+        let mut found = vec![];
+        for (score, address) in searcher.search(&query, &TopDocs::with_limit(limit))? {
+            let document: TantivyDocument = searcher.doc(address)?;
+            let text = |field| document.get_first(field).and_then(|v| v.as_str());
+            let number = |field| document.get_first(field).and_then(|v| v.as_u64());
+            found.push(PostRef {
+                board: text(fields.board).unwrap_or_default().to_string(),
+                thread: u32::try_from(number(fields.thread).unwrap_or_default())?,
+                post: u32::try_from(number(fields.post).unwrap_or_default())?,
+                time: document
+                    .get_first(fields.time)
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or_default(),
+                score,
+            });
+        }
+        Ok(found)
+    }
+}
@@ -0,0 +1,413 @@
+//! Summaries of a [`Board`] cache or a single [`Thread`].
+//!
+//! A [`PageTracker`] follows where threads are in the catalog from poll to poll.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, board::Board};
+//! # async fn stats() -> anyhow::Result<()> {
+//! # let client = Client::new();
+//! let board = Board::build(&client, "g").await?;
+//! let stats = board.stats();
+//!
+//! println!("{} posts in {} threads", stats.posts, stats.threads);
+//! for (thread, posts) in &stats.most_active {
+//!     println!("{} got {} posts in the last hour", thread, posts);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    board::Board,
+    boards::BoardInfo,
+    catalog::Catalog,
+    post::{FileKind, Post},
+    thread::Thread,
+};
+use chrono::{DateTime, Utc};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    iter,
+    time::Duration,
+};
+
+/// The number of threads in [`BoardStats::most_active`].
+const MOST_ACTIVE: usize = 5;
+
+/// The number of files in [`MediaReport::largest`].
+const LARGEST: usize = 5;
+
+/// Totals over every cached thread of a [`Board`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BoardStats {
+    /// The number of threads
+    pub threads: usize,
+    /// The number of posts, OPs included
+    pub posts: usize,
+    /// The number of posts with a file
+    pub images: usize,
+    /// The sum of the unique posters of every thread
+    pub unique_posters: u64,
+    /// The number of posts made in the last hour
+    pub last_hour: usize,
+    /// The threads with the most posts in the last hour and their post count, most active first
+    pub most_active: Vec<(u32, usize)>,
+}
+
+impl Board {
+    /// Returns totals over every cached thread.
+    ///
+    /// The numbers reflect the cache as of the last build or update.
+    pub fn stats(&self) -> BoardStats {
+        let hour_ago = Utc::now().timestamp() - 3600;
+        let mut stats = BoardStats {
+            threads: self.len(),
+            ..BoardStats::default()
+        };
+        let mut active = vec![];
+        for (&id, thread) in self {
+            let mut recent = 0;
+            for post in posts(thread) {
+                stats.posts += 1;
+                if !post.filename().is_empty() {
+                    stats.images += 1;
+                }
+                if post.post_time() >= hour_ago {
+                    recent += 1;
+                }
+            }
+            stats.unique_posters += u64::from(thread.op().unique_ips().unwrap_or(0));
+            stats.last_hour += recent;
+            if recent > 0 {
+                active.push((id, recent));
+            }
+        }
+        active.sort_unstable_by_key(|&(id, recent)| (Reverse(recent), id));
+        active.truncate(MOST_ACTIVE);
+        stats.most_active = active;
+        stats
+    }
+}
+
+/// Figures about a single [`Thread`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThreadStats {
+    /// The number of replies
+    pub replies: usize,
+    /// The number of posts with a file, OP included
+    pub images: usize,
+    /// The number of unique posters as reported by the API, only present on live threads
+    pub unique_ips: Option<u16>,
+    /// The number of distinct names and tripcodes other than `Anonymous`
+    pub named_posters: usize,
+    /// The number of posts of every poster ID, empty on boards without IDs
+    pub poster_ids: HashMap<String, usize>,
+    /// The time between the OP and the last reply
+    pub span: Duration,
+    /// The average time between two posts, `None` without replies
+    pub average_gap: Option<Duration>,
+    /// Whether the thread reached the bump limit
+    pub bump_limit: bool,
+}
+
+impl ThreadStats {
+    /// Returns how many replies are left until a bump limit of `limit` replies.
+    ///
+    /// The bump limit of every board is listed in `boards.json`.
+    pub fn until_bump_limit(&self, limit: usize) -> usize {
+        if self.bump_limit {
+            return 0;
+        }
+        limit.saturating_sub(self.replies)
+    }
+}
+
+impl Thread {
+    /// Returns figures about the posts of the thread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, thread::Thread};
+    /// # async fn stats() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let stats = Thread::new(&client, "g", 76759434).await?.stats();
+    /// println!("{} replies, one every {:?}", stats.replies, stats.average_gap);
+    /// println!("{} replies until the bump limit", stats.until_bump_limit(310));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stats(&self) -> ThreadStats {
+        let op = self.op();
+        let mut names = HashSet::new();
+        let mut stats = ThreadStats {
+            replies: self[..].len(),
+            unique_ips: op.unique_ips(),
+            bump_limit: op.bump_limit(),
+            ..ThreadStats::default()
+        };
+        for post in posts(self) {
+            if !post.filename().is_empty() {
+                stats.images += 1;
+            }
+            if let Some(trip) = post.tripcode() {
+                names.insert(trip);
+            } else if !post.name().is_empty() && post.name() != "Anonymous" {
+                names.insert(post.name());
+            }
+            if let Some(id) = post.poster_id() {
+                *stats.poster_ids.entry(id.to_string()).or_insert(0) += 1;
+            }
+        }
+        stats.named_posters = names.len();
+
+        let last = self.last_post().unwrap_or(op);
+        let span = u64::try_from(last.post_time() - op.post_time()).unwrap_or(0);
+        stats.span = Duration::from_secs(span);
+        stats.average_gap = u32::try_from(stats.replies)
+            .ok()
+            .filter(|&replies| replies > 0)
+            .map(|replies| stats.span / replies);
+        stats
+    }
+}
+
+/// The files of one kind in a [`MediaReport`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KindTotal {
+    /// The number of files
+    pub files: usize,
+    /// The sum of their sizes in bytes
+    pub bytes: u64,
+}
+
+/// The files attached to the posts of a [`Thread`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MediaReport {
+    /// The number of files which were not deleted
+    pub files: usize,
+    /// The sum of the sizes of the files which were not deleted, in bytes
+    pub bytes: u64,
+    /// The files and bytes of every kind of file
+    pub kinds: HashMap<FileKind, KindTotal>,
+    /// The posts with the largest files and their size in bytes, largest first
+    pub largest: Vec<(u32, u32)>,
+    /// The number of files which were deleted
+    pub deleted: usize,
+}
+
+impl Thread {
+    /// Returns what downloading every file of the thread takes.
+    ///
+    /// Deleted files cannot be downloaded, so they are only counted in [`MediaReport::deleted`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dot4ch::{Client, post::{FileKind, Post}, thread::Thread};
+    ///
+    /// let op = Post::builder(1).file(1, "op", ".png").file_size(2048, 640, 480).build();
+    /// let reply = Post::builder(2)
+    ///     .reply_to(1)
+    ///     .file(2, "clip", ".webm")
+    ///     .file_size(4096, 640, 480)
+    ///     .build();
+    /// let thread = Thread::builder("g", op).reply(reply).build(&Client::new());
+    ///
+    /// let report = thread.media_report();
+    /// assert_eq!(report.files, 2);
+    /// assert_eq!(report.bytes, 6144);
+    /// assert_eq!(report.kinds[&FileKind::Webm].bytes, 4096);
+    /// assert_eq!(report.largest[0], (2, 4096));
+    /// ```
+    pub fn media_report(&self) -> MediaReport {
+        let mut report = MediaReport::default();
+        for post in posts(self) {
+            if post.file_deleted() {
+                report.deleted += 1;
+                continue;
+            }
+            let Some(kind) = post.file_kind() else {
+                continue;
+            };
+            let size = post.filesize().unwrap_or(0);
+            report.files += 1;
+            report.bytes += u64::from(size);
+            let total = report.kinds.entry(kind).or_default();
+            total.files += 1;
+            total.bytes += u64::from(size);
+            report.largest.push((post.id(), size));
+        }
+        report
+            .largest
+            .sort_unstable_by_key(|&(id, size)| (Reverse(size), id));
+        report.largest.truncate(LARGEST);
+        report
+    }
+
+    /// Estimates how long until the thread falls off the last page of its board,
+    /// if it gets no more bumps.
+    ///
+    /// Every thread bumped or created behind this one pushes it one place down.
+    /// The pace of that is read from the catalog: the last thread was pushed down by
+    /// every thread above it since its own last bump.
+    ///
+    /// Threads past the bump limit get no more bumps, so the estimate holds for them.
+    /// For other threads it is a lower bound, as every bump brings them back to the first page.
+    ///
+    /// Returns `None` if the thread is pinned, is not in the catalog, or the catalog
+    /// has too few threads to tell the pace.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, boards::Boards, catalog::Catalog, thread::Thread};
+    /// # async fn prune() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let boards = Boards::new(&client).await?;
+    /// let catalog = Catalog::new(&client, "g").await?;
+    /// let thread = Thread::new(&client, "g", 76759434).await?;
+    /// if let Some(g) = boards.get("g") {
+    ///     println!("pruned in {:?}", thread.estimate_time_to_prune(&catalog, g));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn estimate_time_to_prune(&self, catalog: &Catalog, board: &BoardInfo) -> Option<Duration> {
+        if self.op().sticky() {
+            return None;
+        }
+        let threads: Vec<_> = catalog.threads().collect();
+        let position = threads
+            .iter()
+            .position(|thread| thread.id() == self.op().id())?;
+        let last = threads.last()?;
+        let span =
+            u64::try_from(catalog.last_accessed().timestamp() - last.last_modified()).ok()?;
+        let pushed = u64::try_from(threads.len() - 1)
+            .ok()
+            .filter(|&pushed| pushed > 0)?;
+        let capacity = u64::from(board.pages()) * u64::from(board.per_page());
+        let behind = capacity.saturating_sub(u64::try_from(position).ok()? + 1);
+        Some(Duration::from_secs(behind.saturating_mul(span) / pushed))
+    }
+}
+
+/// Where a thread was in one catalog, see [`PageTracker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    /// When the catalog was fetched
+    pub at: DateTime<Utc>,
+    /// The page the thread was on, starting at 1
+    pub page: u8,
+    /// The place of the thread on its page, starting at 0
+    pub position: usize,
+    /// The place of the thread in the whole catalog, starting at 0
+    pub rank: usize,
+}
+
+/// Records where chosen threads are in every catalog it is given.
+///
+/// # Example
+///
+/// ```
+/// # use dot4ch::{Client, Update, catalog::Catalog, stats::PageTracker};
+/// # async fn track() -> anyhow::Result<()> {
+/// # let client = Client::new();
+/// let mut tracker = PageTracker::new();
+/// tracker.track(76759434);
+///
+/// let mut catalog = Catalog::new(&client, "g").await?;
+/// loop {
+///     for gone in tracker.record(&catalog) {
+///         println!("{} fell off the board", gone);
+///     }
+///     if let Some(last) = tracker.last(76759434) {
+///         if last.page >= 9 {
+///             println!("time for a final archive pass");
+///         }
+///     }
+///     catalog = catalog.update().await?;
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PageTracker {
+    /// The placements of every tracked thread, oldest first
+    trajectories: HashMap<u32, Vec<Placement>>,
+}
+
+impl PageTracker {
+    /// Creates a tracker without any threads.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts tracking a thread. Its recorded placements are kept if it was tracked already.
+    pub fn track(&mut self, id: u32) {
+        self.trajectories.entry(id).or_default();
+    }
+
+    /// Stops tracking a thread and returns its placements.
+    pub fn untrack(&mut self, id: u32) -> Option<Vec<Placement>> {
+        self.trajectories.remove(&id)
+    }
+
+    /// Returns the tracked threads, in no particular order.
+    pub fn tracked(&self) -> impl Iterator<Item = u32> + '_ {
+        self.trajectories.keys().copied()
+    }
+
+    /// Records where every tracked thread is in `catalog`.
+    ///
+    /// Returns the tracked threads which are not in the catalog, sorted,
+    /// which were pruned or archived. They are still tracked, in case the catalog was stale.
+    pub fn record(&mut self, catalog: &Catalog) -> Vec<u32> {
+        let at = catalog.last_accessed();
+        let mut seen = HashSet::new();
+        let mut rank = 0;
+        for page in &catalog[..] {
+            for (position, thread) in page[..].iter().enumerate() {
+                if let Some(trajectory) = self.trajectories.get_mut(&thread.id()) {
+                    trajectory.push(Placement {
+                        at,
+                        page: page.num(),
+                        position,
+                        rank,
+                    });
+                    seen.insert(thread.id());
+                }
+                rank += 1;
+            }
+        }
+        let mut missing: Vec<_> = self
+            .trajectories
+            .keys()
+            .copied()
+            .filter(|id| !seen.contains(id))
+            .collect();
+        missing.sort_unstable();
+        missing
+    }
+
+    /// Returns the placements of a thread, oldest first.
+    ///
+    /// Empty if the thread is not tracked or was in none of the recorded catalogs.
+    pub fn trajectory(&self, id: u32) -> &[Placement] {
+        self.trajectories.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the latest placement of a thread.
+    pub fn last(&self, id: u32) -> Option<&Placement> {
+        self.trajectory(id).last()
+    }
+}
+
+/// Returns every post of a thread, OP first.
+fn posts(thread: &Thread) -> impl Iterator<Item = &Post> {
+    iter::once(thread.op()).chain(&thread[..])
+}
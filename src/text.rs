@@ -0,0 +1,200 @@
+//! Utilities for turning HTML escaped comments into plain text.
+//!
+//! Each step is usable on its own, or composed into a [`Pipeline`].
+//!
+//! # Example
+//!
+//! ```
+//! use dot4ch::text::{Pipeline, Step};
+//!
+//! let com = "<span class=\"quote\">&gt;be me</span><br><br><br>  it&#039;s   over";
+//!
+//! assert_eq!(Pipeline::default().apply(com), ">be me\n\nit's over");
+//!
+//! // Only strip the tags and keep everything else as is.
+//! let strip = Pipeline::new().then(Step::StripTags);
+//! assert_eq!(strip.apply(com), "&gt;be me\n\n\n  it&#039;s   over");
+//! ```
+
+use crate::{
+    comment::{Token, Tokens},
+    post::Post,
+};
+
+/// A single transformation in a [`Pipeline`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// See [`strip_tags`]
+    StripTags,
+    /// See [`decode_entities`]
+    DecodeEntities,
+    /// See [`collapse_whitespace`]
+    CollapseWhitespace,
+    /// See [`normalize_unicode`]
+    #[cfg(feature = "unicode")]
+    NormalizeUnicode,
+}
+
+impl Step {
+    /// Applies this step to `text`.
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Self::StripTags => strip_tags(text),
+            Self::DecodeEntities => decode_entities(text),
+            Self::CollapseWhitespace => collapse_whitespace(text),
+            #[cfg(feature = "unicode")]
+            Self::NormalizeUnicode => normalize_unicode(text),
+        }
+    }
+}
+
+/// An ordered list of [`Step`]s applied to a text.
+///
+/// The default pipeline strips tags, decodes entities and collapses whitespace,
+/// in that order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pipeline {
+    /// The steps in the order they are applied
+    steps: Vec<Step>,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+            .then(Step::StripTags)
+            .then(Step::DecodeEntities)
+            .then(Step::CollapseWhitespace)
+    }
+}
+
+impl Pipeline {
+    /// Returns an empty pipeline which leaves the text unchanged.
+    pub fn new() -> Self {
+        Self { steps: vec![] }
+    }
+
+    /// Adds a step at the end of the pipeline.
+    #[must_use]
+    pub fn then(mut self, step: Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Returns the steps of the pipeline.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Runs `text` through every step of the pipeline.
+    pub fn apply(&self, text: &str) -> String {
+        self.steps
+            .iter()
+            .fold(text.to_string(), |text, step| step.apply(&text))
+    }
+}
+
+/// Removes all HTML tags, turning `<br>` into line breaks.
+///
+/// Entities are left as they are.
+pub fn strip_tags(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for token in Tokens::new(text) {
+        match token {
+            Token::Text(text) => out.push_str(text),
+            Token::Open { name, .. } if name.eq_ignore_ascii_case("br") => out.push('\n'),
+            Token::Open { .. } | Token::Close(_) => {}
+        }
+    }
+    out
+}
+
+/// Decodes named and numeric HTML entities.
+///
+/// Unknown entities are left as they are.
+///
+/// ```
+/// # use dot4ch::text::decode_entities;
+/// assert_eq!(decode_entities("&gt;&gt;1 &amp; &#039;&#x41;&quot; &bogus;"), ">>1 & 'A\" &bogus;");
+/// ```
+pub fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest
+            .find(';')
+            .and_then(|end| Some((decode_entity(&rest[1..end])?, end)));
+        if let Some((c, end)) = decoded {
+            out.push(c);
+            rest = &rest[end + 1..];
+        } else {
+            out.push('&');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Decodes the name of a single entity, without the `&` and `;`.
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(num) = name.strip_prefix('#') {
+        let code = match num.strip_prefix('x').or_else(|| num.strip_prefix('X')) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => num.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        _ => return None,
+    })
+}
+
+/// Collapses runs of whitespace into a single space
+/// and runs of blank lines into a single blank line.
+///
+/// Leading and trailing whitespace is removed.
+pub fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank_lines = 0;
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+        }
+        out.push_str(&line);
+        blank_lines = 0;
+    }
+    out
+}
+
+/// Normalizes text to Unicode NFKC, folding lookalike characters
+/// such as fullwidth letters into their plain forms.
+///
+/// ```
+/// # use dot4ch::text::normalize_unicode;
+/// assert_eq!(normalize_unicode("ｒｕｓｔ"), "rust");
+/// ```
+#[cfg(feature = "unicode")]
+pub fn normalize_unicode(text: &str) -> String {
+    use unicode_normalization::UnicodeNormalization;
+    text.nfkc().collect()
+}
+
+impl Post {
+    /// Returns the comment as plain text using the default [`Pipeline`].
+    pub fn plain_text(&self) -> String {
+        Pipeline::default().apply(self.content())
+    }
+}
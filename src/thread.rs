@@ -6,25 +6,57 @@
 //! This contains all the replies from the given thread.
 //!
 
-use crate::{board::Board, Dot4chClient, IfModifiedSince, Procedures, Update};
+use crate::{
+    board::Board,
+    comment::Link,
+    hash::HashMap,
+    logging::debug,
+    persist::{read, write, Format, Metadata, Persist, Snapshot},
+    text, Dot4chClient, Error, IfModifiedSince, PostNo, Procedures, ThreadId, Updatable, Update,
+    UpdateStatus,
+};
+use anyhow::Context;
 use async_trait::async_trait;
 
-use super::{post::Post, Result};
+use super::{
+    post::{parse_each, Capcode, Post, SkippedPost},
+    Result,
+};
 use chrono::{DateTime, Duration, NaiveDateTime, Utc};
-use log::debug;
-use reqwest::{header::IF_MODIFIED_SINCE, Response, StatusCode};
+use futures::stream::{self, Stream, StreamExt};
+use reqwest::{Response, StatusCode};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::{
-    collections::HashMap,
+    collections::BTreeSet,
     fmt::{Display, Formatter},
+    iter, mem,
     ops::Index,
+    path::Path,
     slice::SliceIndex,
+    sync::{Arc, OnceLock},
 };
-use tokio::time;
+
+mod lazy;
+
+pub use lazy::LazyThread;
+
+/// Where a thread was fetched from, see [`Thread::new_or_archived`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Source {
+    /// The thread is still live on 4chan
+    Live,
+    /// The thread is in the board's archive on 4chan
+    Archive,
+    /// The thread is gone from 4chan and came from an external archive
+    External,
+}
 
 /// The main end user interface to the 4chan thread API.
 ///
 /// Contains data about a chan thread.
+///
+/// The posts are shared between clones, so cloning a thread is cheap.
 #[derive(Debug, Clone)]
 pub struct Thread {
     /// The Original Post
@@ -35,18 +67,72 @@ pub struct Thread {
     replies_no: usize,
     /// The latest reply
     last_reply: Option<u32>,
-    /// All the posts in the thread
-    all_replies: Vec<Post>,
+    /// All the posts in the thread, shared between clones
+    all_replies: Arc<Vec<Post>>,
+    /// The position of every reply in `all_replies` by post number, shared between clones
+    index: Arc<HashMap<PostNo, usize>>,
+    /// The posts quoting each post number, by position OP first, built on first use
+    quotes: Arc<OnceLock<HashMap<u32, Vec<usize>>>>,
     /// When the thread was archived
     archive_time: Option<NaiveDateTime>,
     /// Thread archival status
     archived: bool,
     /// Last time the thread was requested.
     last_update: Option<DateTime<Utc>>,
+    /// The `Last-Modified` header of the last response
+    last_modified: Option<String>,
+    /// The posts which were skipped in lenient mode
+    skipped: Vec<SkippedPost>,
     /// the client
     client: Dot4chClient,
 }
 
+/// Builds a [`Thread`] without fetching it, see [`Thread::builder`].
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct ThreadBuilder {
+    /// The board of the thread
+    board: String,
+    /// The original post
+    op: Post,
+    /// The replies, in order
+    replies: Vec<Post>,
+    /// The `Last-Modified` header the thread was fetched with
+    last_modified: Option<String>,
+}
+
+impl ThreadBuilder {
+    /// Adds a reply after the previous ones.
+    pub fn reply(mut self, post: Post) -> Self {
+        self.replies.push(post);
+        self
+    }
+
+    /// Adds replies after the previous ones.
+    pub fn replies<I: IntoIterator<Item = Post>>(mut self, posts: I) -> Self {
+        self.replies.extend(posts);
+        self
+    }
+
+    /// Sets the `Last-Modified` header the thread was fetched with,
+    /// sent as `If-Modified-Since` by its next update.
+    pub fn last_modified(mut self, last_modified: &str) -> Self {
+        self.last_modified = Some(last_modified.to_string());
+        self
+    }
+
+    /// Builds the thread, updated through `client`.
+    pub fn build(self, client: &Dot4chClient) -> Thread {
+        Thread::assemble(
+            client,
+            &self.board,
+            self.op,
+            self.replies,
+            self.last_modified,
+        )
+    }
+}
+
 impl Display for Thread {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let fmt = format!(
@@ -66,15 +152,8 @@ impl IfModifiedSince for Thread {
         client: &Dot4chClient,
         url: &str,
         header: &str,
-    ) -> std::result::Result<Response, reqwest::Error> {
-        client
-            .lock()
-            .await
-            .req_client()
-            .get(url)
-            .header(IF_MODIFIED_SINCE, header)
-            .send()
-            .await
+    ) -> std::result::Result<Response, Error> {
+        crate::get_since(client, url, header).await
     }
 }
 
@@ -85,7 +164,22 @@ impl Update for Thread {
     ///
     /// `update()` respects
     /// 4chan's 10 seconds between each chan thread call.
-    async fn update(mut self) -> Result<Self> {
+    async fn update(self) -> Result<Self> {
+        Ok(self.updated().await?.0)
+    }
+}
+
+#[async_trait(?Send)]
+impl Updatable for Thread {
+    async fn update(&mut self, client: &Dot4chClient) -> Result<UpdateStatus> {
+        self.client = client.clone();
+        self.try_update().await
+    }
+}
+
+impl Thread {
+    /// Returns the updated thread and whether it changed.
+    pub(crate) async fn updated(mut self) -> Result<(Self, UpdateStatus)> {
         if self.archived {
             let archival_time = match self.archive_time {
                 // If-Modified-Since: Wed, 21 Oct 2015 07:28:00 GMT
@@ -103,11 +197,24 @@ impl Update for Thread {
 
         self.refresh_time().await?;
 
-        let header = crate::header(&self.client).await;
-        let response = Self::fetch(&self.client, &self.thread_url(), &header).await?;
-        self.client.lock().await.last_checked = Utc::now();
-
-        let mut thread = self.fetch_status(response).await?;
+        let url = self.thread_url();
+        let context = format!("updating thread /{}/{} ({url})", self.board, self.op.id());
+        let response = crate::refresh(
+            &self.client,
+            &url,
+            &self.board,
+            Some(self.op.id()),
+            self.last_modified.as_deref(),
+        )
+        .await
+        .with_context(|| context.clone())?;
+        let (mut thread, status) = match response {
+            Some(response) => (
+                self.into_upper(response).await.context(context)?,
+                UpdateStatus::Modified,
+            ),
+            None => (self, UpdateStatus::NotModified),
+        };
 
         thread.update_time();
 
@@ -117,7 +224,7 @@ impl Update for Thread {
         );
 
         thread.client.lock().await.last_checked = Utc::now();
-        Ok(thread)
+        Ok((thread, status))
     }
 }
 
@@ -133,7 +240,7 @@ impl Procedures for Thread {
                     10000_f32 - curr.num_milliseconds() as f32 / 1000_f32
                 );
                 match Duration::seconds(10).checked_sub(&curr) {
-                    Some(time) => time::sleep(time.to_std()?).await,
+                    Some(time) => crate::sleep(&self.client, time.to_std()?).await,
                     None => return Err(anyhow::anyhow!("Overflow in subtraction of `Duration`s")),
                 }
             }
@@ -143,13 +250,14 @@ impl Procedures for Thread {
 
     /// Checks the status of a `Response and generates a new thread if needed.`
     async fn fetch_status(mut self, response: Response) -> Result<Thread> {
+        let response = crate::check_in(response, &self.board, Some(self.op.id())).await?;
         match response.status() {
             StatusCode::OK => self.into_upper(response).await,
             StatusCode::NOT_MODIFIED => {
                 self.last_update = Some(Utc::now());
                 Ok(self)
             }
-            other_resp => return Err(anyhow::anyhow!("Unexpected StatusCode {}", other_resp)),
+            _ => Err(Error::unexpected(&response).into()),
         }
     }
 
@@ -157,21 +265,48 @@ impl Procedures for Thread {
     async fn into_upper(self, response: Response) -> Result<Self::Output> {
         // Note: into json is ok here since StatusCode is OK
         // and any further errors will be from Parsing JSON
-        let thread_data = response.json::<DeserializedThread>().await?.posts;
-
-        Ok(Self {
-            op: thread_data.first().expect("No OP found").clone(),
-            board: self.board().to_string(),
-            replies_no: thread_data.len() - 1_usize,
-            last_reply: thread_data.last().map(Post::id),
-            all_replies: thread_data.iter().skip(1).cloned().collect(),
-            archive_time: thread_data
-                .first()
-                .map(|data| NaiveDateTime::from_timestamp(data.archived_on(), 0)),
-            archived: thread_data.first().expect("No OP found.").archived(),
-            last_update: Some(Utc::now()),
-            client: self.client.clone(),
-        })
+        let last_modified = crate::last_modified(&response);
+        let thread_data = if self.client.lock().await.lenient {
+            DeserializedThread::lenient(crate::json::<LenientThread>(response).await?)?
+        } else {
+            crate::json::<DeserializedThread>(response).await?
+        };
+
+        let mut thread =
+            Self::from_posts(&self.client, &self.board, thread_data.posts, last_modified)?;
+        thread.all_replies = self.merge(thread.all_replies);
+        thread.index = Arc::new(index(&thread.all_replies));
+        thread.skipped = thread_data.skipped;
+        thread.last_update = Some(Utc::now());
+        Ok(thread)
+    }
+}
+
+impl Thread {
+    /// Merges the fresh replies of an update into the replies of this thread.
+    ///
+    /// Deleted replies are removed and the remaining ones are replaced by their fresh
+    /// copies in place, so the positions of replies stay the same unless one was deleted.
+    /// Only the replies past the last known one are appended, reusing the allocation
+    /// of the reply list when no clone shares it.
+    fn merge(self, mut fresh: Arc<Vec<Post>>) -> Arc<Vec<Post>> {
+        let tail = self.all_replies.last().map_or(self.op.id(), Post::id);
+        let mut known = mem::take(Arc::make_mut(&mut fresh));
+        let new = known.split_off(known.partition_point(|post| post.id() <= tail));
+
+        let mut merged = self.all_replies;
+        let replies = Arc::make_mut(&mut merged);
+        replies.retain(|post| known.binary_search_by_key(&post.id(), Post::id).is_ok());
+        if replies.len() == known.len() {
+            for (post, fresh) in replies.iter_mut().zip(known) {
+                *post = fresh;
+            }
+        } else {
+            // a reply this thread never had, such as one skipped in lenient mode
+            *replies = known;
+        }
+        replies.extend(new);
+        merged
     }
 }
 
@@ -183,38 +318,302 @@ impl Thread {
     ///
     /// # Errors
     ///
-    /// This function will panic if it does not find an OP for the thread.
-    pub async fn new(client: &Dot4chClient, board: &str, post_id: u32) -> Result<Self> {
-        let thread_data = thread_deserializer(client, board, post_id).await?.posts;
-        let op = { thread_data.first().expect("NO OP FOUND").clone() };
-        let archived = op.archived();
-        let last_reply = thread_data.last().map(Post::id);
+    /// This function will return an error if the request fails
+    /// or if it does not find an OP for the thread.
+    pub async fn new(
+        client: &Dot4chClient,
+        board: &str,
+        post_id: impl Into<ThreadId>,
+    ) -> Result<Self> {
+        let post_id = post_id.into().get();
+        crate::boards::check(client, board).await?;
+        let (thread_data, last_modified) = thread_deserializer(client, board, post_id).await?;
+        let mut thread = Self::from_posts(client, board, thread_data.posts, last_modified)?;
+        thread.skipped = thread_data.skipped;
+        Ok(thread)
+    }
+
+    /// Fetches a thread from wherever it still exists.
+    ///
+    /// Tries the live API first, which also serves threads in the board's archive.
+    /// Once the thread is gone from 4chan, it is fetched from the archive registered
+    /// for the board with [`Client::add_resolver`](crate::Client::add_resolver).
+    ///
+    /// Returns the thread along with where it came from.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails,
+    /// or if the thread is gone from 4chan and no archive has it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, thread::{Source, Thread}};
+    /// # async fn new_or_archived() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let (thread, source) = Thread::new_or_archived(&client, "g", 76759434).await?;
+    /// if source != Source::Live {
+    ///     println!("/g/{} is closed to replies", thread.op().id());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn new_or_archived(
+        client: &Dot4chClient,
+        board: &str,
+        post_id: impl Into<ThreadId>,
+    ) -> Result<(Self, Source)> {
+        let post_id = post_id.into().get();
+        let err = match Self::new(client, board, post_id).await {
+            Ok(thread) if thread.archived => return Ok((thread, Source::Archive)),
+            Ok(thread) => return Ok((thread, Source::Live)),
+            Err(err) if crate::is_not_found(&err) => err,
+            Err(err) => return Err(err),
+        };
+        let resolver = client.lock().await.resolver(board);
+        let Some(resolver) = resolver else {
+            return Err(err);
+        };
+        debug!("/{board}/{post_id} is gone, trying {resolver:?}");
+        let thread = resolver.thread(client, board, post_id).await?;
+        Ok((thread, Source::External))
+    }
+
+    /// Fetches several threads of a board one after another, yielding them in the order of `ids`.
+    ///
+    /// Requests go through the client one at a time, so they respect the request cooldown.
+    /// A thread which cannot be fetched, such as one that 404'd, yields its error and
+    /// the stream continues with the next one. The error of a 404 is an
+    /// [`Error::NotFound`] carrying the thread number.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, thread::Thread};
+    /// # use futures::StreamExt;
+    /// # async fn fetch_many() {
+    /// # let client = Client::new();
+    /// let threads = Thread::fetch_many(&client, "g", vec![76759434, 76759435]);
+    /// futures::pin_mut!(threads);
+    /// while let Some(thread) = threads.next().await {
+    ///     match thread {
+    ///         Ok(thread) => println!("{} replies", thread.op().replies()),
+    ///         Err(err) => println!("skipped: {}", err),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn fetch_many<I>(
+        client: &Dot4chClient,
+        board: &str,
+        ids: I,
+    ) -> impl Stream<Item = Result<Self>>
+    where
+        I: IntoIterator,
+        I::Item: Into<ThreadId>,
+    {
+        let client = client.clone();
+        let board = board.to_string();
+        stream::iter(ids.into_iter().map(Into::into).collect::<Vec<ThreadId>>()).then(move |id| {
+            let client = client.clone();
+            let board = board.clone();
+            async move { Self::new(&client, &board, id).await }
+        })
+    }
+
+    /// Builds a [`Thread`] from all of its posts, OP first.
+    pub(crate) fn from_posts(
+        client: &Dot4chClient,
+        board: &str,
+        mut posts: Vec<Post>,
+        last_modified: Option<String>,
+    ) -> Result<Self> {
+        if posts.is_empty() {
+            return Err(anyhow::anyhow!("No OP found for thread on /{}/", board));
+        }
+        let all_replies = posts.split_off(1);
+        let op = posts.remove(0);
+        Ok(Self::assemble(
+            client,
+            board,
+            op,
+            all_replies,
+            last_modified,
+        ))
+    }
 
+    /// Builds a [`Thread`] from its OP and replies.
+    fn assemble(
+        client: &Dot4chClient,
+        board: &str,
+        op: Post,
+        all_replies: Vec<Post>,
+        last_modified: Option<String>,
+    ) -> Self {
+        let archived = op.archived();
         let archive_time = if archived {
-            let secs = thread_data.first().expect("NO OP FOUND").archived_on();
-            Some(NaiveDateTime::from_timestamp(secs, 0))
+            Some(NaiveDateTime::from_timestamp(op.archived_on(), 0))
         } else {
             None
         };
 
-        Ok(Self {
-            op,
+        Self {
             board: board.to_string(),
-            replies_no: thread_data.len() - 1_usize,
-            last_reply,
-            all_replies: thread_data.iter().skip(1).cloned().collect(),
+            replies_no: all_replies.len(),
+            last_reply: Some(all_replies.last().map_or(op.id(), Post::id)),
+            index: Arc::new(index(&all_replies)),
+            quotes: Arc::default(),
+            all_replies: Arc::new(all_replies),
+            op,
             archive_time,
             archived,
             last_update: None,
+            last_modified,
+            skipped: vec![],
             client: client.clone(),
-        })
+        }
+    }
+
+    /// Returns a builder of a thread on `board` started by `op`, such as for a test fixture.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dot4ch::{Client, post::Post, thread::Thread};
+    ///
+    /// let client = Client::new();
+    /// let reply = Post::builder(12346)
+    ///     .reply_to(12345)
+    ///     .content(r##"<a href="#p12345" class="quotelink">&gt;&gt;12345</a>"##)
+    ///     .build();
+    /// let thread = Thread::builder("g", Post::builder(12345).subject("dpt").build())
+    ///     .reply(reply)
+    ///     .build(&client);
+    /// assert_eq!(thread.op().subject(), "dpt");
+    /// assert!(thread.contains(12346));
+    /// assert_eq!(thread.quotes_of(12345).count(), 1);
+    /// ```
+    pub fn builder(board: &str, op: Post) -> ThreadBuilder {
+        ThreadBuilder {
+            board: board.to_string(),
+            op,
+            replies: vec![],
+            last_modified: None,
+        }
     }
 
     /// Find an post with an ID
     ///
     /// Returns the first element of
-    pub fn find(&self, id: u32) -> Option<&Post> {
-        self.all_replies.iter().find(|post| post.id() == id)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dot4ch::{Client, post::Post, thread::Thread};
+    ///
+    /// let client = Client::new();
+    /// let thread = Thread::builder("g", Post::builder(81730460).build())
+    ///     .reply(Post::builder(81730461).reply_to(81730460).build())
+    ///     .build(&client);
+    /// assert_eq!(thread.find(81730461).map(Post::id), Some(81730461));
+    /// assert!(thread.find(81730462).is_none());
+    /// assert!(thread.find(81730461_u64).is_some());
+    /// ```
+    pub fn find(&self, id: impl Into<PostNo>) -> Option<&Post> {
+        self.index
+            .get(&id.into())
+            .map(|&idx| &self.all_replies[idx])
+    }
+
+    /// Returns true if the OP or a reply has the post number `no`.
+    pub fn contains(&self, no: impl Into<PostNo>) -> bool {
+        let no = no.into();
+        u64::from(self.op.id()) == no.get() || self.find(no).is_some()
+    }
+
+    /// Returns the posts quoting each post number, by position OP first.
+    ///
+    /// Built from the comments on first use and shared between clones until the next update.
+    pub(crate) fn quote_index(&self) -> &HashMap<u32, Vec<usize>> {
+        self.quotes.get_or_init(|| {
+            let id = self.op.id();
+            let mut index: HashMap<u32, Vec<usize>> = HashMap::default();
+            for (pos, post) in self.posts().enumerate() {
+                let mut targets: Vec<_> = post
+                    .links()
+                    .into_iter()
+                    .filter_map(|link| match link {
+                        Link::Quote { post, thread } if thread.is_none_or(|t| t == id) => {
+                            Some(post)
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                targets.sort_unstable();
+                targets.dedup();
+                for target in targets {
+                    index.entry(target).or_default().push(pos);
+                }
+            }
+            index
+        })
+    }
+
+    /// Returns the post at `idx` counting the OP as 0.
+    pub(crate) fn post_at(&self, idx: usize) -> Option<&Post> {
+        match idx {
+            0 => Some(&self.op),
+            idx => self.all_replies.get(idx - 1),
+        }
+    }
+
+    /// Returns the number of the thread, which is the number of its OP.
+    pub fn id(&self) -> ThreadId {
+        ThreadId(self.op.id())
+    }
+
+    /// Returns the posts which were skipped because they could not be parsed.
+    ///
+    /// Always empty unless the client is in lenient mode,
+    /// see [`Client::set_lenient`](crate::Client::set_lenient).
+    pub fn skipped(&self) -> &[SkippedPost] {
+        &self.skipped
+    }
+
+    /// Returns an estimate of the memory used by the posts of the thread, in bytes.
+    ///
+    /// See [`Post::approx_size`].
+    pub fn approx_size(&self) -> usize {
+        self.op.approx_size()
+            + self
+                .all_replies
+                .iter()
+                .map(Post::approx_size)
+                .sum::<usize>()
+    }
+
+    /// Returns the time until the thread may be updated again, `None` if it may be updated now.
+    pub fn cooldown(&self) -> Option<std::time::Duration> {
+        self.last_update.and_then(crate::cooldown)
+    }
+
+    /// Updates the thread in place without waiting out the cooldown.
+    ///
+    /// Returns whether the thread changed since the last update.
+    /// Schedulers can sleep for the `remaining` time of the error and try again.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an [`Error::Cooldown`] if the thread was updated
+    /// less than 10 seconds ago, or any error of [`Update::update`].
+    pub async fn try_update(&mut self) -> Result<UpdateStatus> {
+        if let Some(remaining) = self.cooldown() {
+            return Err(Error::Cooldown { remaining }.into());
+        }
+        let (thread, status) = self.clone().updated().await?;
+        *self = thread;
+        Ok(status)
     }
 
     /// Updates the time when the last GET was performed
@@ -237,11 +636,95 @@ impl Thread {
         self.all_replies.get(idx)
     }
 
+    /// Returns an iterator over every post of the thread, OP first.
+    pub fn posts(&self) -> impl Iterator<Item = &Post> {
+        iter::once(&self.op).chain(self.all_replies.iter())
+    }
+
     /// Return the last post from a thread
     pub fn last_post(&self) -> Option<&Post> {
         self.all_replies.last()
     }
 
+    /// Returns every post made under a poster ID, OP first.
+    ///
+    /// Always empty on boards without poster IDs, see [`Post::poster_id`].
+    pub fn posts_by_id<'a>(&'a self, poster_id: &'a str) -> impl Iterator<Item = &'a Post> {
+        self.posts()
+            .filter(move |post| post.poster_id() == Some(poster_id))
+    }
+
+    /// Returns every post made with a tripcode, such as `!Ep8pui8Vw2`, OP first.
+    pub fn posts_by_tripcode<'a>(&'a self, tripcode: &'a str) -> impl Iterator<Item = &'a Post> {
+        self.posts()
+            .filter(move |post| post.tripcode() == Some(tripcode))
+    }
+
+    /// Returns every post made with a [`Capcode`], OP first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, post::Capcode, thread::Thread};
+    /// # async fn staff() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let thread = Thread::new(&client, "g", 76759434).await?;
+    /// for post in thread.posts_by_capcode(Capcode::Mod) {
+    ///     println!("a moderator posted >>{}", post.id());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn posts_by_capcode(&self, capcode: Capcode) -> impl Iterator<Item = &Post> {
+        self.posts()
+            .filter(move |post| post.capcode() == Some(capcode.as_str()))
+    }
+
+    /// Returns every post made with any capcode, OP first.
+    pub fn staff_posts(&self) -> impl Iterator<Item = &Post> {
+        self.posts().filter(|post| post.capcode().is_some())
+    }
+
+    /// Returns every poster ID seen in the thread, sorted.
+    ///
+    /// Always empty on boards without poster IDs, see [`Post::poster_id`].
+    pub fn ids(&self) -> BTreeSet<&str> {
+        self.posts().filter_map(Post::poster_id).collect()
+    }
+
+    /// Returns the title of the thread the way the site labels it.
+    ///
+    /// This is the subject of the OP, or the first line of its comment as plain text,
+    /// cut to `max_chars` characters with `...` appended, when there is no subject.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, thread::Thread};
+    /// # async fn title() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let thread = Thread::new(&client, "g", 76759434).await?;
+    /// println!("{}", thread.title(50));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn title(&self, max_chars: usize) -> String {
+        let subject = self.op.subject();
+        if !subject.is_empty() {
+            return text::decode_entities(subject);
+        }
+        let text = self.op.plain_text();
+        let line = text
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("");
+        let line = line.trim();
+        match line.char_indices().nth(max_chars) {
+            Some((end, _)) => format!("{}...", line[..end].trim_end()),
+            None => line.to_string(),
+        }
+    }
+
     /// Return the name of the board
     pub fn board(&self) -> &str {
         &self.board
@@ -256,19 +739,72 @@ impl Thread {
         )
     }
 
+    /// Returns the link to the thread on the site, to share with people.
+    ///
+    /// Includes the SEO slug of the OP when 4chan sent one,
+    /// such as `https://boards.4chan.org/g/thread/76759434/this-board-is-for-the-discussion-of-technology`.
+    pub fn web_url(&self) -> String {
+        let url = format!(
+            "https://boards.4chan.org/{}/thread/{}",
+            self.board,
+            self.op.id()
+        );
+        match self.op.semantic_url() {
+            Some(slug) => format!("{url}/{slug}"),
+            None => url,
+        }
+    }
+
     /// Convert one [`Thread`] to a [`Board`]
     pub fn into_board(self) -> Board {
-        let mut hash = HashMap::new();
-        let num = &self.op.id();
-        let client = self.client.clone();
-        let board = self.board.to_string();
-        hash.insert(*num, self);
-        Board {
-            threads: hash,
-            board,
-            client,
+        let mut board = Board::empty(&self.client, &self.board);
+        board.insert(self.op.id(), self);
+        board
+    }
+}
+
+impl Persist for Thread {
+    fn save<P: AsRef<Path>>(&self, path: P, format: Format) -> Result<()> {
+        write(&self.snapshot(), path, format)
+    }
+
+    fn load<P: AsRef<Path>>(client: &Dot4chClient, path: P, format: Format) -> Result<Self> {
+        Self::from_snapshot(client, read(path, format)?)
+    }
+}
+
+impl Thread {
+    /// Returns a snapshot of all posts, OP first, with the metadata needed to update them.
+    pub(crate) fn snapshot(&self) -> Snapshot<Vec<&Post>> {
+        Snapshot {
+            metadata: Metadata::new(
+                self.thread_url(),
+                &self.board,
+                self.last_modified.as_deref(),
+            ),
+            data: iter::once(&self.op)
+                .chain(self.all_replies.iter())
+                .collect(),
         }
     }
+
+    /// Rebuilds a [`Thread`] from a snapshot.
+    pub(crate) fn from_snapshot(
+        client: &Dot4chClient,
+        snapshot: Snapshot<Vec<Post>>,
+    ) -> Result<Self> {
+        let Snapshot { metadata, data } = snapshot;
+        Self::from_posts(client, &metadata.board, data, metadata.last_modified)
+    }
+}
+
+/// Maps the number of every reply to its position.
+fn index(replies: &[Post]) -> HashMap<PostNo, usize> {
+    replies
+        .iter()
+        .enumerate()
+        .map(|(idx, post)| (PostNo::from(post.id()), idx))
+        .collect()
 }
 
 impl<Idx> Index<Idx> for Thread
@@ -291,12 +827,37 @@ where
 struct DeserializedThread {
     /// A vector of posts. Used internally.
     posts: Vec<Post>,
+    /// The posts which were skipped in lenient mode
+    #[serde(skip)]
+    skipped: Vec<SkippedPost>,
+}
+
+impl DeserializedThread {
+    /// Parses the posts of a thread one by one, skipping the malformed ones.
+    ///
+    /// Fails if the OP is malformed.
+    fn lenient(thread: LenientThread) -> Result<Self> {
+        let (posts, skipped) = parse_each(thread.posts);
+        if let Some(op) = skipped.iter().find(|post| post.index == 0) {
+            return Err(anyhow::anyhow!("The OP is malformed: {}", op.reason));
+        }
+        Ok(Self { posts, skipped })
+    }
+}
+
+/// A thread whose posts are not parsed yet.
+#[derive(Debug, Deserialize)]
+struct LenientThread {
+    /// The unparsed posts, OP first
+    posts: Vec<Value>,
 }
 
 /// Converts 4chan thread JSON to [`DeserializedThread`].
 ///
 /// This is a helper function to `from_deserialized()`
 ///
+/// Also returns the `Last-Modified` header of the response.
+///
 /// # Errors
 ///
 /// Returns an error if the given thread is not found
@@ -304,13 +865,20 @@ async fn thread_deserializer(
     client: &Dot4chClient,
     board: &str,
     post_num: u32,
-) -> Result<DeserializedThread> {
+) -> Result<(DeserializedThread, Option<String>)> {
     let rq = format!("https://a.4cdn.org/{}/thread/{}.json", board, post_num);
-    let req = client.lock().await.get(&rq).await?;
-
-    req.error_for_status_ref().map_err(anyhow::Error::from)?;
-
-    let req = req.json::<DeserializedThread>().await?;
-    debug!("Deserialized Post: {}", post_num);
-    Ok(req)
+    let operation = format!("fetching thread /{board}/{post_num}");
+    let lenient = client.lock().await.lenient;
+    let (req, last_modified) = if lenient {
+        let (thread, last_modified) =
+            crate::fetch_json::<LenientThread>(client, &rq, board, Some(post_num), &operation)
+                .await?;
+        let thread = DeserializedThread::lenient(thread).context(operation)?;
+        (thread, last_modified)
+    } else {
+        crate::fetch_json::<DeserializedThread>(client, &rq, board, Some(post_num), &operation)
+            .await?
+    };
+    debug!("Deserialized Post: {post_num}");
+    Ok((req, last_modified))
 }
@@ -0,0 +1,275 @@
+//! A thread whose posts are parsed on access.
+
+use super::Thread;
+use crate::{
+    logging::debug, post::Post, Dot4chClient, Error, IfModifiedSince, PostNo, Procedures, ThreadId,
+    Updatable, Update, UpdateStatus,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{Response, StatusCode};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+/// A thread which keeps its posts as raw JSON and parses a post only when it is accessed.
+///
+/// Polling a thread of hundreds of posts to look at its OP or a handful of replies
+/// then costs a fraction of parsing every post into a [`Thread`].
+///
+/// # Example
+///
+/// ```
+/// # use dot4ch::{Client, Update, thread::LazyThread};
+/// # async fn lazy() -> anyhow::Result<()> {
+/// # let client = Client::new();
+/// let mut thread = LazyThread::new(&client, "g", 76759434).await?;
+/// loop {
+///     println!("{} posts, closed: {}", thread.len(), thread.op()?.closed());
+///     thread = thread.update().await?;
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LazyThread {
+    /// The board of the thread
+    board: String,
+    /// The number of the thread
+    id: u32,
+    /// Every post as the API sent it, OP first
+    posts: Vec<Box<RawValue>>,
+    /// The time when the thread was fetched
+    last_update: DateTime<Utc>,
+    /// The `Last-Modified` header of the last response
+    last_modified: Option<String>,
+    /// the client
+    client: Dot4chClient,
+}
+
+/// The JSON of a thread with unparsed posts.
+#[derive(Deserialize)]
+struct RawThread {
+    /// Every post, OP first
+    posts: Vec<Box<RawValue>>,
+}
+
+/// The number of a post, the only field read to find a post.
+#[derive(Deserialize)]
+struct PostNumber {
+    /// The numeric post ID
+    no: u64,
+}
+
+impl LazyThread {
+    /// Fetches a thread without parsing its posts.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails
+    /// or if the thread has no posts.
+    pub async fn new(
+        client: &Dot4chClient,
+        board: &str,
+        id: impl Into<ThreadId>,
+    ) -> crate::Result<Self> {
+        let id = id.into().get();
+        crate::boards::check(client, board).await?;
+        let url = format!("https://a.4cdn.org/{board}/thread/{id}.json");
+        let operation = format!("fetching thread /{board}/{id}");
+        let (thread, last_modified) =
+            crate::fetch_json::<RawThread>(client, &url, board, Some(id), &operation).await?;
+        if thread.posts.is_empty() {
+            return Err(anyhow::anyhow!("No OP found for thread on /{}/", board));
+        }
+        Ok(Self {
+            board: board.to_string(),
+            id,
+            posts: thread.posts,
+            last_update: Utc::now(),
+            last_modified,
+            client: client.clone(),
+        })
+    }
+
+    /// Parses the original post of the thread.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the OP cannot be parsed.
+    pub fn op(&self) -> crate::Result<Post> {
+        parse(&self.posts[0])
+    }
+
+    /// Parses the post at `idx`, counting the OP as 0.
+    ///
+    /// Returns `None` if there is no such post.
+    pub fn get(&self, idx: usize) -> Option<crate::Result<Post>> {
+        self.posts.get(idx).map(|post| parse(post))
+    }
+
+    /// Finds a post by its number, parsing only that post.
+    pub fn find(&self, no: impl Into<PostNo>) -> Option<crate::Result<Post>> {
+        let no = no.into().get();
+        self.posts
+            .iter()
+            .find(|post| {
+                serde_json::from_str::<PostNumber>(post.get()).is_ok_and(|post| post.no == no)
+            })
+            .map(|post| parse(post))
+    }
+
+    /// Returns the JSON of the post at `idx` as the API sent it.
+    pub fn raw(&self, idx: usize) -> Option<&str> {
+        self.posts.get(idx).map(|post| post.get())
+    }
+
+    /// Returns an iterator parsing every post of the thread, OP first.
+    pub fn posts(&self) -> impl Iterator<Item = crate::Result<Post>> + '_ {
+        self.posts.iter().map(|post| parse(post))
+    }
+
+    /// Returns the number of posts, including the OP.
+    pub fn len(&self) -> usize {
+        self.posts.len()
+    }
+
+    /// Returns true if the thread has no posts, which is never the case for a fetched thread.
+    pub fn is_empty(&self) -> bool {
+        self.posts.is_empty()
+    }
+
+    /// Returns the name of the board.
+    pub fn board(&self) -> &str {
+        &self.board
+    }
+
+    /// Returns the number of the thread.
+    pub fn id(&self) -> ThreadId {
+        ThreadId(self.id)
+    }
+
+    /// Returns the API URL of the thread.
+    pub fn thread_url(&self) -> String {
+        format!("https://a.4cdn.org/{}/thread/{}.json", self.board, self.id)
+    }
+
+    /// Replaces the posts with those of a response, `None` if they did not change.
+    ///
+    /// Fails without touching the posts if the response has none, as the OP is always expected.
+    async fn apply(&mut self, response: Option<Response>) -> crate::Result<UpdateStatus> {
+        let status = if response.is_some() {
+            UpdateStatus::Modified
+        } else {
+            UpdateStatus::NotModified
+        };
+        if let Some(response) = response {
+            let last_modified = crate::last_modified(&response);
+            let posts = crate::json::<RawThread>(response).await?.posts;
+            if posts.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "No OP found for thread on /{}/",
+                    self.board
+                ));
+            }
+            self.posts = posts;
+            self.last_modified = last_modified;
+        }
+        self.last_update = Utc::now();
+        Ok(status)
+    }
+
+    /// Fetches the thread if it changed since the last update.
+    async fn refresh(&mut self) -> crate::Result<UpdateStatus> {
+        let url = self.thread_url();
+        let context = format!("updating thread /{}/{} ({url})", self.board, self.id);
+        let response = crate::refresh(
+            &self.client,
+            &url,
+            &self.board,
+            Some(self.id),
+            self.last_modified.as_deref(),
+        )
+        .await
+        .with_context(|| context.clone())?;
+        self.apply(response).await.context(context)
+    }
+
+    /// Parses every post into a [`Thread`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any post cannot be parsed.
+    pub fn into_thread(self) -> crate::Result<Thread> {
+        let posts = self.posts().collect::<crate::Result<Vec<_>>>()?;
+        Thread::from_posts(&self.client, &self.board, posts, self.last_modified)
+    }
+}
+
+/// Parses a single post.
+fn parse(post: &RawValue) -> crate::Result<Post> {
+    Ok(serde_json::from_str(post.get())?)
+}
+
+#[async_trait(?Send)]
+impl Update for LazyThread {
+    type Output = Self;
+    /// Returns the updated thread, still unparsed.
+    ///
+    /// Respects 4chan's 10 seconds between each thread update.
+    async fn update(mut self) -> crate::Result<Self> {
+        self.refresh_time().await?;
+        self.refresh().await?;
+        Ok(self)
+    }
+}
+
+#[async_trait(?Send)]
+impl Updatable for LazyThread {
+    async fn update(&mut self, client: &Dot4chClient) -> crate::Result<UpdateStatus> {
+        if let Some(remaining) = crate::cooldown(self.last_update) {
+            return Err(Error::Cooldown { remaining }.into());
+        }
+        self.client = client.clone();
+        self.refresh().await
+    }
+}
+
+#[async_trait(?Send)]
+impl Procedures for LazyThread {
+    type Output = Self;
+
+    async fn refresh_time(&mut self) -> crate::Result<()> {
+        if let Some(remaining) = crate::cooldown(self.last_update) {
+            debug!(
+                "Updating LazyThread too quickly! Waiting for {} seconds",
+                remaining.as_secs_f32()
+            );
+            crate::sleep(&self.client, remaining).await;
+        }
+        Ok(())
+    }
+
+    async fn fetch_status(mut self, response: Response) -> crate::Result<Self::Output> {
+        let response = crate::check_in(response, &self.board, Some(self.id)).await?;
+        match response.status() {
+            StatusCode::OK => self.into_upper(response).await,
+            StatusCode::NOT_MODIFIED => {
+                self.apply(None).await?;
+                Ok(self)
+            }
+            _ => Err(Error::unexpected(&response).into()),
+        }
+    }
+
+    async fn into_upper(mut self, response: Response) -> crate::Result<Self::Output> {
+        self.apply(Some(response)).await?;
+        Ok(self)
+    }
+}
+
+#[async_trait(?Send)]
+impl IfModifiedSince for LazyThread {
+    async fn fetch(client: &Dot4chClient, url: &str, header: &str) -> Result<Response, Error> {
+        crate::get_since(client, url, header).await
+    }
+}
@@ -14,19 +14,31 @@
 //! - A UNIX timestamp marking the last time the thread was modified
 //! - The number of replies a thread has
 //!
+//! [`Catalog::full`] reads `catalog.json` instead, which also tells whether a thread is
+//! pinned, closed or archived.
+//!
 
-use crate::{header, thread::Thread, Dot4chClient, IfModifiedSince, Procedures, Update};
+use crate::{
+    logging::debug,
+    persist::{read, write, Format, Metadata, Persist, Snapshot},
+    post::{parse_each, SkippedPost},
+    thread::Thread,
+    Dot4chClient, Error, IfModifiedSince, Procedures, Updatable, Update, UpdateStatus,
+};
+use anyhow::Context;
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, NaiveDateTime, Utc};
-use log::debug;
-use reqwest::{header::IF_MODIFIED_SINCE, Response, StatusCode};
+use futures::{Stream, StreamExt};
+use reqwest::{Response, StatusCode};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::{
+    collections::HashMap,
     fmt::{self, Display, Formatter},
     ops::Index,
+    path::Path,
     slice::SliceIndex,
 };
-use tokio::time;
 
 /// A summarized list of all threads on a board including
 /// thread numbers, their modification time and reply count.
@@ -46,14 +58,22 @@ use tokio::time;
 /// ```
 ///
 /// to get all threads from catalog
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Catalog {
     /// The board of the catalog
     board: String,
+    /// The file the catalog is read from, `threads` or `catalog`
+    endpoint: &'static str,
     /// The pages of the catalog which contain threads
     threads: Vec<Page>,
     /// The time when catalog was accessed
     last_accessed: DateTime<Utc>,
+    /// The `Last-Modified` header of the last response
+    last_modified: Option<String>,
+    /// The reply count of every thread before the last update and when it was taken
+    previous: Option<(DateTime<Utc>, HashMap<u32, u32>)>,
+    /// The threads which were skipped in lenient mode
+    skipped: Vec<SkippedPost>,
     /// client
     client: Dot4chClient,
 }
@@ -70,6 +90,32 @@ pub struct Page {
     threads: Vec<CatalogThread>,
 }
 
+/// A page whose threads are not parsed yet.
+#[derive(Debug, Deserialize)]
+struct LenientPage {
+    /// The page number
+    page: u8,
+    /// The unparsed threads
+    threads: Vec<Value>,
+}
+
+/// Parses the threads of every page one by one, skipping the malformed ones.
+fn lenient_pages(pages: Vec<LenientPage>) -> (Vec<Page>, Vec<SkippedPost>) {
+    let mut skipped = vec![];
+    let pages = pages
+        .into_iter()
+        .map(|page| {
+            let (threads, mut malformed) = parse_each(page.threads);
+            skipped.append(&mut malformed);
+            Page {
+                page: page.page,
+                threads,
+            }
+        })
+        .collect();
+    (pages, skipped)
+}
+
 impl Page {
     /// Returns the threads in the catalog.
     pub fn threads(self) -> Vec<CatalogThread> {
@@ -77,7 +123,7 @@ impl Page {
     }
 
     /// Returns the page number of a page.
-    pub fn num(self) -> u8 {
+    pub fn num(&self) -> u8 {
         self.page
     }
 }
@@ -119,24 +165,22 @@ impl Display for Page {
     }
 }
 
+#[async_trait(?Send)]
+impl Updatable for Catalog {
+    async fn update(&mut self, client: &Dot4chClient) -> crate::Result<UpdateStatus> {
+        self.client = client.clone();
+        self.try_update().await
+    }
+}
+
 #[async_trait(?Send)]
 impl Update for Catalog {
     type Output = Self;
     /// Returns an updated catalog.
     async fn update(mut self) -> crate::Result<Self> {
         self.refresh_time().await?;
-
-        let updated_catalog = {
-            let header = header(&self.client).await;
-            let get_url = format!("https://a.4cdn.org/{}/threads.json", &self.board);
-            let response = Self::fetch(&self.client, &get_url, &header).await?;
-
-            self.client.lock().await.last_checked = Utc::now();
-
-            self.fetch_status(response).await?
-        };
-
-        Ok(updated_catalog)
+        self.refresh().await?;
+        Ok(self)
     }
 }
 
@@ -158,7 +202,7 @@ impl Procedures for Catalog {
                 (10000_f32 - curr.num_milliseconds() as f32) / 1000_f32
             );
             match Duration::seconds(10).checked_sub(&curr) {
-                Some(time) => time::sleep(time.to_std()?).await,
+                Some(time) => crate::sleep(&self.client, time.to_std()?).await,
                 None => return Err(anyhow::anyhow!("Overflow in subtraction of `Duration`")),
             }
         }
@@ -167,50 +211,28 @@ impl Procedures for Catalog {
 
     /// Updates the status of a `Response` and generates a new Catalog if needed.
     async fn fetch_status(mut self, response: Response) -> crate::Result<Self::Output> {
+        let response = crate::check_in(response, &self.board, None).await?;
         Ok(match response.status() {
             StatusCode::OK => self.into_upper(response).await?,
             StatusCode::NOT_MODIFIED => {
-                self.last_accessed = Utc::now();
+                self.apply(None).await?;
                 self
             }
-            other => {
-                return Err(anyhow::anyhow!(
-                    "Unexpected StatusCode on Catalog Update: {}",
-                    other
-                ))
-            }
+            _ => return Err(Error::unexpected(&response).into()),
         })
     }
 
     /// Converts the `Response` into a `Catalog`
-    async fn into_upper(self, response: Response) -> crate::Result<Self::Output> {
-        let threads = response.json::<Vec<Page>>().await?;
-        let last_accessed = Utc::now();
-        Ok(Self {
-            threads,
-            last_accessed,
-            board: self.board.to_string(),
-            client: self.client.clone(),
-        })
+    async fn into_upper(mut self, response: Response) -> crate::Result<Self::Output> {
+        self.apply(Some(response)).await?;
+        Ok(self)
     }
 }
 
 #[async_trait(?Send)]
 impl IfModifiedSince for Catalog {
-    async fn fetch(
-        client: &Dot4chClient,
-        url: &str,
-        header: &str,
-    ) -> Result<Response, reqwest::Error> {
-        let response = client
-            .lock()
-            .await
-            .req_client()
-            .get(url)
-            .header(IF_MODIFIED_SINCE, header)
-            .send()
-            .await;
-        response
+    async fn fetch(client: &Dot4chClient, url: &str, header: &str) -> Result<Response, Error> {
+        crate::get_since(client, url, header).await
     }
 }
 
@@ -221,28 +243,120 @@ impl Catalog {
     ///
     /// This function will return an error if the board isn't valid
     pub async fn new(client: &Dot4chClient, board: &str) -> crate::Result<Self> {
-        let url = format!("https://a.4cdn.org/{}/threads.json", board);
-        let threads = client.lock().await.get(&url).await?;
+        Self::fetch(client, board, "threads").await
+    }
 
-        threads
-            .error_for_status_ref()
-            .map_err(anyhow::Error::from)?;
+    /// Returns the catalog of a board from `catalog.json`.
+    ///
+    /// It is larger than `threads.json`, but tells which threads are pinned, closed or archived,
+    /// see [`Catalog::stickies`], [`Catalog::closed`] and [`Catalog::archived`].
+    /// Updates keep reading `catalog.json`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{catalog::Catalog, Client};
+    /// # async fn full() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let catalog = Catalog::full(&client, "g").await?;
+    /// let pinned: Vec<_> = catalog.stickies().map(|thread| thread.id()).collect();
+    /// let open = catalog.threads().filter(|thread| !thread.sticky() && !thread.closed());
+    /// println!("{} pinned, {} open", pinned.len(), open.count());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the board isn't valid
+    pub async fn full(client: &Dot4chClient, board: &str) -> crate::Result<Self> {
+        Self::fetch(client, board, "catalog").await
+    }
 
-        let threads = threads.json::<Vec<Page>>().await?;
+    /// Fetches the catalog of a board from `threads.json` or `catalog.json`.
+    async fn fetch(
+        client: &Dot4chClient,
+        board: &str,
+        endpoint: &'static str,
+    ) -> crate::Result<Self> {
+        crate::boards::check(client, board).await?;
+        let url = format!("https://a.4cdn.org/{}/{}.json", board, endpoint);
+        let operation = format!("fetching the catalog of /{board}/");
+        let lenient = client.lock().await.lenient;
+        let (threads, skipped, last_modified) = if lenient {
+            let (pages, last_modified) =
+                crate::fetch_json::<Vec<LenientPage>>(client, &url, board, None, &operation)
+                    .await?;
+            let (threads, skipped) = lenient_pages(pages);
+            (threads, skipped, last_modified)
+        } else {
+            let (threads, last_modified) =
+                crate::fetch_json::<Vec<Page>>(client, &url, board, None, &operation).await?;
+            (threads, vec![], last_modified)
+        };
 
         Ok(Self {
             threads,
             last_accessed: Utc::now(),
+            last_modified,
+            previous: None,
+            skipped,
             board: board.to_string(),
+            endpoint,
             client: client.clone(),
         })
     }
 
+    /// Streams the pages of the catalog of a board, first page first.
+    ///
+    /// Every page is parsed as soon as its bytes arrive, so the first page is there
+    /// before the rest of the body is downloaded and the body is never held in memory as a whole.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{Client, catalog::Catalog};
+    /// # use futures::StreamExt;
+    /// # async fn stream_pages() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let pages = Catalog::stream_pages(&client, "g").await?;
+    /// futures::pin_mut!(pages);
+    /// while let Some(page) = pages.next().await {
+    ///     println!("{}", page?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    /// The stream yields an error if a page cannot be parsed or the body is cut off.
+    pub async fn stream_pages(
+        client: &Dot4chClient,
+        board: &str,
+    ) -> crate::Result<impl Stream<Item = crate::Result<Page>>> {
+        crate::boards::check(client, board).await?;
+        let url = format!("https://a.4cdn.org/{board}/threads.json");
+        let context = format!("streaming the catalog of /{board}/ ({url})");
+        let fetch = async {
+            let response = client.lock().await.get(&url).await?;
+            Ok::<_, anyhow::Error>(crate::check_in(response, board, None).await?)
+        };
+        let response = fetch.await.with_context(|| context.clone())?;
+        Ok(crate::stream_array(response).map(move |page| page.with_context(|| context.clone())))
+    }
+
     /// Updates the last accessed time to be the current time.
     pub fn update_time(mut self) {
         self.last_accessed = Utc::now();
     }
 
+    /// Returns the time when the catalog was last fetched.
+    pub fn last_accessed(&self) -> DateTime<Utc> {
+        self.last_accessed
+    }
+
     /// Returns a reference to the Page if it exists. None otherwise
     pub fn page(&self, index: usize) -> Option<&Page> {
         self.threads.get(index)
@@ -252,6 +366,213 @@ impl Catalog {
     pub fn all_pages(self) -> Vec<Page> {
         self.threads
     }
+
+    /// Returns an iterator over the threads of every page, in catalog order.
+    pub fn threads(&self) -> impl Iterator<Item = &CatalogThread> {
+        self.threads.iter().flat_map(|page| page.threads.iter())
+    }
+
+    /// Returns the pinned threads, such as the rules and the board's FAQ.
+    ///
+    /// Always empty unless the catalog was fetched with [`Catalog::full`].
+    pub fn stickies(&self) -> impl Iterator<Item = &CatalogThread> {
+        self.threads().filter(|thread| thread.sticky())
+    }
+
+    /// Returns the threads which are closed to replies.
+    ///
+    /// Always empty unless the catalog was fetched with [`Catalog::full`].
+    pub fn closed(&self) -> impl Iterator<Item = &CatalogThread> {
+        self.threads().filter(|thread| thread.closed())
+    }
+
+    /// Returns the threads which were archived but are still listed.
+    ///
+    /// Always empty unless the catalog was fetched with [`Catalog::full`].
+    pub fn archived(&self) -> impl Iterator<Item = &CatalogThread> {
+        self.threads().filter(|thread| thread.archived())
+    }
+
+    /// Returns the threads which were skipped because they could not be parsed.
+    ///
+    /// Always empty unless the client is in lenient mode,
+    /// see [`Client::set_lenient`](crate::Client::set_lenient).
+    pub fn skipped(&self) -> &[SkippedPost] {
+        &self.skipped
+    }
+
+    /// Returns the time until the catalog may be updated again, `None` if it may be updated now.
+    pub fn cooldown(&self) -> Option<std::time::Duration> {
+        crate::cooldown(self.last_accessed)
+    }
+
+    /// Updates the catalog in place without waiting out the cooldown.
+    ///
+    /// Returns whether the catalog changed since the last update.
+    /// Schedulers can sleep for the `remaining` time of the error and try again.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an [`Error::Cooldown`] if the catalog was updated
+    /// less than 10 seconds ago, or any error of [`Update::update`].
+    pub async fn try_update(&mut self) -> crate::Result<UpdateStatus> {
+        if let Some(remaining) = self.cooldown() {
+            return Err(Error::Cooldown { remaining }.into());
+        }
+        self.refresh().await
+    }
+
+    /// Fetches the catalog if it changed since the last update.
+    async fn refresh(&mut self) -> crate::Result<UpdateStatus> {
+        let url = self.url();
+        let context = format!("updating the catalog of /{}/ ({url})", self.board);
+        let response = crate::refresh(
+            &self.client,
+            &url,
+            &self.board,
+            None,
+            self.last_modified.as_deref(),
+        )
+        .await
+        .with_context(|| context.clone())?;
+        self.apply(response).await.context(context)
+    }
+
+    /// Replaces the pages with those of a response, `None` if they did not change.
+    ///
+    /// The reply counts of the replaced pages are kept for [`Catalog::velocity`].
+    async fn apply(&mut self, response: Option<Response>) -> crate::Result<UpdateStatus> {
+        let status = if response.is_some() {
+            UpdateStatus::Modified
+        } else {
+            UpdateStatus::NotModified
+        };
+        if let Some(response) = response {
+            let last_modified = crate::last_modified(&response);
+            let (threads, skipped) = if self.client.lock().await.lenient {
+                lenient_pages(crate::json::<Vec<LenientPage>>(response).await?)
+            } else {
+                (crate::json::<Vec<Page>>(response).await?, vec![])
+            };
+            self.previous = Some((self.last_accessed, self.reply_counts()));
+            self.threads = threads;
+            self.skipped = skipped;
+            self.last_modified = last_modified;
+        }
+        self.last_accessed = Utc::now();
+        Ok(status)
+    }
+
+    /// Returns the board of the catalog.
+    pub fn board(&self) -> &str {
+        &self.board
+    }
+
+    /// Returns the API URL of the catalog.
+    pub fn url(&self) -> String {
+        format!("https://a.4cdn.org/{}/{}.json", self.board, self.endpoint)
+    }
+
+    /// Returns the posts per minute of every thread since `older` was fetched,
+    /// fastest first.
+    ///
+    /// Threads which are new since `older` count all of their replies.
+    /// Returns nothing if `older` is not older than this catalog.
+    pub fn velocity(&self, older: &Self) -> Vec<(u32, f64)> {
+        self.velocity_since(older.last_accessed, &older.reply_counts())
+    }
+
+    /// Returns the `n` threads with the most posts per minute, fastest first.
+    ///
+    /// The rate is measured between the last two fetches of the catalog,
+    /// so this returns nothing until the catalog was updated at least once.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dot4ch::{catalog::Catalog, Client, Update};
+    /// # async fn hottest() -> anyhow::Result<()> {
+    /// # let client = Client::new();
+    /// let catalog = Catalog::new(&client, "g").await?;
+    /// // a few minutes later
+    /// let catalog = catalog.update().await?;
+    ///
+    /// for (thread, rate) in catalog.hottest(5) {
+    ///     println!("{}: {:.1} posts/min", thread, rate);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn hottest(&self, n: usize) -> Vec<(u32, f64)> {
+        let Some((since, counts)) = &self.previous else {
+            return vec![];
+        };
+        let mut velocity = self.velocity_since(*since, counts);
+        velocity.truncate(n);
+        velocity
+    }
+
+    /// Returns the reply count of every thread.
+    fn reply_counts(&self) -> HashMap<u32, u32> {
+        self.threads()
+            .map(|thread| (thread.id(), thread.replies()))
+            .collect()
+    }
+
+    /// Returns the posts per minute of every thread since the given reply counts, fastest first.
+    fn velocity_since(&self, since: DateTime<Utc>, counts: &HashMap<u32, u32>) -> Vec<(u32, f64)> {
+        let minutes = self
+            .last_accessed
+            .signed_duration_since(since)
+            .num_milliseconds() as f64
+            / 60_000_f64;
+        if minutes <= 0_f64 {
+            return vec![];
+        }
+        let mut velocity: Vec<_> = self
+            .threads()
+            .map(|thread| {
+                let old = counts.get(&thread.id()).copied().unwrap_or(0);
+                let posts = thread.replies().saturating_sub(old);
+                (thread.id(), f64::from(posts) / minutes)
+            })
+            .collect();
+        velocity.sort_by(|a, b| b.1.total_cmp(&a.1));
+        velocity
+    }
+}
+
+impl Persist for Catalog {
+    fn save<P: AsRef<Path>>(&self, path: P, format: Format) -> crate::Result<()> {
+        let metadata = Metadata::new(self.url(), &self.board, self.last_modified.as_deref());
+        write(
+            &Snapshot {
+                metadata,
+                data: &self.threads,
+            },
+            path,
+            format,
+        )
+    }
+
+    fn load<P: AsRef<Path>>(client: &Dot4chClient, path: P, format: Format) -> crate::Result<Self> {
+        let snapshot: Snapshot<Vec<Page>> = read(path, format)?;
+        let endpoint = if snapshot.metadata.url.ends_with("/catalog.json") {
+            "catalog"
+        } else {
+            "threads"
+        };
+        Ok(Self {
+            board: snapshot.metadata.board,
+            endpoint,
+            threads: snapshot.data,
+            last_accessed: snapshot.metadata.saved_at,
+            last_modified: snapshot.metadata.last_modified,
+            previous: None,
+            skipped: vec![],
+            client: client.clone(),
+        })
+    }
 }
 
 /// Contains some metadata about the thread.
@@ -266,6 +587,15 @@ pub struct CatalogThread {
     last_modified: i64,
     /// A numeric count of the number of replies in the thread
     replies: u32,
+    /// If the thread is pinned, only in `catalog.json`
+    #[serde(default)]
+    sticky: u8,
+    /// If the thread is closed to replies, only in `catalog.json`
+    #[serde(default)]
+    closed: u8,
+    /// If the thread is archived, only in `catalog.json`
+    #[serde(default)]
+    archived: u8,
 }
 
 impl CatalogThread {
@@ -284,6 +614,27 @@ impl CatalogThread {
         self.replies
     }
 
+    /// Returns true if the thread is pinned.
+    ///
+    /// Always false unless the catalog was fetched with [`Catalog::full`].
+    pub fn sticky(&self) -> bool {
+        self.sticky != 0
+    }
+
+    /// Returns true if the thread is closed to replies.
+    ///
+    /// Always false unless the catalog was fetched with [`Catalog::full`].
+    pub fn closed(&self) -> bool {
+        self.closed != 0
+    }
+
+    /// Returns true if the thread is archived.
+    ///
+    /// Always false unless the catalog was fetched with [`Catalog::full`].
+    pub fn archived(&self) -> bool {
+        self.archived != 0
+    }
+
     /// Convert a [`CatalogThread`] into a [`Thread`]
     ///
     /// # Errors
@@ -0,0 +1,49 @@
+//! The sleeps behind the request cooldowns.
+//!
+//! Every wait of the crate, such as the 1 second between requests or the 10 seconds
+//! between updates, goes through the [`Timer`] of the client.
+//! The default is [`TokioTimer`]. With the `async-std` feature, [`AsyncStdTimer`]
+//! lets the cooldowns run on an async-std executor instead.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, timer::TokioTimer};
+//! # async fn timer() {
+//! let client = Client::new();
+//! client.lock().await.set_timer(TokioTimer);
+//! # }
+//! ```
+
+use futures::future::BoxFuture;
+use std::{fmt::Debug, time::Duration};
+
+/// Waits for a duration on some async runtime.
+pub trait Timer: Debug + Send + Sync {
+    /// Returns a future which completes after `duration`.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// A [`Timer`] on the tokio runtime.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TokioTimer;
+
+impl Timer for TokioTimer {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// A [`Timer`] on the async-std runtime.
+///
+/// Requires the `async-std` feature.
+#[cfg(feature = "async-std")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct AsyncStdTimer;
+
+#[cfg(feature = "async-std")]
+impl Timer for AsyncStdTimer {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(async_std::task::sleep(duration))
+    }
+}
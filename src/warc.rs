@@ -0,0 +1,145 @@
+//! Recording of fetched responses into a [WARC](<https://iipc.github.io/warc-specifications/specifications/warc-format/warc-1.1/>) file.
+//!
+//! Requires the `warc` feature.
+//!
+//! Once recording is enabled on a [`Client`](crate::Client),
+//! every response it receives is written as a WARC `response` record,
+//! with its status line, headers and body exactly as received.
+//!
+//! # Example
+//!
+//! ```
+//! # use dot4ch::{Client, thread::Thread};
+//! # async fn warc() -> anyhow::Result<()> {
+//! let client = Client::new();
+//! client.lock().await.record_warc("g.warc")?;
+//!
+//! // This request and any of its updates will end up in `g.warc`
+//! let thread = Thread::new(&client, "g", 76759434).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::logging::error;
+use chrono::{SecondsFormat, Utc};
+use reqwest::{header::HeaderMap, Response, ResponseBuilderExt, StatusCode, Url, Version};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// Writes WARC records to a file.
+#[derive(Debug)]
+pub struct WarcWriter {
+    /// The WARC file
+    file: BufWriter<File>,
+}
+
+impl WarcWriter {
+    /// Opens a WARC file for appending and writes a `warcinfo` record to it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file cannot be opened or written to.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut writer = Self {
+            file: BufWriter::new(file),
+        };
+        let info = format!(
+            "software: {}/{}\r\nformat: WARC File Format 1.1\r\n",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        );
+        writer.write_record("warcinfo", None, "application/warc-fields", info.as_bytes())?;
+        Ok(writer)
+    }
+
+    /// Writes a `response` record for a response which has already been read.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file cannot be written to.
+    pub fn write_response(
+        &mut self,
+        url: &Url,
+        version: Version,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> io::Result<()> {
+        let mut block = format!(
+            "{:?} {} {}\r\n",
+            version,
+            status.as_u16(),
+            status.canonical_reason().unwrap_or_default()
+        )
+        .into_bytes();
+        for (name, value) in headers {
+            block.extend_from_slice(name.as_str().as_bytes());
+            block.extend_from_slice(b": ");
+            block.extend_from_slice(value.as_bytes());
+            block.extend_from_slice(b"\r\n");
+        }
+        block.extend_from_slice(b"\r\n");
+        block.extend_from_slice(body);
+        self.write_record(
+            "response",
+            Some(url.as_str()),
+            "application/http;msgtype=response",
+            &block,
+        )
+    }
+
+    /// Writes a single WARC record and flushes it to disk.
+    fn write_record(
+        &mut self,
+        kind: &str,
+        target: Option<&str>,
+        content_type: &str,
+        block: &[u8],
+    ) -> io::Result<()> {
+        let date = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        write!(
+            self.file,
+            "WARC/1.1\r\nWARC-Type: {kind}\r\nWARC-Record-ID: <urn:uuid:{}>\r\nWARC-Date: {date}\r\n",
+            uuid::Uuid::new_v4()
+        )?;
+        if let Some(target) = target {
+            write!(self.file, "WARC-Target-URI: {target}\r\n")?;
+        }
+        write!(
+            self.file,
+            "Content-Type: {content_type}\r\nContent-Length: {}\r\n\r\n",
+            block.len()
+        )?;
+        self.file.write_all(block)?;
+        self.file.write_all(b"\r\n\r\n")?;
+        self.file.flush()
+    }
+
+    /// Reads the whole response, records it and returns an identical response.
+    ///
+    /// Failing to write the record is logged and does not fail the request.
+    pub(crate) async fn record(&mut self, response: Response) -> reqwest::Result<Response> {
+        let url = response.url().clone();
+        let version = response.version();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+
+        if let Err(err) = self.write_response(&url, version, status, &headers, &body) {
+            error!("Could not write WARC record for {url}: {err}");
+        }
+
+        let mut rebuilt = http::Response::builder()
+            .url(url)
+            .body(body)
+            .unwrap_or_default();
+        *rebuilt.version_mut() = version;
+        *rebuilt.status_mut() = status;
+        *rebuilt.headers_mut() = headers;
+        Ok(Response::from(rebuilt))
+    }
+}